@@ -0,0 +1,68 @@
+use std::f64::consts;
+
+/// The golden ratio, (1 + sqrt(5)) / 2.
+const PHI: f64 = 1.618_033_988_749_895;
+
+/// Built-in mathematical constants. Names are matched case-insensitively, so `PI`, `Pi` and `pi`
+/// are all the same constant.
+const CONSTANTS: &[(&str, f64)] = &[
+    ("pi", consts::PI),
+    ("π", consts::PI),
+    ("e", consts::E),
+    ("tau", consts::TAU),
+    ("τ", consts::TAU),
+    ("phi", PHI),
+    ("φ", PHI),
+    ("inf", f64::INFINITY),
+    ("infinity", f64::INFINITY),
+    ("nan", f64::NAN),
+];
+
+/// The name of the imaginary unit, which is only a constant in complex mode. Unlike the other
+/// constants it is case-sensitive, so `I` is still an ordinary variable.
+pub const IMAGINARY_UNIT: &str = "i";
+
+/// Looks up the value of a constant by name, ignoring case.
+///
+/// # Example
+/// ```
+/// # use argon::constants;
+/// assert_eq!(constants::lookup("PI"), Some(std::f64::consts::PI));
+/// assert_eq!(constants::lookup("speed"), None);
+/// ```
+pub fn lookup(name: &str) -> Option<f64> {
+    let name = name.to_lowercase();
+    CONSTANTS
+        .iter()
+        .find(|(constant, _)| *constant == name)
+        .map(|(_, value)| *value)
+}
+
+/// Returns true if the name refers to a constant, which means it cannot be assigned to.
+pub fn is_constant(name: &str) -> bool {
+    lookup(name).is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_is_case_insensitive() {
+        assert_eq!(lookup("pi"), Some(consts::PI));
+        assert_eq!(lookup("PI"), Some(consts::PI));
+        assert_eq!(lookup("Tau"), Some(consts::TAU));
+        assert_eq!(lookup("E"), Some(consts::E));
+        assert_eq!(lookup("INF"), Some(f64::INFINITY));
+        assert!(lookup("NaN").unwrap().is_nan());
+    }
+
+    #[test]
+    fn test_lookup_matches_whole_names() {
+        assert_eq!(lookup("SPEED"), None);
+        assert_eq!(lookup("PIPE"), None);
+        assert_eq!(lookup("ex"), None);
+        assert!(!is_constant("x"));
+        assert!(is_constant("phi"));
+    }
+}
@@ -0,0 +1,738 @@
+//! Decimal floating point numbers with a chosen number of significant digits, used for numbers
+//! when a precision is set with `precision N`.
+//!
+//! A [`Decimal`] is an integer mantissa times a power of ten, rounded to its precision after
+//! every operation. Functions such as [`Decimal::sqrt`] and [`Decimal::exp`] work with a few
+//! extra digits and round the result, so every digit they return is correct.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+use num_bigint::BigInt;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+
+use crate::rational::{self, BigRational};
+
+/// The most significant digits a decimal can have.
+pub const MAX_PRECISION: usize = 1000;
+
+/// The significant digits decimals have when `mode decimal` is used without `precision N`.
+pub const DEFAULT_PRECISION: usize = 50;
+
+/// The extra digits functions work with, so rounding errors don't reach the digits they return.
+const GUARD_DIGITS: usize = 10;
+
+/// The largest result of [`Decimal::exp`] and [`Decimal::pow`], as a power of ten. Larger results
+/// are calculated with floating point numbers instead, which gives infinity.
+const MAX_EXPONENT: f64 = 1e15;
+
+/// A decimal floating point number, `mantissa * 10^exponent`, with `precision` significant
+/// digits.
+///
+/// # Example
+/// ```
+/// # use argon::decimal::Decimal;
+/// let two = Decimal::from_f64(2.0, 30).unwrap();
+/// assert_eq!(two.sqrt().unwrap().to_string(), "1.41421356237309504880168872421");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Decimal {
+    mantissa: BigInt,
+    exponent: i64,
+    precision: usize,
+}
+
+/// Returns 10 raised to the power `n`.
+fn pow10(n: usize) -> BigInt {
+    BigInt::from(10).pow(n as u32)
+}
+
+/// Returns the number of decimal digits in `n`.
+fn digit_count(n: &BigInt) -> usize {
+    if n.is_zero() {
+        1
+    } else {
+        n.abs().to_string().len()
+    }
+}
+
+impl Decimal {
+    /// Creates the decimal `mantissa * 10^exponent`, rounded to `precision` significant digits.
+    fn new(mantissa: BigInt, exponent: i64, precision: usize) -> Self {
+        let mut decimal = Decimal {
+            mantissa,
+            exponent,
+            precision: precision.max(1),
+        };
+        decimal.round();
+        decimal
+    }
+
+    /// Rounds the mantissa to the precision, halves away from zero, and removes trailing zeros so
+    /// every number has one representation.
+    fn round(&mut self) {
+        let count = digit_count(&self.mantissa);
+        if count > self.precision {
+            let dropped = count - self.precision;
+            let divisor = pow10(dropped);
+            let remainder = &self.mantissa % &divisor;
+            self.mantissa = &self.mantissa / &divisor;
+            if remainder.abs() * 2 >= divisor {
+                self.mantissa += remainder.signum();
+            }
+            self.exponent += dropped as i64;
+        }
+
+        if self.mantissa.is_zero() {
+            self.exponent = 0;
+            return;
+        }
+        let ten = BigInt::from(10);
+        while (&self.mantissa % &ten).is_zero() {
+            self.mantissa /= &ten;
+            self.exponent += 1;
+        }
+    }
+
+    /// Creates a decimal from an integer, rounded to `precision` significant digits.
+    pub fn from_integer(n: BigInt, precision: usize) -> Self {
+        Decimal::new(n, 0, precision)
+    }
+
+    /// Creates the decimal closest to a fraction.
+    pub fn from_rational(r: &BigRational, precision: usize) -> Self {
+        quotient(r.numer(), r.denom(), 0, precision)
+    }
+
+    /// Converts a floating point number to the decimal it is written as, so `0.1` is exactly
+    /// `0.1`. Returns `None` for infinity and `NaN`.
+    pub fn from_f64(n: f64, precision: usize) -> Option<Self> {
+        rational::from_f64(n).map(|r| Decimal::from_rational(&r, precision))
+    }
+
+    /// Converts the decimal to the closest floating point number.
+    pub fn to_f64(&self) -> f64 {
+        format!("{}e{}", self.mantissa, self.exponent)
+            .parse()
+            .unwrap_or(f64::NAN)
+    }
+
+    /// Converts the decimal to the fraction it is exactly equal to.
+    pub fn to_rational(&self) -> BigRational {
+        let scale = pow10(self.exponent.unsigned_abs() as usize);
+        if self.exponent >= 0 {
+            BigRational::from_integer(&self.mantissa * scale)
+        } else {
+            BigRational::new(self.mantissa.clone(), scale)
+        }
+    }
+
+    /// Returns the number of significant digits the decimal is rounded to.
+    pub fn precision(&self) -> usize {
+        self.precision
+    }
+
+    /// Returns the decimal rounded to `precision` significant digits. A larger precision keeps
+    /// the same value, which is useful for calculating with extra digits.
+    pub fn with_precision(&self, precision: usize) -> Self {
+        Decimal::new(self.mantissa.clone(), self.exponent, precision)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mantissa.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.mantissa.is_negative()
+    }
+
+    pub fn abs(&self) -> Self {
+        Decimal {
+            mantissa: self.mantissa.abs(),
+            ..self.clone()
+        }
+    }
+
+    /// Returns the power of ten just above the decimal's leading digit, so it is 1 for numbers
+    /// from 1 up to 10 and -2 for numbers from 0.001 up to 0.01.
+    fn magnitude(&self) -> i64 {
+        self.exponent + digit_count(&self.mantissa) as i64
+    }
+
+    /// Returns true if adding `self` to `sum` wouldn't change any of the `digits` leading digits
+    /// of `sum`, which is when a series can stop.
+    fn is_negligible(&self, sum: &Decimal, digits: usize) -> bool {
+        self.is_zero() || self.magnitude() < sum.magnitude() - digits as i64 - 1
+    }
+
+    /// Returns the whole number closest to the decimal.
+    fn round_to_integer(&self) -> BigInt {
+        let r = self.to_rational();
+        r.round().to_integer()
+    }
+
+    /// Divides by `other`, returning `None` if it is zero.
+    pub fn checked_div(&self, other: &Decimal) -> Option<Self> {
+        if other.is_zero() {
+            return None;
+        }
+        Some(quotient(
+            &self.mantissa,
+            &other.mantissa,
+            self.exponent - other.exponent,
+            self.precision.max(other.precision),
+        ))
+    }
+
+    /// Returns the remainder of dividing by `other`, which has the same sign as `self` like `%`
+    /// on floating point numbers. Returns `None` if `other` is zero.
+    pub fn remainder(&self, other: &Decimal) -> Option<Self> {
+        let precision = self.precision.max(other.precision);
+        rational::remainder(&self.to_rational(), &other.to_rational())
+            .map(|r| Decimal::from_rational(&r, precision))
+    }
+
+    /// Calculates the square root, or `None` for negative numbers.
+    pub fn sqrt(&self) -> Option<Self> {
+        if self.is_negative() {
+            return None;
+        }
+        if self.is_zero() {
+            return Some(self.clone());
+        }
+
+        // Scale the mantissa by an even power of ten until its root has enough digits
+        let wanted = 2 * (self.precision + 2);
+        let mut shift = wanted.saturating_sub(digit_count(&self.mantissa)) as i64;
+        if (self.exponent - shift) % 2 != 0 {
+            shift += 1;
+        }
+        let n = &self.mantissa * pow10(shift as usize);
+        let root = n.sqrt();
+        let exponent = (self.exponent - shift) / 2;
+
+        // An inexact root is nudged up by a final digit, so it still rounds the right way
+        if &root * &root == n {
+            Some(Decimal::new(root, exponent, self.precision))
+        } else {
+            Some(Decimal::new(root * 10 + 1, exponent - 1, self.precision))
+        }
+    }
+
+    /// Calculates e raised to the power of the decimal. Returns `None` if the result is too
+    /// large or small to hold.
+    pub fn exp(&self) -> Option<Self> {
+        let x = self.to_f64();
+        if x.abs() * std::f64::consts::LOG10_E > MAX_EXPONENT {
+            return None;
+        }
+
+        // exp(x) = exp(x / 2^k)^(2^k), where the series for exp(x / 2^k) converges quickly.
+        // Each squaring doubles the rounding error, so a few more digits are needed.
+        let halvings = x.abs().log2().max(0.0) as usize + 8;
+        let working = self.precision + GUARD_DIGITS + halvings * 3 / 10 + 1;
+        let divisor = Decimal::from_integer(BigInt::from(2).pow(halvings as u32), working);
+        let r = self.with_precision(working).checked_div(&divisor)?;
+
+        let mut result = series(Decimal::one(working), working, |term, n| {
+            (term * &r).checked_div(&Decimal::from_integer(n.into(), working))
+        });
+        for _ in 0..halvings {
+            result = &result * &result;
+        }
+        Some(result.with_precision(self.precision))
+    }
+
+    /// Calculates the natural logarithm, or `None` for numbers that aren't positive.
+    pub fn ln(&self) -> Option<Self> {
+        if self.is_negative() || self.is_zero() {
+            return None;
+        }
+        let working = self.precision + GUARD_DIGITS;
+        let x = self.with_precision(working);
+
+        // Close to 1 the series is used directly, so small results keep all their digits
+        let half = Decimal::from_f64(0.5, working)?;
+        let two = Decimal::from_integer(2.into(), working);
+        if x >= half && x < two {
+            return Some(ln_series(&x, working)?.with_precision(self.precision));
+        }
+
+        // Otherwise x = y * 10^d * 2^j, where y is between 1 and 2
+        let d = x.magnitude() - 1;
+        let y = Decimal::new(x.mantissa.clone(), x.exponent - d, working);
+        let j = y.to_f64().log2().floor() as u32;
+        let y = y.checked_div(&Decimal::from_integer(BigInt::from(2).pow(j), working))?;
+
+        let ln2 = ln_series(&two, working)?;
+        let ln10 = &(&ln2 * &Decimal::from_integer(3.into(), working))
+            + &ln_series(&Decimal::from_f64(1.25, working)?, working)?;
+        let result = &(&ln_series(&y, working)?
+            + &(&ln2 * &Decimal::from_integer(j.into(), working)))
+            + &(&ln10 * &Decimal::from_integer(d.into(), working));
+        Some(result.with_precision(self.precision))
+    }
+
+    /// Calculates the logarithm in `base`. Returns `None` if either number isn't positive or the
+    /// base is 1.
+    pub fn log(&self, base: &Decimal) -> Option<Self> {
+        let precision = self.precision.max(base.precision);
+        let working = precision + GUARD_DIGITS;
+        let ln = self.with_precision(working).ln()?;
+        let ln_base = base.with_precision(working).ln()?;
+        Some(ln.checked_div(&ln_base)?.with_precision(precision))
+    }
+
+    /// Raises the decimal to the power `exponent`. Whole powers are calculated by repeated
+    /// multiplication and other powers with `exp` and `ln`. Returns `None` if the result isn't a
+    /// real number or is too large or small to hold.
+    pub fn pow(&self, exponent: &Decimal) -> Option<Self> {
+        let precision = self.precision.max(exponent.precision);
+        let estimate = exponent.to_f64() * self.to_f64().abs().log10();
+        if estimate.abs() > MAX_EXPONENT {
+            return None;
+        }
+
+        if exponent.exponent >= 0 {
+            let power = exponent.round_to_integer();
+            let working = precision + GUARD_DIGITS + digit_count(&power);
+            let mut base = self.with_precision(working);
+            let mut result = Decimal::one(working);
+            let mut remaining = power.abs().to_u64()?;
+            while remaining > 0 {
+                if remaining % 2 == 1 {
+                    result = &result * &base;
+                }
+                base = &base * &base;
+                remaining /= 2;
+            }
+            if power.is_negative() {
+                result = Decimal::one(working).checked_div(&result)?;
+            }
+            return Some(result.with_precision(precision));
+        }
+
+        if self.is_zero() && !exponent.is_negative() {
+            return Some(self.with_precision(precision));
+        }
+        let working = precision + GUARD_DIGITS;
+        let product = &self.with_precision(working).ln()? * &exponent.with_precision(working);
+        Some(product.exp()?.with_precision(precision))
+    }
+
+    /// Returns pi to `precision` significant digits.
+    pub fn pi(precision: usize) -> Self {
+        // Machin's formula, pi = 16 atan(1/5) - 4 atan(1/239)
+        let working = precision + GUARD_DIGITS;
+        let atan_recip = |n: i64| {
+            let x = Decimal::one(working)
+                .checked_div(&Decimal::from_integer(n.into(), working))
+                .unwrap();
+            atan_series(&x, working)
+        };
+        let pi = &(&atan_recip(5) * &Decimal::from_integer(16.into(), working))
+            - &(&atan_recip(239) * &Decimal::from_integer(4.into(), working));
+        pi.with_precision(precision)
+    }
+
+    /// Returns the value of the constant called `name`, such as `pi` or `e`, if it is a real
+    /// number with as many digits as needed. Names are matched case-insensitively.
+    pub fn constant(name: &str, precision: usize) -> Option<Self> {
+        let working = precision + GUARD_DIGITS;
+        let constant = match name.to_lowercase().as_str() {
+            "pi" | "π" => Decimal::pi(working),
+            "tau" | "τ" => &Decimal::pi(working) * &Decimal::from_integer(2.into(), working),
+            "e" => Decimal::one(working).exp()?,
+            "phi" | "φ" => {
+                let five = Decimal::from_integer(5.into(), working);
+                (&Decimal::one(working) + &five.sqrt()?)
+                    .checked_div(&Decimal::from_integer(2.into(), working))?
+            }
+            _ => return None,
+        };
+        Some(constant.with_precision(precision))
+    }
+
+    /// Calculates the sine of an angle in radians. Returns `None` for angles so large that
+    /// reducing them to one turn would need too many digits.
+    pub fn sin(&self) -> Option<Self> {
+        let (r, working) = self.reduce_angle()?;
+        let r2 = &r * &r;
+        let sin = series(r.clone(), working, |term, n| {
+            let divisor = Decimal::from_integer((2 * n * (2 * n + 1)).into(), working);
+            (-&(term * &r2)).checked_div(&divisor)
+        });
+        Some(sin.with_precision(self.precision))
+    }
+
+    /// Calculates the cosine of an angle in radians.
+    pub fn cos(&self) -> Option<Self> {
+        let (r, working) = self.reduce_angle()?;
+        let r2 = &r * &r;
+        let cos = series(Decimal::one(working), working, |term, n| {
+            let divisor = Decimal::from_integer(((2 * n - 1) * (2 * n)).into(), working);
+            (-&(term * &r2)).checked_div(&divisor)
+        });
+        Some(cos.with_precision(self.precision))
+    }
+
+    /// Calculates the tangent of an angle in radians.
+    pub fn tan(&self) -> Option<Self> {
+        let working = self.with_precision(self.precision + GUARD_DIGITS);
+        let tan = working.sin()?.checked_div(&working.cos()?)?;
+        Some(tan.with_precision(self.precision))
+    }
+
+    /// Calculates the arctangent in radians.
+    pub fn atan(&self) -> Option<Self> {
+        let working = self.precision + GUARD_DIGITS;
+        let mut x = self.with_precision(working);
+        let one = Decimal::one(working);
+
+        // atan(x) = pi/2 - atan(1/x) for positive x, so the series only sees |x| <= 1
+        if x.abs() > one {
+            let half_pi =
+                Decimal::pi(working).checked_div(&Decimal::from_integer(2.into(), working))?;
+            let half_pi = if x.is_negative() { -&half_pi } else { half_pi };
+            let result = &half_pi - &one.checked_div(&x)?.atan()?;
+            return Some(result.with_precision(self.precision));
+        }
+
+        // atan(x) = 2 atan(x / (1 + sqrt(1 + x^2))), which makes the series converge faster
+        let halvings = 3;
+        for _ in 0..halvings {
+            let root = (&one + &(&x * &x)).sqrt()?;
+            x = x.checked_div(&(&one + &root))?;
+        }
+        let scale = Decimal::from_integer((1 << halvings).into(), working);
+        Some((&atan_series(&x, working) * &scale).with_precision(self.precision))
+    }
+
+    /// Calculates the arcsine in radians, or `None` for numbers outside -1 to 1.
+    pub fn asin(&self) -> Option<Self> {
+        let working = self.precision + GUARD_DIGITS;
+        let x = self.with_precision(working);
+        let one = Decimal::one(working);
+        match x.abs().partial_cmp(&one)? {
+            Ordering::Greater => None,
+            Ordering::Equal => {
+                let half_pi =
+                    Decimal::pi(working).checked_div(&Decimal::from_integer(2.into(), working))?;
+                let result = if x.is_negative() { -&half_pi } else { half_pi };
+                Some(result.with_precision(self.precision))
+            }
+            Ordering::Less => {
+                let cos = (&one - &(&x * &x)).sqrt()?;
+                Some(x.checked_div(&cos)?.atan()?.with_precision(self.precision))
+            }
+        }
+    }
+
+    /// Calculates the arccosine in radians, or `None` for numbers outside -1 to 1.
+    pub fn acos(&self) -> Option<Self> {
+        let working = self.precision + GUARD_DIGITS;
+        let half_pi =
+            Decimal::pi(working).checked_div(&Decimal::from_integer(2.into(), working))?;
+        let acos = &half_pi - &self.with_precision(working).asin()?;
+        Some(acos.with_precision(self.precision))
+    }
+
+    /// Reduces an angle to between -pi and pi, returning it with the number of digits it has.
+    fn reduce_angle(&self) -> Option<(Decimal, usize)> {
+        let extra = self.magnitude().max(0) as usize;
+        if extra > MAX_PRECISION * 10 {
+            return None;
+        }
+        let working = self.precision + GUARD_DIGITS + extra;
+        let x = self.with_precision(working);
+        let turn = &Decimal::pi(working) * &Decimal::from_integer(2.into(), working);
+        let turns = x.checked_div(&turn)?.round_to_integer();
+        let reduced = &x - &(&turn * &Decimal::from_integer(turns, working));
+        let working = self.precision + GUARD_DIGITS;
+        Some((reduced.with_precision(working), working))
+    }
+
+    fn one(precision: usize) -> Self {
+        Decimal::from_integer(BigInt::one(), precision)
+    }
+}
+
+/// Calculates `a / b * 10^exponent` to `precision` significant digits.
+fn quotient(a: &BigInt, b: &BigInt, exponent: i64, precision: usize) -> Decimal {
+    // Scale `a` so the quotient has a digit more than the precision
+    let shift = (precision + 2 + digit_count(b)).saturating_sub(digit_count(a));
+    let n = a * pow10(shift);
+    let q = &n / b;
+    let exponent = exponent - shift as i64;
+
+    // An inexact quotient is nudged away from zero by a final digit, so it rounds the right way
+    if (&n % b).is_zero() {
+        Decimal::new(q, exponent, precision)
+    } else {
+        let nudge = if n.is_negative() != b.is_negative() {
+            -1
+        } else {
+            1
+        };
+        Decimal::new(q * 10 + nudge, exponent - 1, precision)
+    }
+}
+
+/// Adds up a series starting at `first`, where `next` calculates term `n` from term `n - 1`,
+/// until the terms are too small to change `digits` significant digits.
+fn series<F>(first: Decimal, digits: usize, mut next: F) -> Decimal
+where
+    F: FnMut(&Decimal, usize) -> Option<Decimal>,
+{
+    let mut sum = first.clone();
+    let mut term = first;
+    for n in 1.. {
+        term = match next(&term, n) {
+            Some(term) => term,
+            None => break,
+        };
+        if term.is_negligible(&sum, digits) {
+            break;
+        }
+        sum = &sum + &term;
+    }
+    sum
+}
+
+/// Calculates atan(x) = x - x^3/3 + x^5/5 - ..., which converges for |x| <= 1.
+fn atan_series(x: &Decimal, digits: usize) -> Decimal {
+    alternating_odd_series(x, digits, true)
+}
+
+/// Calculates ln(x) = 2 atanh((x - 1) / (x + 1)), where atanh(z) = z + z^3/3 + z^5/5 + ...
+fn ln_series(x: &Decimal, digits: usize) -> Option<Decimal> {
+    let one = Decimal::one(digits);
+    let z = (x - &one).checked_div(&(x + &one))?;
+    Some(&alternating_odd_series(&z, digits, false) * &Decimal::from_integer(2.into(), digits))
+}
+
+/// Calculates x + s x^3/3 + x^5/5 + s x^7/7 + ..., where `s` is -1 if `alternate` is true and 1
+/// otherwise.
+fn alternating_odd_series(x: &Decimal, digits: usize, alternate: bool) -> Decimal {
+    let x2 = x * x;
+    let x2 = if alternate { -&x2 } else { x2 };
+    let mut power = x.clone();
+    let mut sum = x.clone();
+    for k in 1.. {
+        power = &power * &x2;
+        let term = power
+            .checked_div(&Decimal::from_integer((2 * k + 1).into(), digits))
+            .unwrap();
+        if term.is_negligible(&sum, digits) {
+            break;
+        }
+        sum = &sum + &term;
+    }
+    sum
+}
+
+impl Add for &Decimal {
+    type Output = Decimal;
+
+    fn add(self, other: &Decimal) -> Decimal {
+        let precision = self.precision.max(other.precision);
+        if self.is_zero() {
+            return other.with_precision(precision);
+        }
+        if other.is_zero() {
+            return self.with_precision(precision);
+        }
+        // A number too small to change any digit of the sum is left out, rather than scaling
+        // the larger number by a huge power of ten
+        let gap = self.magnitude() - other.magnitude();
+        if gap > precision as i64 + 2 {
+            return self.with_precision(precision);
+        }
+        if -gap > precision as i64 + 2 {
+            return other.with_precision(precision);
+        }
+
+        let exponent = self.exponent.min(other.exponent);
+        let scale = |d: &Decimal| &d.mantissa * pow10((d.exponent - exponent) as usize);
+        Decimal::new(scale(self) + scale(other), exponent, precision)
+    }
+}
+
+impl Sub for &Decimal {
+    type Output = Decimal;
+
+    fn sub(self, other: &Decimal) -> Decimal {
+        self + &-other
+    }
+}
+
+impl Mul for &Decimal {
+    type Output = Decimal;
+
+    fn mul(self, other: &Decimal) -> Decimal {
+        Decimal::new(
+            &self.mantissa * &other.mantissa,
+            self.exponent + other.exponent,
+            self.precision.max(other.precision),
+        )
+    }
+}
+
+impl Neg for &Decimal {
+    type Output = Decimal;
+
+    fn neg(self) -> Decimal {
+        Decimal {
+            mantissa: -&self.mantissa,
+            ..self.clone()
+        }
+    }
+}
+
+impl PartialEq for Decimal {
+    /// Compares the values of two decimals, whatever their precisions are.
+    fn eq(&self, other: &Self) -> bool {
+        self.mantissa == other.mantissa && self.exponent == other.exponent
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.to_rational().cmp(&other.to_rational()))
+    }
+}
+
+impl fmt::Display for Decimal {
+    /// Formats the decimal with its significant digits, in scientific notation if it is very
+    /// large or small, such as `1.5e-30`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        if self.is_negative() {
+            write!(f, "-")?;
+        }
+        let digits = self.mantissa.abs().to_string();
+        // The number of digits before the decimal point
+        let point = self.magnitude();
+
+        if self.exponent >= 0 && point <= self.precision as i64 {
+            write!(f, "{}{}", digits, "0".repeat(self.exponent as usize))
+        } else if point > 0 && self.exponent < 0 {
+            let (whole, fraction) = digits.split_at(point as usize);
+            write!(f, "{}.{}", whole, fraction)
+        } else if point > -6 && point <= 0 {
+            write!(f, "0.{}{}", "0".repeat(-point as usize), digits)
+        } else {
+            let (first, rest) = digits.split_at(1);
+            let point = if rest.is_empty() { "" } else { "." };
+            write!(f, "{}{}{}e{}", first, point, rest, self.magnitude() - 1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decimal(n: f64, precision: usize) -> Decimal {
+        Decimal::from_f64(n, precision).unwrap()
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let third = decimal(1.0, 20).checked_div(&decimal(3.0, 20)).unwrap();
+        assert_eq!(third.to_string(), "0.33333333333333333333");
+        assert_eq!(
+            (&third * &decimal(3.0, 20)).to_string(),
+            "0.99999999999999999999"
+        );
+        assert_eq!((&decimal(0.1, 20) + &decimal(0.2, 20)).to_string(), "0.3");
+        assert_eq!((&decimal(1.0, 5) - &decimal(1e-10, 5)).to_string(), "1");
+        assert_eq!(decimal(2.0, 5).checked_div(&decimal(0.0, 5)), None);
+        assert_eq!(
+            decimal(7.5, 10).remainder(&decimal(2.0, 10)),
+            Some(decimal(1.5, 10))
+        );
+        assert!(decimal(0.1, 10) < decimal(0.2, 5));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(decimal(1e20, 5).to_string(), "1e20");
+        assert_eq!(decimal(123456.0, 10).to_string(), "123456");
+        assert_eq!(decimal(-0.00125, 10).to_string(), "-0.00125");
+        assert_eq!(decimal(1.5e-30, 10).to_string(), "1.5e-30");
+        assert_eq!(decimal(2.0 / 3.0, 3).to_string(), "0.667");
+    }
+
+    #[test]
+    fn test_functions() {
+        let sqrt2 = decimal(2.0, 50).sqrt().unwrap();
+        assert_eq!(
+            sqrt2.to_string(),
+            "1.4142135623730950488016887242096980785696718753769"
+        );
+        assert_eq!(decimal(1.44, 10).sqrt().unwrap().to_string(), "1.2");
+        assert_eq!(decimal(-1.0, 10).sqrt(), None);
+
+        assert_eq!(
+            Decimal::pi(40).to_string(),
+            "3.141592653589793238462643383279502884197"
+        );
+        assert_eq!(
+            Decimal::constant("E", 30).unwrap().to_string(),
+            "2.71828182845904523536028747135"
+        );
+        assert_eq!(
+            decimal(10.0, 30).ln().unwrap().to_string(),
+            "2.30258509299404568401799145468"
+        );
+        assert_eq!(
+            decimal(1.000001, 10).ln().unwrap().to_string(),
+            "9.999995e-7"
+        );
+        assert_eq!(decimal(0.0, 10).ln(), None);
+        assert_eq!(
+            decimal(1000.0, 40).log(&decimal(10.0, 40)),
+            Some(decimal(3.0, 40))
+        );
+        assert_eq!(decimal(5.0, 10).log(&decimal(1.0, 10)), None);
+        assert_eq!(decimal(-20.0, 20).exp().unwrap().to_f64(), (-20f64).exp());
+        assert_eq!(
+            decimal(2.0, 10)
+                .pow(&decimal(10.0, 10))
+                .unwrap()
+                .to_string(),
+            "1024"
+        );
+        assert_eq!(
+            decimal(2.0, 20).pow(&decimal(0.5, 20)).unwrap(),
+            decimal(2.0, 20).sqrt().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_trigonometry() {
+        let precision = 30;
+        let sin = decimal(1.0, precision).sin().unwrap();
+        assert_eq!(sin.to_string(), "0.84147098480789650665250232163");
+        let cos = decimal(1.0, precision).cos().unwrap();
+        assert_eq!(cos.to_string(), "0.540302305868139717400936607443");
+        let atan = decimal(1.0, precision).atan().unwrap();
+        let quarter_pi = Decimal::pi(precision)
+            .checked_div(&decimal(4.0, precision))
+            .unwrap();
+        assert_eq!(atan, quarter_pi);
+        assert_eq!(
+            decimal(0.5, precision).asin().unwrap().to_string(),
+            "0.523598775598298873077107230547"
+        );
+        assert_eq!(decimal(2.0, precision).asin(), None);
+        assert_eq!(
+            decimal(1000.0, 20).sin().unwrap().to_string(),
+            "0.82687954053200256026"
+        );
+    }
+}
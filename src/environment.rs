@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+
+use num_complex::Complex64;
+
+use crate::{
+    constants, decimal,
+    decimal::Decimal,
+    error::{ArgonError, Result},
+    functions::{self, FunctionRegistry},
+    value::Value,
+};
+
+/// The most function calls that can be nested inside each other before evaluation is stopped,
+/// so a function that calls itself forever gives an error instead of overflowing the stack.
+pub const MAX_CALL_DEPTH: usize = 128;
+
+/// How numbers are evaluated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    /// Every number is real, so `sqrt(-1)` is `NaN`.
+    #[default]
+    Real,
+    /// `i` is the imaginary unit, and functions such as `sqrt` and `ln` give complex numbers for
+    /// arguments outside their real domain.
+    Complex,
+    /// Number literals and `+ - * /` give exact fractions, so `1/3 * 3` is exactly `1`. Functions
+    /// such as `sin` and `sqrt` give floating point numbers.
+    Exact,
+    /// Number literals, constants such as `pi`, arithmetic and functions such as `sqrt`, `exp`
+    /// and `sin` give decimals with this many significant digits.
+    Decimal(usize),
+}
+
+impl Mode {
+    /// Returns the mode called `name`, if there is one. `decimal` calculates with
+    /// [`decimal::DEFAULT_PRECISION`] significant digits, and `precision N` chooses another number.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "real" => Some(Mode::Real),
+            "complex" => Some(Mode::Complex),
+            "exact" => Some(Mode::Exact),
+            "decimal" => Some(Mode::Decimal(decimal::DEFAULT_PRECISION)),
+            _ => None,
+        }
+    }
+}
+
+/// What created a scope, which decides which other scopes can be seen from it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScopeKind {
+    /// The session's variables. There is always exactly one global scope.
+    Global,
+    /// The parameters of a call to the named user defined function.
+    Function(String),
+    /// The bindings of a `let ... in ...` expression.
+    Let,
+}
+
+#[derive(Debug, Clone)]
+struct Scope {
+    kind: ScopeKind,
+    variables: HashMap<String, Value>,
+}
+
+impl Scope {
+    fn new(kind: ScopeKind) -> Self {
+        Scope {
+            kind,
+            variables: HashMap::new(),
+        }
+    }
+}
+
+/// The variables and functions an equation is evaluated with.
+///
+/// Variables live in a stack of scopes. Looking up a name walks outward from the innermost scope,
+/// so inner bindings shadow outer ones. A function call can only see its own scopes and the
+/// global scope, not the local variables of whoever called it.
+///
+/// # Example
+/// ```
+/// # use argon::{environment::{Environment, ScopeKind}, Value};
+/// let mut environment = Environment::new();
+/// environment.define("x", Value::Number(1.0));
+///
+/// environment.push_scope(ScopeKind::Let).unwrap();
+/// environment.define("x", Value::Number(2.0));
+/// assert_eq!(environment.get("x"), Some(Value::Number(2.0)));
+///
+/// environment.pop_scope();
+/// assert_eq!(environment.get("x"), Some(Value::Number(1.0)));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Environment {
+    scopes: Vec<Scope>,
+    functions: FunctionRegistry,
+    call_depth: usize,
+    mode: Mode,
+}
+
+impl Default for Environment {
+    fn default() -> Self {
+        Environment::new()
+    }
+}
+
+impl Environment {
+    /// Creates an environment with the built-in functions and no variables.
+    pub fn new() -> Self {
+        Environment::with_functions(functions::load_functions())
+    }
+
+    /// Creates an environment with the given functions and no variables.
+    pub fn with_functions(functions: FunctionRegistry) -> Self {
+        Environment {
+            scopes: vec![Scope::new(ScopeKind::Global)],
+            functions,
+            call_depth: 0,
+            mode: Mode::default(),
+        }
+    }
+
+    /// Returns the value of a constant or variable, looking in the innermost scope first.
+    pub fn get(&self, name: &str) -> Option<Value> {
+        if let Some(value) = constants::lookup(name) {
+            if let Mode::Decimal(precision) = self.mode {
+                if let Some(constant) = Decimal::constant(name, precision) {
+                    return Some(constant.into());
+                }
+            }
+            return Some(Value::Number(value));
+        }
+        if self.mode == Mode::Complex && name == constants::IMAGINARY_UNIT {
+            return Some(Value::Complex(Complex64::i()));
+        }
+
+        for scope in self.scopes.iter().rev() {
+            if let Some(value) = scope.variables.get(name) {
+                return Some(value.clone());
+            }
+            // The caller's local variables are hidden from a function's body
+            if let ScopeKind::Function(_) = scope.kind {
+                break;
+            }
+        }
+        self.scopes[0].variables.get(name).cloned()
+    }
+
+    /// Returns true if `name` is a constant in the current mode, which means it cannot be
+    /// assigned to.
+    pub fn is_constant(&self, name: &str) -> bool {
+        constants::is_constant(name)
+            || (self.mode == Mode::Complex && name == constants::IMAGINARY_UNIT)
+    }
+
+    /// Returns how numbers are evaluated.
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
+
+    /// Changes how numbers are evaluated. Variables keep their values, so a complex number
+    /// stored in complex mode is still complex after switching back to real mode.
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+    }
+
+    /// Binds a variable in the innermost scope, shadowing any variable with the same name in the
+    /// scopes around it.
+    pub fn define(&mut self, name: &str, value: Value) {
+        self.innermost().variables.insert(name.to_string(), value);
+    }
+
+    /// Starts a new innermost scope. Returns an error if it is a function call that would nest
+    /// deeper than [`MAX_CALL_DEPTH`].
+    pub fn push_scope(&mut self, kind: ScopeKind) -> Result<()> {
+        if let ScopeKind::Function(name) = &kind {
+            if self.call_depth >= MAX_CALL_DEPTH {
+                return Err(ArgonError::RecursionLimit {
+                    name: name.clone(),
+                    span: None,
+                });
+            }
+            self.call_depth += 1;
+        }
+        self.scopes.push(Scope::new(kind));
+        Ok(())
+    }
+
+    /// Removes the innermost scope along with its variables. The global scope is never removed.
+    pub fn pop_scope(&mut self) {
+        if self.scopes.len() > 1 {
+            if let Some(Scope {
+                kind: ScopeKind::Function(_),
+                ..
+            }) = self.scopes.pop()
+            {
+                self.call_depth -= 1;
+            }
+        }
+    }
+
+    /// Returns the functions that can be called.
+    pub fn functions(&self) -> &FunctionRegistry {
+        &self.functions
+    }
+
+    /// Returns the functions that can be called, so new ones can be added.
+    pub fn functions_mut(&mut self) -> &mut FunctionRegistry {
+        &mut self.functions
+    }
+
+    fn innermost(&mut self) -> &mut Scope {
+        self.scopes
+            .last_mut()
+            .expect("the global scope is never removed")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_function_scope_hides_caller_locals() {
+        let mut environment = Environment::new();
+        environment.define("g", Value::Number(1.0));
+
+        environment.push_scope(ScopeKind::Let).unwrap();
+        environment.define("local", Value::Number(2.0));
+        environment
+            .push_scope(ScopeKind::Function("f".to_string()))
+            .unwrap();
+        environment.define("x", Value::Number(3.0));
+
+        assert_eq!(environment.get("x"), Some(Value::Number(3.0)));
+        assert_eq!(environment.get("g"), Some(Value::Number(1.0)));
+        assert_eq!(environment.get("local"), None);
+        assert_eq!(
+            environment.get("pi"),
+            Some(Value::Number(std::f64::consts::PI))
+        );
+
+        environment.pop_scope();
+        assert_eq!(environment.get("local"), Some(Value::Number(2.0)));
+        assert_eq!(environment.get("x"), None);
+    }
+
+    #[test]
+    fn test_imaginary_unit_only_in_complex_mode() {
+        let mut environment = Environment::new();
+        environment.define("i", Value::Number(1.0));
+        assert_eq!(environment.get("i"), Some(Value::Number(1.0)));
+        assert!(!environment.is_constant("i"));
+
+        environment.set_mode(Mode::Complex);
+        assert_eq!(environment.get("i"), Some(Value::Complex(Complex64::i())));
+        assert!(environment.is_constant("i"));
+        assert!(!environment.is_constant("I"));
+    }
+
+    #[test]
+    fn test_global_scope_is_never_popped() {
+        let mut environment = Environment::new();
+        environment.define("x", Value::Number(1.0));
+        environment.pop_scope();
+        assert_eq!(environment.get("x"), Some(Value::Number(1.0)));
+    }
+
+    #[test]
+    fn test_call_depth_limit() {
+        let mut environment = Environment::new();
+        for _ in 0..MAX_CALL_DEPTH {
+            environment
+                .push_scope(ScopeKind::Function("f".to_string()))
+                .unwrap();
+        }
+        // Let blocks don't count towards the limit
+        assert!(environment.push_scope(ScopeKind::Let).is_ok());
+        assert!(matches!(
+            environment.push_scope(ScopeKind::Function("f".to_string())),
+            Err(ArgonError::RecursionLimit { .. })
+        ));
+
+        environment.pop_scope();
+        environment.pop_scope();
+        assert!(environment
+            .push_scope(ScopeKind::Function("f".to_string()))
+            .is_ok());
+    }
+}
@@ -0,0 +1,333 @@
+use std::fmt;
+
+use crate::lexer::Span;
+
+/// Every error that can happen while lexing, parsing or evaluating an equation.
+///
+/// Errors that come from a specific part of the equation carry a `span` pointing at it. Errors
+/// raised inside built-in functions don't know where they were called from, so their span is
+/// filled in by the interpreter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgonError {
+    /// The equation contains a character or number literal that can't be turned into a token.
+    LexError { message: String, span: Option<Span> },
+    /// The tokens don't form a valid equation.
+    ParseError { message: String, span: Option<Span> },
+    /// A variable was used before being assigned.
+    UndefinedVariable { name: String, span: Option<Span> },
+    /// A function was called that isn't built-in or declared.
+    UnknownFunction { name: String, span: Option<Span> },
+    /// A function was called with the wrong number of arguments.
+    ArityMismatch {
+        name: String,
+        min: usize,
+        /// `None` if the function takes any number of arguments above `min`.
+        max: Option<usize>,
+        got: usize,
+        span: Option<Span>,
+    },
+    /// A function was given arguments it isn't defined for, such as `(-1)!`.
+    DomainError {
+        function: String,
+        message: String,
+        span: Option<Span>,
+    },
+    /// A constant was assigned to or used as a parameter name.
+    ConstantAssignment { name: String, span: Option<Span> },
+    /// A user defined function tried to replace a built-in one.
+    BuiltInRedefinition { name: String, span: Option<Span> },
+    /// A value had the wrong type for what was done with it, such as adding a list to a bool.
+    TypeError { message: String, span: Option<Span> },
+    /// Matrices of the wrong size were combined, such as multiplying a 2x3 matrix by a 2x3
+    /// matrix.
+    DimensionMismatch { message: String, span: Option<Span> },
+    /// A list or string was indexed past its end.
+    IndexOutOfRange {
+        index: i64,
+        len: usize,
+        span: Option<Span>,
+    },
+    /// An operator ended up somewhere the interpreter doesn't support it.
+    UnsupportedOperator {
+        operator: String,
+        span: Option<Span>,
+    },
+    /// Too many function calls were nested inside each other, usually because a function calls
+    /// itself without stopping.
+    RecursionLimit { name: String, span: Option<Span> },
+    /// An error raised while evaluating the body of a user defined function. The span points at
+    /// the call, since the body was written on another line.
+    InFunction {
+        name: String,
+        error: Box<ArgonError>,
+        span: Option<Span>,
+    },
+}
+
+/// A `Result` whose error is an [`ArgonError`].
+pub type Result<T> = std::result::Result<T, ArgonError>;
+
+impl ArgonError {
+    /// Returns the part of the equation this error points at, if it is known.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            ArgonError::LexError { span, .. }
+            | ArgonError::ParseError { span, .. }
+            | ArgonError::UndefinedVariable { span, .. }
+            | ArgonError::UnknownFunction { span, .. }
+            | ArgonError::ArityMismatch { span, .. }
+            | ArgonError::DomainError { span, .. }
+            | ArgonError::ConstantAssignment { span, .. }
+            | ArgonError::BuiltInRedefinition { span, .. }
+            | ArgonError::TypeError { span, .. }
+            | ArgonError::DimensionMismatch { span, .. }
+            | ArgonError::IndexOutOfRange { span, .. }
+            | ArgonError::UnsupportedOperator { span, .. }
+            | ArgonError::RecursionLimit { span, .. }
+            | ArgonError::InFunction { span, .. } => *span,
+        }
+    }
+
+    /// Points the error at `span` if it does not already point somewhere.
+    pub fn with_span(mut self, new_span: Span) -> Self {
+        match &mut self {
+            ArgonError::LexError { span, .. }
+            | ArgonError::ParseError { span, .. }
+            | ArgonError::UndefinedVariable { span, .. }
+            | ArgonError::UnknownFunction { span, .. }
+            | ArgonError::ArityMismatch { span, .. }
+            | ArgonError::DomainError { span, .. }
+            | ArgonError::ConstantAssignment { span, .. }
+            | ArgonError::BuiltInRedefinition { span, .. }
+            | ArgonError::TypeError { span, .. }
+            | ArgonError::DimensionMismatch { span, .. }
+            | ArgonError::IndexOutOfRange { span, .. }
+            | ArgonError::UnsupportedOperator { span, .. }
+            | ArgonError::RecursionLimit { span, .. }
+            | ArgonError::InFunction { span, .. } => {
+                span.get_or_insert(new_span);
+            }
+        }
+        self
+    }
+
+    /// Creates a [`ArgonError::LexError`] without a span.
+    pub fn lex(message: impl Into<String>) -> Self {
+        ArgonError::LexError {
+            message: message.into(),
+            span: None,
+        }
+    }
+
+    /// Creates a [`ArgonError::ParseError`] pointing at `span`.
+    pub fn parse(span: Span, message: impl Into<String>) -> Self {
+        ArgonError::ParseError {
+            message: message.into(),
+            span: Some(span),
+        }
+    }
+
+    /// Creates a [`ArgonError::TypeError`] without a span.
+    pub fn type_error(message: impl Into<String>) -> Self {
+        ArgonError::TypeError {
+            message: message.into(),
+            span: None,
+        }
+    }
+
+    /// Creates a [`ArgonError::DimensionMismatch`] without a span.
+    pub fn dimension(message: impl Into<String>) -> Self {
+        ArgonError::DimensionMismatch {
+            message: message.into(),
+            span: None,
+        }
+    }
+
+    /// Creates a [`ArgonError::DomainError`] for `function` without a span.
+    pub fn domain(function: impl Into<String>, message: impl Into<String>) -> Self {
+        ArgonError::DomainError {
+            function: function.into(),
+            message: message.into(),
+            span: None,
+        }
+    }
+}
+
+impl fmt::Display for ArgonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgonError::LexError { message, .. }
+            | ArgonError::ParseError { message, .. }
+            | ArgonError::TypeError { message, .. }
+            | ArgonError::DimensionMismatch { message, .. } => write!(f, "{}", message),
+            ArgonError::UndefinedVariable { name, .. } => {
+                write!(f, "undefined variable '{}'", name)
+            }
+            ArgonError::UnknownFunction { name, .. } => write!(f, "unknown function '{}'", name),
+            ArgonError::ArityMismatch {
+                name,
+                min,
+                max,
+                got,
+                ..
+            } => {
+                let (expected, count) = match max {
+                    Some(max) if max == min => (min.to_string(), *max),
+                    Some(max) => (format!("between {} and {}", min, max), *max),
+                    None => (format!("at least {}", min), *min),
+                };
+                let plural = if count == 1 { "" } else { "s" };
+                write!(
+                    f,
+                    "{} expects {} argument{}, got {}",
+                    name, expected, plural, got
+                )
+            }
+            ArgonError::DomainError {
+                function, message, ..
+            } => write!(f, "{} {}", function, message),
+            ArgonError::ConstantAssignment { name, .. } => {
+                write!(f, "cannot assign to constant '{}'", name)
+            }
+            ArgonError::BuiltInRedefinition { name, .. } => {
+                write!(f, "Cannot redefine built-in function '{}'", name)
+            }
+            ArgonError::IndexOutOfRange { index, len, .. } => write!(
+                f,
+                "index {} is out of range for a list of length {}",
+                index, len
+            ),
+            ArgonError::UnsupportedOperator { operator, .. } => {
+                write!(f, "Unexpected operator {}", operator)
+            }
+            ArgonError::RecursionLimit { name, .. } => {
+                write!(f, "maximum recursion depth exceeded in '{}'", name)
+            }
+            ArgonError::InFunction { name, error, .. } => write!(f, "{} (in '{}')", error, name),
+        }
+    }
+}
+
+impl std::error::Error for ArgonError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ArgonError::InFunction { error, .. } => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+/// Formats an error for the user. If the error points at part of the line, the line is printed
+/// with the offending part underlined, along with its line and column number.
+///
+/// # Example
+/// ```
+/// # use argon::{error, lexer};
+/// let error = error::ArgonError::UndefinedVariable {
+///     name: "foo".to_string(),
+///     span: Some(lexer::Span::new(4, 7)),
+/// };
+/// assert_eq!(
+///     error::report(&error, "x = foo + 1", 1),
+///     "Error: undefined variable 'foo' (line 1, column 5)\n 1 | x = foo + 1\n   |     ^~~"
+/// );
+/// ```
+pub fn report(error: &ArgonError, line: &str, line_number: usize) -> String {
+    let span = match error.span() {
+        Some(span) => span,
+        None => return format!("Error: {}", error),
+    };
+
+    let start = span.start.min(line.len());
+    let end = span.end.clamp(start, line.len());
+    let column = line[..start].chars().count() + 1;
+    let width = line[start..end].chars().count().max(1);
+
+    let gutter = " ".repeat(line_number.to_string().len());
+    let padding: String = line[..start]
+        .chars()
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+
+    format!(
+        "Error: {} (line {}, column {})\n {} | {}\n {} | {}^{}",
+        error,
+        line_number,
+        column,
+        line_number,
+        line,
+        gutter,
+        padding,
+        "~".repeat(width - 1)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let error = ArgonError::UndefinedVariable {
+            name: "foo".to_string(),
+            span: Some(Span::new(4, 7)),
+        };
+        assert_eq!(
+            report(&error, "x = foo + 1", 12),
+            "Error: undefined variable 'foo' (line 12, column 5)\n 12 | x = foo + 1\n    |     ^~~"
+        );
+
+        // Errors at the end of the line point just past it
+        let error = ArgonError::parse(Span::new(5, 5), "Unexpected end of input");
+        assert_eq!(
+            report(&error, "1 + 2", 1),
+            "Error: Unexpected end of input (line 1, column 6)\n 1 | 1 + 2\n   |      ^"
+        );
+
+        let error = ArgonError::domain("factorial", "is only defined for non-negative integers");
+        assert_eq!(
+            report(&error, "", 1),
+            "Error: factorial is only defined for non-negative integers"
+        );
+    }
+
+    #[test]
+    fn test_with_span_keeps_existing_span() {
+        let error = ArgonError::parse(Span::new(1, 2), "inner").with_span(Span::new(0, 5));
+        assert_eq!(error.span(), Some(Span::new(1, 2)));
+
+        let error = ArgonError::lex("outer").with_span(Span::new(0, 5));
+        assert_eq!(error.span(), Some(Span::new(0, 5)));
+    }
+
+    #[test]
+    fn test_display() {
+        let error = ArgonError::ArityMismatch {
+            name: "f".to_string(),
+            min: 1,
+            max: Some(1),
+            got: 2,
+            span: None,
+        };
+        assert_eq!(error.to_string(), "f expects 1 argument, got 2");
+
+        let error = ArgonError::ArityMismatch {
+            name: "max".to_string(),
+            min: 1,
+            max: None,
+            got: 0,
+            span: None,
+        };
+        assert_eq!(error.to_string(), "max expects at least 1 argument, got 0");
+
+        let error = ArgonError::InFunction {
+            name: "f".to_string(),
+            error: Box::new(ArgonError::UnknownFunction {
+                name: "g".to_string(),
+                span: None,
+            }),
+            span: None,
+        };
+        assert_eq!(error.to_string(), "unknown function 'g' (in 'f')");
+    }
+}
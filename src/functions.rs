@@ -1,50 +1,781 @@
-use anyhow::Result;
-use std::collections::HashMap;
-
-/// Type alias for calculator functions.
-pub type CalculatorFunction = fn(Vec<f64>) -> Result<f64>;
-
-/// Loads the calculator functions into a HashMap.
-///
-/// # Returns
-///
-/// A HashMap containing the calculator functions.
-pub fn load_functions() -> HashMap<String, CalculatorFunction> {
-    let mut functions: HashMap<String, CalculatorFunction> = HashMap::new();
-
-    functions.insert("sqrt".to_string(), sqrt);
-    functions.insert("sin".to_string(), sin);
-    functions.insert("cos".to_string(), cos);
-    functions.insert("tan".to_string(), tan);
-    functions.insert("asin".to_string(), asin);
-    functions.insert("acos".to_string(), acos);
-    functions.insert("atan".to_string(), atan);
-    functions.insert("sinh".to_string(), sinh);
-    functions.insert("cosh".to_string(), cosh);
-    functions.insert("tanh".to_string(), tanh);
-    functions.insert("asinh".to_string(), asinh);
-    functions.insert("acosh".to_string(), acosh);
-    functions.insert("atanh".to_string(), atanh);
-    functions.insert("floor".to_string(), floor);
-    functions.insert("ceil".to_string(), ceil);
-    functions.insert("round".to_string(), round);
-    functions.insert("recip".to_string(), recip);
-    functions.insert("abs".to_string(), abs);
-    functions.insert("rad2deg".to_string(), rad2deg);
-    functions.insert("deg2rad".to_string(), deg2rad);
-    functions.insert("ln".to_string(), ln);
-    functions.insert("log10".to_string(), log10);
-    functions.insert("log2".to_string(), log2);
-    functions.insert("log".to_string(), log);
-    functions.insert("factorial".to_string(), factorial);
-    functions.insert("pow".to_string(), pow);
-    functions.insert("max".to_string(), max);
-    functions.insert("min".to_string(), min);
-    functions.insert("sum".to_string(), sum);
-    functions.insert("avg".to_string(), avg);
-    functions.insert("median".to_string(), median);
-    functions.insert("root".to_string(), root);
-    functions.insert("exp".to_string(), exp);
+use std::{cmp::Ordering, collections::HashMap, fmt, sync::Arc};
+
+use num_complex::Complex64;
+use num_traits::{Signed, ToPrimitive};
+
+use crate::decimal::Decimal;
+use crate::error::{ArgonError, Result};
+use crate::integer::{self, BigInt};
+use crate::matrix::Matrix;
+use crate::number::{Elementary, Number};
+use crate::parser::Expr;
+use crate::rational::BigRational;
+use crate::value::Value;
+
+/// Type alias for calculator functions. Functions are stored as shared closures, so they can
+/// capture whatever state they need, such as a lookup table or calibration constants.
+pub type CalculatorFunction = Arc<dyn Fn(Vec<Value>) -> Result<Value> + Send + Sync>;
+
+/// Describes a built-in function: its name, the arguments it takes and what it does.
+///
+/// The interpreter checks the number of arguments against `min_args` and `max_args` before
+/// calling the function, so a function can index its arguments without checking them itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionInfo {
+    pub name: String,
+    pub min_args: usize,
+    /// `None` if the function takes any number of arguments.
+    pub max_args: Option<usize>,
+    pub params: Vec<String>,
+    pub description: String,
+}
+
+impl FunctionInfo {
+    /// Describes a function that takes exactly one argument for each of `params`.
+    pub fn new(name: &str, params: &[&str], description: &str) -> Self {
+        FunctionInfo {
+            name: name.to_string(),
+            min_args: params.len(),
+            max_args: Some(params.len()),
+            params: params.iter().map(|param| param.to_string()).collect(),
+            description: description.to_string(),
+        }
+    }
+
+    /// Lets the last parameters be left out, so the function can be called with as few as
+    /// `min_args` arguments.
+    pub fn with_min_args(mut self, min_args: usize) -> Self {
+        self.min_args = min_args;
+        self
+    }
+
+    /// Describes a function that takes at least `min_args` arguments, all called `param`.
+    pub fn variadic(name: &str, param: &str, min_args: usize, description: &str) -> Self {
+        FunctionInfo {
+            name: name.to_string(),
+            min_args,
+            max_args: None,
+            params: vec![param.to_string()],
+            description: description.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for FunctionInfo {
+    /// Formats the function's signature and description, such as `log(number, base): ...`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ellipsis = if self.max_args.is_none() { "..." } else { "" };
+        write!(
+            f,
+            "{}({}{}): {}",
+            self.name,
+            self.params.join(", "),
+            ellipsis,
+            self.description
+        )
+    }
+}
+
+/// A function that can be called from an equation.
+///
+/// `BuiltIn` functions are implemented in Rust and loaded by `load_functions`. Most of them are
+/// written once against the [`Elementary`] trait and work on every kind of number.
+///
+/// `UserDefined` functions are declared in the session with `f(x) = ...`. Their body is
+/// interpreted each time they are called, once the parameters are bound.
+#[derive(Clone)]
+pub enum Function {
+    BuiltIn {
+        info: FunctionInfo,
+        function: CalculatorFunction,
+    },
+    UserDefined {
+        params: Vec<String>,
+        body: Expr,
+    },
+}
+
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Function::BuiltIn { info, .. } => f.debug_tuple("BuiltIn").field(info).finish(),
+            Function::UserDefined { params, body } => f
+                .debug_struct("UserDefined")
+                .field("params", params)
+                .field("body", body)
+                .finish(),
+        }
+    }
+}
+
+impl Function {
+    /// Returns an error if the function can't be called with `count` arguments.
+    pub fn check_arity(&self, name: &str, count: usize) -> Result<()> {
+        let (min, max) = match self {
+            Function::BuiltIn { info, .. } => (info.min_args, info.max_args),
+            Function::UserDefined { params, .. } => (params.len(), Some(params.len())),
+        };
+
+        if count < min || max.is_some_and(|max| count > max) {
+            return Err(ArgonError::ArityMismatch {
+                name: name.to_string(),
+                min,
+                max,
+                got: count,
+                span: None,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// The functions that can be called from an equation, both built-in and user defined.
+///
+/// # Example
+/// ```
+/// # use argon::functions;
+/// let mut functions = functions::load_functions();
+/// let scale = 2.5;
+/// functions.register_numeric(
+///     functions::FunctionInfo::new("scaled", &["number"], "the number times the scale"),
+///     move |args| Ok(args[0] * scale),
+/// );
+/// assert!(functions.get("scaled").is_some());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FunctionRegistry {
+    functions: HashMap<String, Function>,
+}
+
+impl FunctionRegistry {
+    /// Creates a registry without any functions. Use `load_functions` to start with the built-in
+    /// ones.
+    pub fn new() -> Self {
+        FunctionRegistry::default()
+    }
+
+    /// Registers a built-in function under `info.name`, replacing any function with that name.
+    /// The interpreter checks the number of arguments against `info` before calling `function`.
+    pub fn register<F>(&mut self, info: FunctionInfo, function: F)
+    where
+        F: Fn(Vec<Value>) -> Result<Value> + Send + Sync + 'static,
+    {
+        self.functions.insert(
+            info.name.clone(),
+            Function::BuiltIn {
+                info,
+                function: Arc::new(function),
+            },
+        );
+    }
+
+    /// Registers a built-in function that only takes and returns numbers. Calling it with anything
+    /// other than numbers is a type error.
+    pub fn register_numeric<F>(&mut self, info: FunctionInfo, function: F)
+    where
+        F: Fn(Vec<f64>) -> Result<f64> + Send + Sync + 'static,
+    {
+        let name = info.name.clone();
+        self.register(info, move |args| {
+            function(numbers(&name, &args)?).map(Value::Number)
+        });
+    }
+
+    /// Adds a function under `name`, replacing any function with that name.
+    pub fn insert(&mut self, name: String, function: Function) {
+        self.functions.insert(name, function);
+    }
+
+    /// Returns the function called `name`, if there is one.
+    pub fn get(&self, name: &str) -> Option<&Function> {
+        self.functions.get(name)
+    }
+}
+
+/// Converts the arguments of the function called `name` to numbers, returning a type error if
+/// any of them is something else.
+pub fn numbers(name: &str, args: &[Value]) -> Result<Vec<f64>> {
+    args.iter()
+        .map(|arg| {
+            arg.as_number().ok_or_else(|| {
+                ArgonError::type_error(format!("{} expects numbers, got {}", name, arg.type_name()))
+            })
+        })
+        .collect()
+}
+
+/// Converts the arguments of the function called `name` to non-negative whole numbers. Large
+/// integers are used as they are, so `nCr(2^64, 2)` keeps every digit of `2^64`, while floating
+/// point numbers too large to be exact are a domain error.
+fn whole_numbers(name: &str, args: &[Value]) -> Result<Vec<BigInt>> {
+    args.iter()
+        .map(|arg| match arg {
+            Value::Integer(n) => whole_integer(name, &BigRational::from_integer((**n).clone())),
+            Value::Rational(r) => whole_integer(name, r),
+            arg => whole_number(name, numbers(name, std::slice::from_ref(arg))?[0]),
+        })
+        .collect()
+}
+
+/// Converts the arguments of the function called `name` to complex numbers, returning a type
+/// error if any of them is something other than a number.
+pub fn complex_numbers(name: &str, args: &[Value]) -> Result<Vec<Complex64>> {
+    args.iter()
+        .map(|arg| {
+            arg.as_complex().ok_or_else(|| {
+                ArgonError::type_error(format!("{} expects numbers, got {}", name, arg.type_name()))
+            })
+        })
+        .collect()
+}
+
+/// Returns the arguments as exact fractions, converting large integers to fractions. Returns
+/// `None` if any of them isn't exact.
+fn rationals(args: &[Value]) -> Option<Vec<BigRational>> {
+    args.iter()
+        .map(|arg| match arg {
+            Value::Integer(n) => Some(BigRational::from_integer((**n).clone())),
+            Value::Rational(r) => Some((**r).clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Returns the arguments as decimals with the largest precision of any decimal among them.
+/// Returns `None` if there is no decimal or any argument can't be converted to one.
+fn decimals(args: &[Value]) -> Option<Vec<Decimal>> {
+    let precision = args
+        .iter()
+        .filter_map(|arg| match arg {
+            Value::Decimal(d) => Some(d.precision()),
+            _ => None,
+        })
+        .max()?;
+    args.iter().map(|arg| arg.to_decimal(precision)).collect()
+}
+
+/// Raises a complex number to a complex power. Whole real powers are calculated by repeated
+/// multiplication, so `i^2` is exactly `-1`.
+pub fn complex_pow(base: Complex64, exponent: Complex64) -> Complex64 {
+    if exponent.im == 0.0 && exponent.re.fract() == 0.0 && exponent.re.abs() <= i32::MAX as f64 {
+        base.powi(exponent.re as i32)
+    } else {
+        base.powc(exponent)
+    }
+}
+
+/// Expands the lists and matrices passed to an aggregate function such as `max`, so
+/// `max([1, 2], 3)` is the same as `max(1, 2, 3)`.
+fn flatten(args: Vec<Value>) -> Vec<Value> {
+    let mut result = Vec::new();
+    for arg in args {
+        match arg {
+            Value::List(items) => result.extend(items),
+            Value::Matrix(matrix) => {
+                result.extend(matrix.values().iter().map(|&n| Value::Number(n)))
+            }
+            arg => result.push(arg),
+        }
+    }
+    result
+}
+
+/// The built-in functions that are written once against [`Elementary`], so they work on every
+/// kind of number.
+#[derive(Debug, Clone, Copy)]
+enum Generic {
+    Sqrt,
+    Root,
+    Exp,
+    Pow,
+    Ln,
+    Log10,
+    Log2,
+    Log,
+    Abs,
+    Recip,
+    Sin,
+    Cos,
+    Tan,
+    Asin,
+    Acos,
+    Atan,
+    Sinh,
+    Cosh,
+    Tanh,
+    Asinh,
+    Acosh,
+    Atanh,
+    Floor,
+    Ceil,
+    Round,
+    Max,
+    Min,
+    Sum,
+    Avg,
+    Median,
+}
+
+impl Generic {
+    /// Whether the function takes any number of arguments, so lists and matrices are expanded.
+    fn is_aggregate(self) -> bool {
+        matches!(
+            self,
+            Generic::Max | Generic::Min | Generic::Sum | Generic::Avg | Generic::Median
+        )
+    }
+
+    /// Calculates the function with numbers of the kind `N`, returning `None` if it has no result
+    /// of that kind.
+    fn call<N: Elementary>(self, args: Vec<N>) -> Result<Option<N>> {
+        Ok(match self {
+            Generic::Sqrt => args[0].sqrt(),
+            Generic::Root => args[0].root(&args[1]),
+            Generic::Exp => args[0].exp(),
+            Generic::Pow => args[0].try_pow(&args[1]),
+            Generic::Ln => args[0].ln(),
+            Generic::Log10 => args[0].log10(),
+            Generic::Log2 => args[0].log2(),
+            Generic::Log => args[0].log(&args[1]),
+            Generic::Abs => args[0].abs(),
+            Generic::Recip => args[0].recip(),
+            Generic::Sin => args[0].sin(),
+            Generic::Cos => args[0].cos(),
+            Generic::Tan => args[0].tan(),
+            Generic::Asin => args[0].asin(),
+            Generic::Acos => args[0].acos(),
+            Generic::Atan => args[0].atan(),
+            Generic::Sinh => args[0].sinh(),
+            Generic::Cosh => args[0].cosh(),
+            Generic::Tanh => args[0].tanh(),
+            Generic::Asinh => args[0].asinh(),
+            Generic::Acosh => args[0].acosh(),
+            Generic::Atanh => args[0].atanh(),
+            Generic::Floor => args[0].floor(),
+            Generic::Ceil => args[0].ceil(),
+            Generic::Round => args[0].round(),
+            // Numbers that can't be ordered, such as complex numbers, have no largest or middle one
+            Generic::Max | Generic::Min | Generic::Median if !N::ORDERED => None,
+            Generic::Max => Some(max(args)?),
+            Generic::Min => Some(min(args)?),
+            Generic::Sum => total(&args),
+            Generic::Avg => Some(avg(args)?),
+            Generic::Median => Some(median(args)?),
+        })
+    }
+}
+
+/// Wraps a function written against [`Elementary`] so it is called with the kind of number that
+/// can hold all of its arguments: complex numbers if any is complex, decimals if any is a decimal,
+/// and exact fractions if every one is exact. If it has no result of that kind, such as the square
+/// root of 2 as a fraction, it is calculated with floating point numbers instead.
+fn generic(
+    name: &'static str,
+    function: Generic,
+) -> impl Fn(Vec<Value>) -> Result<Value> + Send + Sync {
+    move |args| {
+        let args = if function.is_aggregate() {
+            flatten(args)
+        } else {
+            args
+        };
+        let result = if args.iter().any(|arg| matches!(arg, Value::Complex(_))) {
+            function
+                .call(complex_numbers(name, &args)?)?
+                .map(Value::from)
+        } else if let Some(args) = decimals(&args) {
+            function.call(args)?.map(Value::from)
+        } else if let Some(args) = rationals(&args) {
+            function.call(args)?.map(Value::from)
+        } else {
+            None
+        };
+        match result {
+            Some(result) => Ok(result),
+            // Floating point numbers have every function, so the only thing without a result is
+            // the sum of no numbers, which is 0
+            None => Ok(Value::Number(
+                function.call(numbers(name, &args)?)?.unwrap_or(0.0),
+            )),
+        }
+    }
+}
+
+/// Returns `result` as an exact fraction if any of `args` is one, so exact mode stays exact.
+fn exact_if_any(args: &[Value], result: Value) -> Value {
+    if args.iter().any(|arg| matches!(arg, Value::Rational(_))) {
+        result.into_exact()
+    } else {
+        result
+    }
+}
+
+/// Returns an error unless an aggregate function was given at least one number.
+fn require_numbers<T>(name: &str, args: &[T]) -> Result<()> {
+    if args.is_empty() {
+        return Err(ArgonError::domain(name, "needs at least one number"));
+    }
+    Ok(())
+}
+
+/// Takes the list argument of the function called `name`, returning a type error if it is
+/// something else.
+fn expect_list(name: &str, arg: Value) -> Result<Vec<Value>> {
+    match arg {
+        Value::List(items) => Ok(items),
+        arg => Err(ArgonError::type_error(format!(
+            "{} expects a list, got {}",
+            name,
+            arg.type_name()
+        ))),
+    }
+}
+
+/// Takes the matrix argument of the function called `name`, returning a type error if it is
+/// something else.
+fn expect_matrix(name: &str, arg: Value) -> Result<Matrix> {
+    match arg {
+        Value::Matrix(matrix) => Ok(matrix),
+        arg => Err(ArgonError::type_error(format!(
+            "{} expects a matrix, got {}",
+            name,
+            arg.type_name()
+        ))),
+    }
+}
+
+/// The largest matrix `identity` will create.
+const MAX_IDENTITY_SIZE: usize = 1000;
+
+/// The longest list `range` will create.
+const MAX_RANGE_LENGTH: usize = 10_000_000;
+
+/// Loads the built-in calculator functions into a registry.
+///
+/// # Returns
+///
+/// A registry containing the calculator functions.
+pub fn load_functions() -> FunctionRegistry {
+    let mut functions = FunctionRegistry::new();
+
+    functions.register(
+        FunctionInfo::new("sqrt", &["number"], "the square root of the number"),
+        generic("sqrt", Generic::Sqrt),
+    );
+    functions.register(
+        FunctionInfo::new("sin", &["angle"], "the sine of an angle in radians"),
+        generic("sin", Generic::Sin),
+    );
+    functions.register(
+        FunctionInfo::new("cos", &["angle"], "the cosine of an angle in radians"),
+        generic("cos", Generic::Cos),
+    );
+    functions.register(
+        FunctionInfo::new("tan", &["angle"], "the tangent of an angle in radians"),
+        generic("tan", Generic::Tan),
+    );
+    functions.register(
+        FunctionInfo::new("asin", &["number"], "the arcsine of the number"),
+        generic("asin", Generic::Asin),
+    );
+    functions.register(
+        FunctionInfo::new("acos", &["number"], "the arccosine of the number"),
+        generic("acos", Generic::Acos),
+    );
+    functions.register(
+        FunctionInfo::new("atan", &["number"], "the arctangent of the number"),
+        generic("atan", Generic::Atan),
+    );
+    functions.register(
+        FunctionInfo::new("sinh", &["number"], "the hyperbolic sine of the number"),
+        generic("sinh", Generic::Sinh),
+    );
+    functions.register(
+        FunctionInfo::new("cosh", &["number"], "the hyperbolic cosine of the number"),
+        generic("cosh", Generic::Cosh),
+    );
+    functions.register(
+        FunctionInfo::new("tanh", &["number"], "the hyperbolic tangent of the number"),
+        generic("tanh", Generic::Tanh),
+    );
+    functions.register(
+        FunctionInfo::new(
+            "asinh",
+            &["number"],
+            "the inverse hyperbolic sine of the number",
+        ),
+        generic("asinh", Generic::Asinh),
+    );
+    functions.register(
+        FunctionInfo::new(
+            "acosh",
+            &["number"],
+            "the inverse hyperbolic cosine of the number",
+        ),
+        generic("acosh", Generic::Acosh),
+    );
+    functions.register(
+        FunctionInfo::new(
+            "atanh",
+            &["number"],
+            "the inverse hyperbolic tangent of the number",
+        ),
+        generic("atanh", Generic::Atanh),
+    );
+    functions.register(
+        FunctionInfo::new(
+            "floor",
+            &["number"],
+            "the largest integer less than or equal to the number",
+        ),
+        generic("floor", Generic::Floor),
+    );
+    functions.register(
+        FunctionInfo::new(
+            "ceil",
+            &["number"],
+            "the smallest integer greater than or equal to the number",
+        ),
+        generic("ceil", Generic::Ceil),
+    );
+    functions.register(
+        FunctionInfo::new(
+            "round",
+            &["number"],
+            "the number rounded to the nearest integer",
+        ),
+        generic("round", Generic::Round),
+    );
+    functions.register(
+        FunctionInfo::new("recip", &["number"], "the reciprocal of the number"),
+        generic("recip", Generic::Recip),
+    );
+    functions.register(
+        FunctionInfo::new("abs", &["number"], "the absolute value of the number"),
+        generic("abs", Generic::Abs),
+    );
+    functions.register_numeric(
+        FunctionInfo::new(
+            "rad2deg",
+            &["angle"],
+            "converts an angle in radians to degrees",
+        ),
+        rad2deg,
+    );
+    functions.register_numeric(
+        FunctionInfo::new(
+            "deg2rad",
+            &["angle"],
+            "converts an angle in degrees to radians",
+        ),
+        deg2rad,
+    );
+    functions.register(
+        FunctionInfo::new("ln", &["number"], "the natural logarithm of the number"),
+        generic("ln", Generic::Ln),
+    );
+    functions.register(
+        FunctionInfo::new("log10", &["number"], "the base 10 logarithm of the number"),
+        generic("log10", Generic::Log10),
+    );
+    functions.register(
+        FunctionInfo::new("log2", &["number"], "the base 2 logarithm of the number"),
+        generic("log2", Generic::Log2),
+    );
+    functions.register(
+        FunctionInfo::new(
+            "log",
+            &["number", "base"],
+            "the logarithm of the number to the given base",
+        ),
+        generic("log", Generic::Log),
+    );
+    functions.register(
+        FunctionInfo::new("factorial", &["number"], "the factorial of the number"),
+        |args| {
+            let result = factorial_of(&whole_numbers("factorial", &args)?[0]);
+            Ok(exact_if_any(&args, result))
+        },
+    );
+    functions.register(
+        FunctionInfo::new(
+            "nCr",
+            &["n", "k"],
+            "the number of ways to choose k of n items, ignoring their order",
+        ),
+        |args| {
+            let numbers = whole_numbers("nCr", &args)?;
+            Ok(exact_if_any(&args, combinations(&numbers[0], &numbers[1])))
+        },
+    );
+    functions.register(
+        FunctionInfo::new(
+            "nPr",
+            &["n", "k"],
+            "the number of ways to arrange k of n items in order",
+        ),
+        |args| {
+            let numbers = whole_numbers("nPr", &args)?;
+            Ok(exact_if_any(&args, permutations(&numbers[0], &numbers[1])))
+        },
+    );
+    functions.register(
+        FunctionInfo::new(
+            "pow",
+            &["base", "exponent"],
+            "the base raised to the power of the exponent",
+        ),
+        generic("pow", Generic::Pow),
+    );
+    functions.register(
+        FunctionInfo::variadic("max", "numbers", 1, "the largest of the numbers"),
+        generic("max", Generic::Max),
+    );
+    functions.register(
+        FunctionInfo::variadic("min", "numbers", 1, "the smallest of the numbers"),
+        generic("min", Generic::Min),
+    );
+    functions.register(
+        FunctionInfo::variadic("sum", "numbers", 0, "the sum of the numbers"),
+        generic("sum", Generic::Sum),
+    );
+    functions.register(
+        FunctionInfo::variadic("avg", "numbers", 1, "the average of the numbers"),
+        generic("avg", Generic::Avg),
+    );
+    functions.register(
+        FunctionInfo::variadic("median", "numbers", 1, "the median of the numbers"),
+        generic("median", Generic::Median),
+    );
+    functions.register(
+        FunctionInfo::new("det", &["matrix"], "the determinant of a square matrix"),
+        det,
+    );
+    functions.register(
+        FunctionInfo::new("inv", &["matrix"], "the inverse of a square matrix"),
+        inv,
+    );
+    functions.register(
+        FunctionInfo::new(
+            "transpose",
+            &["matrix"],
+            "the matrix with its rows and columns swapped",
+        ),
+        transpose,
+    );
+    functions.register(
+        FunctionInfo::new(
+            "trace",
+            &["matrix"],
+            "the sum of the diagonal of a square matrix",
+        ),
+        trace,
+    );
+    functions.register(
+        FunctionInfo::new(
+            "rank",
+            &["matrix"],
+            "the number of linearly independent rows of the matrix",
+        ),
+        rank,
+    );
+    functions.register(
+        FunctionInfo::new(
+            "identity",
+            &["size"],
+            "the identity matrix of the given size",
+        ),
+        identity,
+    );
+    functions.register(
+        FunctionInfo::new(
+            "lu",
+            &["matrix"],
+            "the matrices [L, U, P] such that P * matrix = L * U",
+        ),
+        lu,
+    );
+    functions.register(
+        FunctionInfo::new(
+            "qr",
+            &["matrix"],
+            "the matrices [Q, R] such that matrix = Q * R",
+        ),
+        qr,
+    );
+    functions.register(
+        FunctionInfo::new(
+            "eig",
+            &["matrix"],
+            "the eigenvalues of a square matrix, from smallest to largest",
+        ),
+        eig,
+    );
+    functions.register(
+        FunctionInfo::new("len", &["list"], "the number of items in a list or string"),
+        len,
+    );
+    functions.register(
+        FunctionInfo::new(
+            "range",
+            &["start", "end", "step"],
+            "the numbers from start up to but not including end, or from 0 if only end is given",
+        )
+        .with_min_args(1),
+        range,
+    );
+    functions.register(
+        FunctionInfo::new(
+            "sort",
+            &["list"],
+            "the list sorted from smallest to largest",
+        ),
+        sort,
+    );
+    functions.register(
+        FunctionInfo::new("reverse", &["list"], "the list or string in reverse order"),
+        reverse,
+    );
+    functions.register(
+        FunctionInfo::variadic("concat", "lists", 1, "the lists joined together"),
+        concat,
+    );
+    functions.register(
+        FunctionInfo::new(
+            "unique",
+            &["list"],
+            "the list without repeated items, keeping the first of each",
+        ),
+        unique,
+    );
+    functions.register(
+        FunctionInfo::new("root", &["number", "root"], "the nth root of the number"),
+        generic("root", Generic::Root),
+    );
+    functions.register(
+        FunctionInfo::new("exp", &["power"], "E raised to the given power"),
+        generic("exp", Generic::Exp),
+    );
+    functions.register(
+        FunctionInfo::new("re", &["number"], "the real part of the number"),
+        re,
+    );
+    functions.register(
+        FunctionInfo::new("im", &["number"], "the imaginary part of the number"),
+        im,
+    );
+    functions.register(
+        FunctionInfo::new(
+            "arg",
+            &["number"],
+            "the angle of the number from the positive real axis, in radians",
+        ),
+        arg,
+    );
+    functions.register(
+        FunctionInfo::new("conj", &["number"], "the complex conjugate of the number"),
+        conj,
+    );
 
     functions
 }
@@ -387,21 +1118,124 @@ pub fn log(args: Vec<f64>) -> Result<f64> {
     Ok(args[0].log(args[1]))
 }
 
-/// Calculates the factorial of a number.
+/// Returns `n` as a whole number, or a domain error for the function called `name` if it is
+/// negative, has a fractional part, or is too large for a floating point number to hold exactly,
+/// such as `1e20`.
+fn whole_number(name: &str, n: f64) -> Result<BigInt> {
+    if n < 0.0 || !integer::is_whole(n) {
+        return Err(ArgonError::domain(
+            name,
+            "is only defined for non-negative integers",
+        ));
+    }
+    if !integer::is_exact(n) {
+        return Err(ArgonError::domain(
+            name,
+            "is only defined for integers small enough to be exact",
+        ));
+    }
+    Ok(BigInt::from(n as u64))
+}
+
+/// Returns the exact fraction `n` as a whole number, or a domain error for the function called
+/// `name` if it is negative or has a fractional part.
+fn whole_integer(name: &str, n: &BigRational) -> Result<BigInt> {
+    if n.is_negative() || !n.is_integer() {
+        return Err(ArgonError::domain(
+            name,
+            "is only defined for non-negative integers",
+        ));
+    }
+    Ok(n.to_integer())
+}
+
+/// Returns `n!`, or infinity if `n` is too large to calculate exactly.
+fn factorial_of(n: &BigInt) -> Value {
+    match n.to_u64().filter(|&n| n <= integer::MAX_EXACT_INPUT) {
+        Some(count) => integer::falling_product(n, count, 1).into(),
+        None => Value::Number(f64::INFINITY),
+    }
+}
+
+/// Returns the number of ways to choose `k` of `n` items, or infinity if it is too large to
+/// calculate exactly.
+fn combinations(n: &BigInt, k: &BigInt) -> Value {
+    integer::binomial(n, k).map_or(Value::Number(f64::INFINITY), Value::from)
+}
+
+/// Returns the number of ways to arrange `k` of `n` items, or infinity if it is too large to
+/// calculate exactly.
+fn permutations(n: &BigInt, k: &BigInt) -> Value {
+    if k > n {
+        return Value::Number(0.0);
+    }
+    match k.to_u64().filter(|&k| k <= integer::MAX_EXACT_INPUT) {
+        Some(k) => integer::falling_product(n, k, 1).into(),
+        None => Value::Number(f64::INFINITY),
+    }
+}
+
+/// Calculates the factorial of a number. Every digit is kept, so `factorial(25)` is exactly
+/// `15511210043330985984000000`.
 ///
 /// # Arguments
 ///
-/// * `args` - A vector containing a single number.
+/// * `args` - A vector containing a single non-negative integer.
+///
+/// # Returns
+///
+/// The factorial of the number, or infinity if it is too large to calculate.
+pub fn factorial(args: Vec<f64>) -> Result<Value> {
+    Ok(factorial_of(&whole_number("factorial", args[0])?))
+}
+
+/// Calculates the double factorial of a number, the product of every integer up to it with the
+/// same parity.
+///
+/// # Arguments
+///
+/// * `args` - A vector containing a single non-negative integer.
 ///
 /// # Returns
 ///
-/// The factorial of the number.
-pub fn factorial(args: Vec<f64>) -> Result<f64> {
-    let mut result = 1.0;
-    for i in 1..(args[0] as u64 + 1) {
-        result *= i as f64;
+/// The double factorial of the number, or infinity if it is too large to calculate.
+pub fn double_factorial(args: Vec<f64>) -> Result<Value> {
+    let n = whole_number("double factorial", args[0])?;
+    match n.to_u64().filter(|&n| n <= integer::MAX_EXACT_INPUT) {
+        Some(count) => Ok(integer::falling_product(&n, count.div_ceil(2), 2).into()),
+        None => Ok(Value::Number(f64::INFINITY)),
     }
-    Ok(result)
+}
+
+/// Calculates the number of ways to choose `k` of `n` items when their order doesn't matter,
+/// also called the binomial coefficient.
+///
+/// # Arguments
+///
+/// * `args` - A vector containing two non-negative integers, `n` and `k`.
+///
+/// # Returns
+///
+/// The number of combinations, which is 0 if `k` is larger than `n`, or infinity if it is too
+/// large to calculate.
+pub fn ncr(args: Vec<f64>) -> Result<Value> {
+    let (n, k) = (whole_number("nCr", args[0])?, whole_number("nCr", args[1])?);
+    Ok(combinations(&n, &k))
+}
+
+/// Calculates the number of ways to arrange `k` of `n` items when their order matters.
+///
+/// # Arguments
+///
+/// * `args` - A vector containing two non-negative integers, `n` and `k`.
+///
+/// # Returns
+///
+/// The number of permutations, which is 0 if `k` is larger than `n`, or infinity if it is too
+/// large to calculate.
+pub fn npr(args: Vec<f64>) -> Result<Value> {
+    let (n, k) = (whole_number("nPr", args[0])?, whole_number("nPr", args[1])?);
+    Ok(permutations(&n, &k))
 }
 
 /// Calculates the power of a number.
@@ -426,8 +1260,9 @@ pub fn pow(args: Vec<f64>) -> Result<f64> {
 /// # Returns
 ///
 /// The maximum value among the numbers.
-pub fn max(args: Vec<f64>) -> Result<f64> {
-    Ok(args.iter().fold(f64::NEG_INFINITY, |a, &b| a.max(b)))
+pub fn max<N: Number>(args: Vec<N>) -> Result<N> {
+    require_numbers("max", &args)?;
+    Ok(largest(args, Ordering::Greater))
 }
 
 /// Calculates the minimum value among a list of numbers.
@@ -439,8 +1274,9 @@ pub fn max(args: Vec<f64>) -> Result<f64> {
 /// # Returns
 ///
 /// The minimum value among the numbers.
-pub fn min(args: Vec<f64>) -> Result<f64> {
-    Ok(args.iter().fold(f64::INFINITY, |a, &b| a.min(b)))
+pub fn min<N: Number>(args: Vec<N>) -> Result<N> {
+    require_numbers("min", &args)?;
+    Ok(largest(args, Ordering::Less))
 }
 
 /// Calculates the sum of a list of numbers.
@@ -453,7 +1289,7 @@ pub fn min(args: Vec<f64>) -> Result<f64> {
 ///
 /// The sum of the numbers.
 pub fn sum(args: Vec<f64>) -> Result<f64> {
-    Ok(args.iter().sum())
+    Ok(total(&args).unwrap_or(0.0))
 }
 
 /// Calculates the average of a list of numbers.
@@ -465,8 +1301,12 @@ pub fn sum(args: Vec<f64>) -> Result<f64> {
 /// # Returns
 ///
 /// The average of the numbers.
-pub fn avg(args: Vec<f64>) -> Result<f64> {
-    Ok(args.iter().sum::<f64>() / args.len() as f64)
+pub fn avg<N: Number>(args: Vec<N>) -> Result<N> {
+    require_numbers("avg", &args)?;
+    let count = args[0].whole(args.len());
+    total(&args)
+        .and_then(|total| total.try_div(&count))
+        .ok_or_else(|| ArgonError::domain("avg", "has no result of the same kind"))
 }
 
 /// Calculates the median of a list of numbers.
@@ -478,21 +1318,583 @@ pub fn avg(args: Vec<f64>) -> Result<f64> {
 /// # Returns
 ///
 /// The median of the numbers.
-pub fn median(args: Vec<f64>) -> Result<f64> {
+pub fn median<N: Number>(args: Vec<N>) -> Result<N> {
+    require_numbers("median", &args)?;
     let mut args = args;
-    args.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    // NaN sorts after every other number, like it does in `sort`
+    args.sort_by(|a, b| a.compare(b).unwrap_or_else(|| is_nan(a).cmp(&is_nan(b))));
     let mid = args.len() / 2;
-    if args.len() % 2 == 0 {
-        Ok((args[mid - 1] + args[mid]) / 2.0)
+    if args.len().is_multiple_of(2) {
+        let two = args[mid].whole(2);
+        args[mid - 1]
+            .add(&args[mid])
+            .try_div(&two)
+            .ok_or_else(|| ArgonError::domain("median", "has no result of the same kind"))
     } else {
-        Ok(args[mid])
+        Ok(args.swap_remove(mid))
     }
 }
 
+/// Returns the number furthest in the direction `order`, ignoring `NaN` unless every number is.
+fn largest<N: Number>(args: Vec<N>, order: Ordering) -> N {
+    args.into_iter()
+        .reduce(|a, b| {
+            if is_nan(&a) || b.compare(&a) == Some(order) {
+                b
+            } else {
+                a
+            }
+        })
+        .unwrap()
+}
+
+/// Adds up the numbers, returning `None` if there are none.
+fn total<N: Number>(args: &[N]) -> Option<N> {
+    let (first, rest) = args.split_first()?;
+    Some(rest.iter().fold(first.clone(), |total, n| total.add(n)))
+}
+
+/// Whether the number is `NaN`, which is the only number that can't be compared with itself.
+/// Kinds of number that can't be ordered at all are never passed here.
+fn is_nan<N: Number>(n: &N) -> bool {
+    n.compare(n).is_none()
+}
+
+/// Calculates the determinant of a square matrix.
+///
+/// # Arguments
+///
+/// * `args` - A vector containing a single square matrix.
+///
+/// # Returns
+///
+/// The determinant of the matrix.
+pub fn det(args: Vec<Value>) -> Result<Value> {
+    let matrix = expect_matrix("det", args.into_iter().next().unwrap())?;
+    Ok(Value::Number(matrix.det()?))
+}
+
+/// Inverts a square matrix.
+///
+/// # Arguments
+///
+/// * `args` - A vector containing a single square matrix.
+///
+/// # Returns
+///
+/// The inverse of the matrix, or an error if the matrix is singular.
+pub fn inv(args: Vec<Value>) -> Result<Value> {
+    let matrix = expect_matrix("inv", args.into_iter().next().unwrap())?;
+    Ok(Value::Matrix(matrix.inverse()?))
+}
+
+/// Transposes a matrix.
+///
+/// # Arguments
+///
+/// * `args` - A vector containing a single matrix.
+///
+/// # Returns
+///
+/// The matrix with its rows and columns swapped.
+pub fn transpose(args: Vec<Value>) -> Result<Value> {
+    let matrix = expect_matrix("transpose", args.into_iter().next().unwrap())?;
+    Ok(Value::Matrix(matrix.transpose()))
+}
+
+/// Calculates the trace of a square matrix.
+///
+/// # Arguments
+///
+/// * `args` - A vector containing a single square matrix.
+///
+/// # Returns
+///
+/// The sum of the entries on the diagonal of the matrix.
+pub fn trace(args: Vec<Value>) -> Result<Value> {
+    let matrix = expect_matrix("trace", args.into_iter().next().unwrap())?;
+    Ok(Value::Number(matrix.trace()?))
+}
+
+/// Calculates the rank of a matrix.
+///
+/// # Arguments
+///
+/// * `args` - A vector containing a single matrix.
+///
+/// # Returns
+///
+/// The number of linearly independent rows of the matrix.
+pub fn rank(args: Vec<Value>) -> Result<Value> {
+    let matrix = expect_matrix("rank", args.into_iter().next().unwrap())?;
+    Ok(Value::Number(matrix.rank() as f64))
+}
+
+/// Creates an identity matrix.
+///
+/// # Arguments
+///
+/// * `args` - A vector containing the number of rows and columns.
+///
+/// # Returns
+///
+/// The identity matrix of the given size.
+pub fn identity(args: Vec<Value>) -> Result<Value> {
+    let size = numbers("identity", &args)?[0];
+    if size < 1.0 || size.fract() != 0.0 || size > MAX_IDENTITY_SIZE as f64 {
+        return Err(ArgonError::domain(
+            "identity",
+            format!(
+                "is only defined for whole numbers between 1 and {}",
+                MAX_IDENTITY_SIZE
+            ),
+        ));
+    }
+    Ok(Value::Matrix(Matrix::identity(size as usize)))
+}
+
+/// Calculates the LU decomposition of a square matrix, with partial pivoting.
+///
+/// # Arguments
+///
+/// * `args` - A vector containing a single square matrix.
+///
+/// # Returns
+///
+/// A list of the lower triangular matrix L, the upper triangular matrix U and the permutation
+/// matrix P, such that P times the matrix is L times U.
+pub fn lu(args: Vec<Value>) -> Result<Value> {
+    let matrix = expect_matrix("lu", args.into_iter().next().unwrap())?;
+    let (l, u, p) = matrix.lu()?;
+    Ok(Value::List(vec![l.into(), u.into(), p.into()]))
+}
+
+/// Calculates the QR decomposition of a matrix.
+///
+/// # Arguments
+///
+/// * `args` - A vector containing a single matrix.
+///
+/// # Returns
+///
+/// A list of the orthogonal matrix Q and the upper triangular matrix R, such that the matrix is
+/// Q times R.
+pub fn qr(args: Vec<Value>) -> Result<Value> {
+    let matrix = expect_matrix("qr", args.into_iter().next().unwrap())?;
+    let (q, r) = matrix.qr();
+    Ok(Value::List(vec![q.into(), r.into()]))
+}
+
+/// Calculates the eigenvalues of a square matrix.
+///
+/// # Arguments
+///
+/// * `args` - A vector containing a single square matrix.
+///
+/// # Returns
+///
+/// A list of the eigenvalues from smallest to largest, or an error if any of them are complex.
+pub fn eig(args: Vec<Value>) -> Result<Value> {
+    let matrix = expect_matrix("eig", args.into_iter().next().unwrap())?;
+    Ok(Value::List(
+        matrix
+            .eigenvalues()?
+            .into_iter()
+            .map(Value::Number)
+            .collect(),
+    ))
+}
+
+/// Returns the real part of a number.
+///
+/// # Arguments
+///
+/// * `args` - A vector containing a single real or complex number.
+///
+/// # Returns
+///
+/// The real part of the number.
+pub fn re(args: Vec<Value>) -> Result<Value> {
+    Ok(Value::Number(complex_numbers("re", &args)?[0].re))
+}
+
+/// Returns the imaginary part of a number.
+///
+/// # Arguments
+///
+/// * `args` - A vector containing a single real or complex number.
+///
+/// # Returns
+///
+/// The imaginary part of the number, which is 0 for real numbers.
+pub fn im(args: Vec<Value>) -> Result<Value> {
+    Ok(Value::Number(complex_numbers("im", &args)?[0].im))
+}
+
+/// Calculates the argument of a number, also called its phase.
+///
+/// # Arguments
+///
+/// * `args` - A vector containing a single real or complex number.
+///
+/// # Returns
+///
+/// The angle between the positive real axis and the number, in radians between -pi and pi.
+pub fn arg(args: Vec<Value>) -> Result<Value> {
+    Ok(Value::Number(complex_numbers("arg", &args)?[0].arg()))
+}
+
+/// Calculates the complex conjugate of a number.
+///
+/// # Arguments
+///
+/// * `args` - A vector containing a single real or complex number.
+///
+/// # Returns
+///
+/// The number with its imaginary part negated.
+pub fn conj(args: Vec<Value>) -> Result<Value> {
+    Ok(complex_numbers("conj", &args)?[0].conj().into())
+}
+
+/// Counts the items in a list or the characters in a string.
+///
+/// # Arguments
+///
+/// * `args` - A vector containing a single list or string.
+///
+/// # Returns
+///
+/// The length of the list or string.
+pub fn len(args: Vec<Value>) -> Result<Value> {
+    match &args[0] {
+        Value::List(items) => Ok(Value::Number(items.len() as f64)),
+        Value::Matrix(matrix) => Ok(Value::Number(matrix.rows() as f64)),
+        Value::String(s) => Ok(Value::Number(s.chars().count() as f64)),
+        arg => Err(ArgonError::type_error(format!(
+            "len expects a list or string, got {}",
+            arg.type_name()
+        ))),
+    }
+}
+
+/// Creates a list of evenly spaced numbers.
+///
+/// # Arguments
+///
+/// * `args` - A vector containing the end; the start and the end; or the start, the end and
+///   the step. The start defaults to 0 and the step to 1.
+///
+/// # Returns
+///
+/// A list of the numbers from the start up to but not including the end.
+pub fn range(args: Vec<Value>) -> Result<Value> {
+    let (start, end, step) = match numbers("range", &args)?[..] {
+        [end] => (0.0, end, 1.0),
+        [start, end] => (start, end, 1.0),
+        [start, end, step] => (start, end, step),
+        _ => unreachable!("range takes between 1 and 3 arguments"),
+    };
+
+    if step == 0.0 || !step.is_finite() {
+        return Err(ArgonError::domain(
+            "range",
+            "step must be a non-zero number",
+        ));
+    }
+    let count = ((end - start) / step).ceil().max(0.0);
+    if count.is_nan() || count > MAX_RANGE_LENGTH as f64 {
+        return Err(ArgonError::domain("range", "would create too many numbers"));
+    }
+
+    Ok(Value::List(
+        (0..count as usize)
+            .map(|i| Value::Number(start + i as f64 * step))
+            .collect(),
+    ))
+}
+
+/// Sorts a list of numbers or a list of strings.
+///
+/// # Arguments
+///
+/// * `args` - A vector containing a single list.
+///
+/// # Returns
+///
+/// The list sorted from smallest to largest.
+pub fn sort(args: Vec<Value>) -> Result<Value> {
+    let mut items = expect_list("sort", args.into_iter().next().unwrap())?;
+    if items.iter().all(|item| item.as_number().is_some()) {
+        // Large integers and exact fractions are compared exactly and keep their exact values
+        items.sort_by(|a, b| match (a, b) {
+            (Value::Integer(a), Value::Integer(b)) => a.cmp(b),
+            (Value::Rational(a), Value::Rational(b)) => a.cmp(b),
+            (a, b) => a.as_number().unwrap().total_cmp(&b.as_number().unwrap()),
+        });
+        return Ok(Value::List(items));
+    }
+    if items.iter().all(|item| matches!(item, Value::String(_))) {
+        items.sort_by(|a, b| match (a, b) {
+            (Value::String(a), Value::String(b)) => a.cmp(b),
+            _ => unreachable!("all items are strings"),
+        });
+        return Ok(Value::List(items));
+    }
+    Err(ArgonError::type_error(
+        "sort expects a list of numbers or a list of strings",
+    ))
+}
+
+/// Reverses a list or string.
+///
+/// # Arguments
+///
+/// * `args` - A vector containing a single list or string.
+///
+/// # Returns
+///
+/// The list or string in reverse order.
+pub fn reverse(args: Vec<Value>) -> Result<Value> {
+    match args.into_iter().next().unwrap() {
+        Value::String(s) => Ok(Value::String(s.chars().rev().collect())),
+        arg => {
+            let mut items = expect_list("reverse", arg)?;
+            items.reverse();
+            Ok(Value::List(items))
+        }
+    }
+}
+
+/// Joins lists together.
+///
+/// # Arguments
+///
+/// * `args` - A vector containing multiple lists.
+///
+/// # Returns
+///
+/// A list with the items of every list, in order.
+pub fn concat(args: Vec<Value>) -> Result<Value> {
+    let mut result = Vec::new();
+    for arg in args {
+        result.extend(expect_list("concat", arg)?);
+    }
+    Ok(Value::List(result))
+}
+
+/// Removes repeated items from a list.
+///
+/// # Arguments
+///
+/// * `args` - A vector containing a single list.
+///
+/// # Returns
+///
+/// The list with only the first of each repeated item.
+pub fn unique(args: Vec<Value>) -> Result<Value> {
+    let mut result: Vec<Value> = Vec::new();
+    for item in expect_list("unique", args.into_iter().next().unwrap())? {
+        if !result.contains(&item) {
+            result.push(item);
+        }
+    }
+    Ok(Value::List(result))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_function_info() {
+        let functions = load_functions();
+        match functions.get("log").unwrap() {
+            Function::BuiltIn { info, .. } => {
+                assert_eq!(info.min_args, 2);
+                assert_eq!(info.max_args, Some(2));
+                assert_eq!(
+                    info.to_string(),
+                    "log(number, base): the logarithm of the number to the given base"
+                );
+            }
+            _ => panic!("log should be built-in"),
+        }
+        let median = functions.get("median").unwrap();
+        assert!(median.check_arity("median", 5).is_ok());
+        assert!(median.check_arity("median", 0).is_err());
+    }
+
+    #[test]
+    fn test_aggregates_accept_lists() {
+        let list = Value::from(vec![Value::from(3.0), Value::from(1.0)]);
+        let functions = load_functions();
+        let call = |name: &str, args: Vec<Value>| match functions.get(name).unwrap() {
+            Function::BuiltIn { function, .. } => function(args),
+            _ => panic!("{} should be built-in", name),
+        };
+
+        assert_eq!(
+            call("max", vec![list.clone(), Value::from(2.0)]).unwrap(),
+            3.0
+        );
+        assert_eq!(call("sum", vec![list.clone()]).unwrap(), 4.0);
+        assert_eq!(call("median", vec![list]).unwrap(), 2.0);
+        assert!(call("avg", vec![Value::List(vec![])]).is_err());
+        assert!(call("min", vec![Value::from(vec![Value::from(true)])]).is_err());
+    }
+
+    #[test]
+    fn test_list_functions() {
+        let numbers = |values: &[f64]| Value::List(values.iter().map(|&n| n.into()).collect());
+
+        assert_eq!(len(vec![numbers(&[1.0, 2.0])]).unwrap(), 2.0);
+        assert_eq!(len(vec!["héllo".into()]).unwrap(), 5.0);
+        assert!(len(vec![1.0.into()]).is_err());
+
+        assert_eq!(range(vec![3.0.into()]).unwrap(), numbers(&[0.0, 1.0, 2.0]));
+        assert_eq!(
+            range(vec![5.0.into(), 0.0.into(), (-2.0).into()]).unwrap(),
+            numbers(&[5.0, 3.0, 1.0])
+        );
+        assert_eq!(range(vec![2.0.into(), 1.0.into()]).unwrap(), numbers(&[]));
+        assert!(range(vec![0.0.into(), 1.0.into(), 0.0.into()]).is_err());
+        assert!(range(vec![f64::INFINITY.into()]).is_err());
+
+        assert_eq!(
+            sort(vec![numbers(&[3.0, 1.0, 2.0])]).unwrap(),
+            numbers(&[1.0, 2.0, 3.0])
+        );
+        assert_eq!(
+            sort(vec![vec!["b".into(), "a".into()].into()]).unwrap(),
+            Value::from(vec!["a".into(), "b".into()])
+        );
+        assert!(sort(vec![vec!["b".into(), 1.0.into()].into()]).is_err());
+
+        assert_eq!(
+            reverse(vec![numbers(&[1.0, 2.0])]).unwrap(),
+            numbers(&[2.0, 1.0])
+        );
+        assert_eq!(reverse(vec!["abc".into()]).unwrap(), Value::from("cba"));
+        assert_eq!(
+            concat(vec![numbers(&[1.0]), numbers(&[]), numbers(&[2.0])]).unwrap(),
+            numbers(&[1.0, 2.0])
+        );
+        assert!(concat(vec![numbers(&[1.0]), 2.0.into()]).is_err());
+        assert_eq!(
+            unique(vec![numbers(&[2.0, 1.0, 2.0, 3.0, 1.0])]).unwrap(),
+            numbers(&[2.0, 1.0, 3.0])
+        );
+    }
+
+    #[test]
+    fn test_matrix_functions() {
+        let matrix = |rows: &[&[f64]]| {
+            Value::Matrix(Matrix::from_rows(rows.iter().map(|row| row.to_vec()).collect()).unwrap())
+        };
+        let m = matrix(&[&[4.0, 7.0], &[2.0, 6.0]]);
+
+        assert_eq!(det(vec![m.clone()]).unwrap(), 10.0);
+        assert_eq!(trace(vec![m.clone()]).unwrap(), 10.0);
+        assert_eq!(rank(vec![m.clone()]).unwrap(), 2.0);
+        assert_eq!(
+            inv(vec![matrix(&[&[2.0, 1.0], &[1.0, 1.0]])]).unwrap(),
+            matrix(&[&[1.0, -1.0], &[-1.0, 2.0]])
+        );
+        assert_eq!(
+            transpose(vec![m.clone()]).unwrap(),
+            matrix(&[&[4.0, 2.0], &[7.0, 6.0]])
+        );
+        assert_eq!(
+            identity(vec![2.0.into()]).unwrap(),
+            matrix(&[&[1.0, 0.0], &[0.0, 1.0]])
+        );
+        assert!(identity(vec![0.0.into()]).is_err());
+        assert!(identity(vec![1.5.into()]).is_err());
+
+        match lu(vec![m.clone()]).unwrap() {
+            Value::List(parts) => assert_eq!(parts.len(), 3),
+            value => panic!("Expected a list, got {}", value),
+        }
+        match qr(vec![m.clone()]).unwrap() {
+            Value::List(parts) => assert_eq!(parts.len(), 2),
+            value => panic!("Expected a list, got {}", value),
+        }
+        assert_eq!(
+            eig(vec![matrix(&[&[2.0, 0.0], &[0.0, -1.0]])]).unwrap(),
+            Value::from(vec![(-1.0).into(), 2.0.into()])
+        );
+
+        assert_eq!(
+            det(vec![Value::from(vec![1.0.into()])])
+                .unwrap_err()
+                .to_string(),
+            "det expects a matrix, got list"
+        );
+        assert!(inv(vec![matrix(&[&[1.0, 2.0], &[2.0, 4.0]])]).is_err());
+    }
+
+    #[test]
+    fn test_complex_functions() {
+        let functions = load_functions();
+        let call = |name: &str, args: Vec<Value>| match functions.get(name).unwrap() {
+            Function::BuiltIn { function, .. } => function(args),
+            _ => panic!("{} should be built-in", name),
+        };
+        let complex = |re: f64| Value::Complex(Complex64::from(re));
+
+        assert_eq!(
+            call("sqrt", vec![complex(-4.0)]).unwrap(),
+            Value::from(Complex64::new(0.0, 2.0))
+        );
+        assert_eq!(call("sqrt", vec![complex(4.0)]).unwrap(), 2.0);
+        assert_eq!(
+            call("ln", vec![complex(-1.0)]).unwrap(),
+            Value::from(Complex64::new(0.0, std::f64::consts::PI))
+        );
+        assert_eq!(
+            call("pow", vec![Complex64::i().into(), 2.0.into()]).unwrap(),
+            -1.0
+        );
+        assert_eq!(
+            call("abs", vec![Complex64::new(3.0, 4.0).into()]).unwrap(),
+            5.0
+        );
+        match call("acos", vec![complex(2.0)]).unwrap() {
+            Value::Complex(c) => {
+                assert!(c.re.abs() < 1e-12 && (c.im.abs() - 1.3169578969248166).abs() < 1e-12)
+            }
+            value => panic!("Expected a complex number, got {}", value),
+        }
+        assert_eq!(
+            call("sum", vec![Complex64::i().into(), 1.0.into()]).unwrap(),
+            Value::from(Complex64::new(1.0, 1.0))
+        );
+        assert_eq!(
+            call("floor", vec![Complex64::i().into()])
+                .unwrap_err()
+                .to_string(),
+            "floor expects numbers, got complex"
+        );
+        assert!(call("max", vec![Complex64::i().into()]).is_err());
+        assert!(call("rad2deg", vec![Complex64::i().into()]).is_err());
+    }
+
+    #[test]
+    fn test_complex_parts() {
+        let z = Value::from(Complex64::new(3.0, -4.0));
+        assert_eq!(re(vec![z.clone()]).unwrap(), 3.0);
+        assert_eq!(im(vec![z.clone()]).unwrap(), -4.0);
+        assert_eq!(im(vec![2.0.into()]).unwrap(), 0.0);
+        assert_eq!(
+            conj(vec![z.clone()]).unwrap(),
+            Value::from(Complex64::new(3.0, 4.0))
+        );
+        assert_eq!(conj(vec![2.0.into()]).unwrap(), 2.0);
+        assert_eq!(arg(vec![(-1.0).into()]).unwrap(), std::f64::consts::PI);
+        assert!(re(vec!["a".into()]).is_err());
+    }
+
     #[test]
     fn test_factorial() {
         // Test case 1: factorial of 5
@@ -502,6 +1904,44 @@ mod tests {
         // Test case 2: factorial of 0
         let result = factorial(vec![0.0]).unwrap();
         assert_eq!(result, 1.0);
+
+        // Test case 3: negative and fractional numbers
+        assert!(factorial(vec![-1.0]).is_err());
+        assert!(factorial(vec![2.5]).is_err());
+
+        // Test case 4: results too large for floating point numbers keep every digit
+        let result = factorial(vec![25.0]).unwrap();
+        assert_eq!(result.to_string(), "15511210043330985984000000");
+        assert_eq!(factorial(vec![171.0]).unwrap().to_string().len(), 310);
+        assert_eq!(factorial(vec![1e9]).unwrap(), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_combinations() {
+        assert_eq!(ncr(vec![5.0, 2.0]).unwrap(), 10.0);
+        assert_eq!(ncr(vec![5.0, 6.0]).unwrap(), 0.0);
+        assert_eq!(npr(vec![5.0, 2.0]).unwrap(), 20.0);
+        assert_eq!(npr(vec![5.0, 6.0]).unwrap(), 0.0);
+        assert_eq!(
+            ncr(vec![100.0, 50.0]).unwrap().to_string(),
+            "100891344545564193334812497256"
+        );
+        assert_eq!(ncr(vec![1000.0, 500.0]).unwrap().to_string().len(), 300);
+        assert!(ncr(vec![5.0, -1.0]).is_err());
+        assert!(npr(vec![5.5, 1.0]).is_err());
+        // Floating point numbers above 2^53 may already be rounded
+        assert!(ncr(vec![1e20, 1.0]).is_err());
+    }
+
+    #[test]
+    fn test_double_factorial() {
+        // Test case 1: double factorial of 7 (7 * 5 * 3 * 1)
+        let result = double_factorial(vec![7.0]).unwrap();
+        assert_eq!(result, 105.0);
+
+        // Test case 2: double factorial of 0
+        let result = double_factorial(vec![0.0]).unwrap();
+        assert_eq!(result, 1.0);
     }
 
     #[test]
@@ -546,6 +1986,10 @@ mod tests {
         // Test case 2: sum of [-1, -2, -3, -4, -5]
         let result = sum(vec![-1.0, -2.0, -3.0, -4.0, -5.0]).unwrap();
         assert_eq!(result, -15.0);
+
+        // Test case 3: the sum of nothing is 0, not -0
+        let result = sum(vec![]).unwrap();
+        assert_eq!(Value::from(result).to_string(), "0");
     }
 
     #[test]
@@ -568,5 +2012,10 @@ mod tests {
         // Test case 2: median of [-1, -2, -3, -4, -5]
         let result = median(vec![-1.0, -2.0, -3.0, -4.0, -5.0]).unwrap();
         assert_eq!(result, -3.0);
+
+        // Test case 3: NaN doesn't panic and sorts last
+        let result = median(vec![f64::NAN, 1.0, 2.0]).unwrap();
+        assert_eq!(result, 2.0);
+        assert!(median(vec![f64::NAN, f64::NAN, 1.0]).unwrap().is_nan());
     }
 }
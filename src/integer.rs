@@ -0,0 +1,169 @@
+//! Whole numbers too large for floating point numbers to hold exactly.
+//!
+//! Floating point numbers only hold every whole number up to 2^53. Results above that, such as
+//! `2^512` or `factorial(100)`, are calculated as a [`BigInt`] when their inputs are whole
+//! numbers, so every digit is kept.
+
+use num_traits::{FromPrimitive, Signed, ToPrimitive, Zero};
+
+pub use num_bigint::BigInt;
+
+/// The largest whole number that floating point numbers can hold, along with every whole number
+/// below it.
+pub const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+/// The most bits an exact result can have, about 300,000 digits. Larger results are calculated
+/// with floating point numbers instead, so `10^1e9` doesn't try to build a number with a billion
+/// digits.
+pub const MAX_EXACT_BITS: u64 = 1_000_000;
+
+/// The largest number that `factorial`, `nCr` and `nPr` calculate exactly.
+pub const MAX_EXACT_INPUT: u64 = 10_000;
+
+/// Returns true if `n` is a whole number, which can be converted to a [`BigInt`].
+pub fn is_whole(n: f64) -> bool {
+    n.is_finite() && n.fract() == 0.0
+}
+
+/// Returns true if `n` is a whole number no larger than [`MAX_SAFE_INTEGER`], so it is exactly
+/// the number it stands for rather than a rounded result such as `1e300`.
+pub fn is_exact(n: f64) -> bool {
+    is_whole(n) && n.abs() <= MAX_SAFE_INTEGER
+}
+
+/// Converts a whole floating point number to the integer it holds. Returns `None` if `n` isn't a
+/// whole number.
+pub fn from_f64(n: f64) -> Option<BigInt> {
+    if !is_whole(n) {
+        return None;
+    }
+    BigInt::from_f64(n)
+}
+
+/// Converts an integer to the closest floating point number.
+pub fn to_f64(n: &BigInt) -> f64 {
+    n.to_f64().unwrap_or(f64::NAN)
+}
+
+/// Returns true if `n` is too large for a floating point number to hold exactly.
+pub fn is_big(n: &BigInt) -> bool {
+    n.abs() > BigInt::from(MAX_SAFE_INTEGER as u64)
+}
+
+/// Raises `base` to a whole `exponent` exactly. Returns `None` if the exponent is negative or the
+/// result would have more than [`MAX_EXACT_BITS`] bits.
+pub fn pow(base: &BigInt, exponent: &BigInt) -> Option<BigInt> {
+    let power = exponent.to_u64()?;
+    if base.bits().saturating_mul(power) > MAX_EXACT_BITS && base.abs() > BigInt::from(1) {
+        return None;
+    }
+    Some(num_traits::pow(base.clone(), power as usize))
+}
+
+/// Calculates the product of `count` numbers counting down from `n` by `step`, stopping early at
+/// 1. With a step of 1 and a count of `n` it is the factorial of `n`.
+pub fn falling_product(n: &BigInt, count: u64, step: u64) -> BigInt {
+    let one = BigInt::from(1);
+    (0..count)
+        .map(|i| n - i * step)
+        .take_while(|factor| factor > &one)
+        .fold(one.clone(), |product, factor| product * factor)
+}
+
+/// Calculates the number of ways to choose `k` items from `n` when their order doesn't matter.
+/// Returns `None` if the smaller of `k` and `n - k` is more than [`MAX_EXACT_INPUT`].
+pub fn binomial(n: &BigInt, k: &BigInt) -> Option<BigInt> {
+    if k > n {
+        return Some(BigInt::zero());
+    }
+    // Each partial product is itself a binomial coefficient, so the division is always exact
+    let k = k.min(&(n - k)).to_u64().filter(|&k| k <= MAX_EXACT_INPUT)?;
+    let start = n - k;
+    Some((1..=k).fold(BigInt::from(1), |result, i| result * (&start + i) / i))
+}
+
+/// Formats an integer with at most `max_digits` significant digits. Longer integers are rounded
+/// and written in scientific notation, such as `9.3326e157`.
+///
+/// # Example
+/// ```
+/// # use argon::integer::{self, BigInt};
+/// let n: BigInt = "123456789".parse().unwrap();
+/// assert_eq!(integer::format(&n, Some(4)), "1.235e8");
+/// assert_eq!(integer::format(&n, None), "123456789");
+/// ```
+pub fn format(n: &BigInt, max_digits: Option<usize>) -> String {
+    let digits = n.abs().to_string();
+    let max_digits = match max_digits {
+        Some(max_digits) if digits.len() > max_digits => max_digits.max(1),
+        _ => return n.to_string(),
+    };
+
+    // Round to `max_digits` digits, which can carry into an extra digit like 999 to 1000
+    let mut kept: BigInt = digits[..max_digits].parse().unwrap();
+    if digits.as_bytes()[max_digits] >= b'5' {
+        kept += 1;
+    }
+    let mut exponent = digits.len() - 1;
+    let mut kept = kept.to_string();
+    if kept.len() > max_digits {
+        kept.truncate(max_digits);
+        exponent += 1;
+    }
+
+    let sign = if n.is_negative() { "-" } else { "" };
+    let fraction = kept[1..].trim_end_matches('0');
+    let point = if fraction.is_empty() { "" } else { "." };
+    format!("{}{}{}{}e{}", sign, &kept[..1], point, fraction, exponent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_f64() {
+        assert_eq!(from_f64(2f64.powi(70)), Some(BigInt::from(2).pow(70)));
+        assert_eq!(from_f64(-3.0), Some(BigInt::from(-3)));
+        assert_eq!(from_f64(2.5), None);
+        assert_eq!(from_f64(f64::INFINITY), None);
+    }
+
+    #[test]
+    fn test_pow() {
+        assert_eq!(
+            pow(&BigInt::from(2), &BigInt::from(64)),
+            Some(BigInt::from(u64::MAX) + 1)
+        );
+        assert_eq!(pow(&BigInt::from(2), &BigInt::from(-1)), None);
+        assert_eq!(pow(&BigInt::from(2), &BigInt::from(2_000_000)), None);
+        assert_eq!(
+            pow(&BigInt::from(-1), &BigInt::from(2_000_001)),
+            Some(BigInt::from(-1))
+        );
+    }
+
+    #[test]
+    fn test_products() {
+        let n = |n: u64| BigInt::from(n);
+        assert_eq!(falling_product(&n(5), 5, 1), n(120));
+        assert_eq!(falling_product(&n(7), 4, 2), n(105));
+        assert_eq!(falling_product(&n(0), 0, 1), n(1));
+        assert_eq!(binomial(&n(5), &n(2)), Some(n(10)));
+        assert_eq!(binomial(&n(5), &n(6)), Some(BigInt::zero()));
+        assert_eq!(binomial(&n(60), &n(30)), Some(n(118_264_581_564_861_424)));
+        let big = n(u64::MAX) + 1;
+        assert_eq!(binomial(&big, &n(1)), Some(big.clone()));
+        assert_eq!(binomial(&big, &(&big - 1)), Some(big.clone()));
+        assert_eq!(binomial(&big, &n(1_000_000)), None);
+    }
+
+    #[test]
+    fn test_format() {
+        let n = BigInt::from(-9_996_000);
+        assert_eq!(format(&n, Some(3)), "-1e7");
+        assert_eq!(format(&n, Some(4)), "-9.996e6");
+        assert_eq!(format(&n, Some(7)), "-9996000");
+        assert_eq!(format(&BigInt::from(12), Some(0)), "1e1");
+    }
+}
@@ -1,49 +1,1662 @@
-use std::collections::HashMap;
-
-use crate::{functions::CalculatorFunction, lexer::Token, parser::Expr};
-use anyhow::{format_err, Result};
-
-/// Interprets the AST and returns the result. If an unexpected operator is found, it returns an error.
-pub fn interpret(
-    expr: &Expr,
-    variables: &mut HashMap<String, f64>,
-    functions: &mut HashMap<String, CalculatorFunction>,
-) -> Result<f64> {
-    match expr {
-        Expr::Number(n) => Ok(*n),
-        Expr::UnaryOp { op, rhs } => {
-            let rhs = interpret(rhs, variables, functions)?;
-            match op {
-                Token::Minus => Ok(-rhs),
-                _ => Err(format_err!("Unexpected unary operator")),
-            }
-        }
-        Expr::BinaryOp { lhs, op, rhs } => {
-            let lhs = interpret(lhs, variables, functions)?;
-            let rhs = interpret(rhs, variables, functions)?;
-            match op {
-                Token::Plus => Ok(lhs + rhs),
-                Token::Minus => Ok(lhs - rhs),
-                Token::Multiply => Ok(lhs * rhs),
-                Token::Divide => Ok(lhs / rhs),
-                Token::Power => Ok(lhs.powf(rhs)),
-                Token::Modulus => Ok(lhs % rhs),
-                _ => Err(format_err!("Unexpected binary operator")),
-            }
-        }
-        Expr::VariableDeclaration { name, value } => {
-            variables.insert(name.to_string(), *value);
-            Ok(*value)
-        }
-        Expr::Function { name, args } => {
+use std::cmp::Ordering;
+
+use num_complex::Complex64;
+
+use crate::{
+    decimal::Decimal,
+    environment::{Environment, Mode, ScopeKind},
+    error::{ArgonError, Result},
+    functions::{self, Function},
+    integer,
+    lexer::{Literal, Span, TokenKind},
+    matrix::Matrix,
+    number::Number,
+    parser::{Expr, ExprKind},
+    rational::{self, BigRational},
+    units::{Quantity, Unit},
+    value::Value,
+};
+
+/// Creates an error for an operator that can't be used where the parser put it.
+fn unsupported(op: &TokenKind) -> ArgonError {
+    ArgonError::UnsupportedOperator {
+        operator: format!("{:?}", op),
+        span: None,
+    }
+}
+
+/// Describes what a binary operator does, for type errors such as "cannot add list and bool".
+fn describe(op: &TokenKind) -> &'static str {
+    match op {
+        TokenKind::Plus => "add",
+        TokenKind::Minus => "subtract",
+        TokenKind::Multiply => "multiply",
+        TokenKind::Divide => "divide",
+        TokenKind::Power => "exponentiate",
+        TokenKind::Modulus => "take the modulus of",
+        TokenKind::To | TokenKind::In => "convert",
+        _ => "compare",
+    }
+}
+
+/// Returns the number in `value`, or a type error saying it can't be used for `operation`.
+fn expect_number(value: &Value, operation: &str) -> Result<f64> {
+    value.as_number().ok_or_else(|| {
+        ArgonError::type_error(format!("cannot {} {}", operation, value.type_name()))
+    })
+}
+
+/// Applies an arithmetic or comparison operator to two values.
+///
+/// Numbers support every operator, and complex numbers support arithmetic but can't be
+/// compared with `<` or `>`. Numbers of different kinds are converted by [`promote`] first.
+/// Strings can be joined with `+` and compared alphabetically. Any two values can be checked for
+/// equality, and values of different types are never equal.
+///
+/// Other operators work element-wise on lists: `[1, 2] + [3, 4]` is `[4, 6]`, and a single value
+/// is applied to every item, so `[1, 2] * 2` is `[2, 4]`. Matrices are handled by [`matrix_op`]
+/// and quantities with units by [`quantity_op`].
+fn binary_op(op: &TokenKind, lhs: Value, rhs: Value) -> Result<Value> {
+    let (lhs, rhs) = promote(lhs, rhs);
+    match (op, lhs, rhs) {
+        (_, lhs, rhs) if is_quantity_op(op, &lhs, &rhs) => quantity_op(op, lhs, rhs),
+        (_, Value::Integer(a), Value::Integer(b)) => arithmetic(op, *a, *b),
+        (_, Value::Rational(a), Value::Rational(b)) => arithmetic(op, *a, *b),
+        (_, Value::Decimal(a), Value::Decimal(b)) => arithmetic(op, *a, *b),
+        (TokenKind::EqualEqual, lhs, rhs) => Ok(Value::Bool(lhs == rhs)),
+        (TokenKind::NotEqual, lhs, rhs) => Ok(Value::Bool(lhs != rhs)),
+        // Whole numbers whose result is too large for a floating point number keep every digit
+        (_, Value::Number(a), Value::Number(b)) if overflows(op, a, b) => arithmetic(
+            op,
+            integer::from_f64(a).unwrap(),
+            integer::from_f64(b).unwrap(),
+        ),
+        (_, Value::Number(a), Value::Number(b)) => arithmetic(op, a, b),
+        (_, Value::Complex(a), b @ Value::Number(_)) => arithmetic(op, a, b.as_complex().unwrap()),
+        (_, a @ Value::Number(_), Value::Complex(b)) => arithmetic(op, a.as_complex().unwrap(), b),
+        (_, Value::Complex(a), Value::Complex(b)) => arithmetic(op, a, b),
+        (TokenKind::Plus, Value::String(a), Value::String(b)) => Ok(Value::String(a + &b)),
+        (TokenKind::Less, Value::String(a), Value::String(b)) => Ok(Value::Bool(a < b)),
+        (TokenKind::LessEqual, Value::String(a), Value::String(b)) => Ok(Value::Bool(a <= b)),
+        (TokenKind::Greater, Value::String(a), Value::String(b)) => Ok(Value::Bool(a > b)),
+        (TokenKind::GreaterEqual, Value::String(a), Value::String(b)) => Ok(Value::Bool(a >= b)),
+        (_, lhs @ Value::Matrix(_), rhs) | (_, lhs, rhs @ Value::Matrix(_)) => {
+            matrix_op(op, lhs, rhs)
+        }
+        (_, Value::List(a), Value::List(b)) => {
+            if a.len() != b.len() {
+                return Err(ArgonError::type_error(format!(
+                    "cannot {} lists of length {} and {}",
+                    describe(op),
+                    a.len(),
+                    b.len()
+                )));
+            }
+            a.into_iter()
+                .zip(b)
+                .map(|(a, b)| binary_op(op, a, b))
+                .collect::<Result<Vec<_>>>()
+                .map(Value::List)
+        }
+        (_, Value::List(a), rhs) => a
+            .into_iter()
+            .map(|a| binary_op(op, a, rhs.clone()))
+            .collect::<Result<Vec<_>>>()
+            .map(Value::List),
+        (_, lhs, Value::List(b)) => b
+            .into_iter()
+            .map(|b| binary_op(op, lhs.clone(), b))
+            .collect::<Result<Vec<_>>>()
+            .map(Value::List),
+        (_, lhs, rhs) => Err(ArgonError::type_error(format!(
+            "cannot {} {} and {}",
+            describe(op),
+            lhs.type_name(),
+            rhs.type_name()
+        ))),
+    }
+}
+
+/// Converts two numbers of different kinds to the same kind, so they can be combined.
+///
+/// Large integers and exact fractions combine exactly with each other, and large integers also
+/// combine exactly with whole floating point numbers. Decimals combine with all of these by
+/// converting them to a decimal with the same precision. Anything else combined with a floating
+/// point number, a complex number or a matrix is converted to a floating point number.
+fn promote(lhs: Value, rhs: Value) -> (Value, Value) {
+    let whole = |n: f64| Value::Integer(Box::new(integer::from_f64(n).unwrap()));
+    let float = |value: Value| Value::Number(value.as_number().unwrap());
+    let is_float = |value: &Value| {
+        matches!(
+            value,
+            Value::Number(_) | Value::Complex(_) | Value::Matrix(_)
+        )
+    };
+    match (lhs, rhs) {
+        (Value::Decimal(a), rhs) => match rhs.to_decimal(a.precision()) {
+            Some(b) => (Value::Decimal(a), b.into()),
+            None if is_float(&rhs) => (float(Value::Decimal(a)), rhs),
+            None => (Value::Decimal(a), rhs),
+        },
+        (lhs, Value::Decimal(b)) => match lhs.to_decimal(b.precision()) {
+            Some(a) => (a.into(), Value::Decimal(b)),
+            None if is_float(&lhs) => (lhs, float(Value::Decimal(b))),
+            None => (lhs, Value::Decimal(b)),
+        },
+        (Value::Integer(a), Value::Rational(b)) => {
+            (BigRational::from_integer(*a).into(), Value::Rational(b))
+        }
+        (Value::Rational(a), Value::Integer(b)) => {
+            (Value::Rational(a), BigRational::from_integer(*b).into())
+        }
+        (Value::Integer(a), Value::Number(b)) if integer::is_exact(b) => {
+            (Value::Integer(a), whole(b))
+        }
+        (Value::Number(a), Value::Integer(b)) if integer::is_exact(a) => {
+            (whole(a), Value::Integer(b))
+        }
+        (
+            lhs @ (Value::Integer(_) | Value::Rational(_)),
+            rhs @ (Value::Number(_) | Value::Complex(_) | Value::Matrix(_)),
+        ) => (float(lhs), rhs),
+        (
+            lhs @ (Value::Number(_) | Value::Complex(_) | Value::Matrix(_)),
+            rhs @ (Value::Integer(_) | Value::Rational(_)),
+        ) => (lhs, float(rhs)),
+        other => other,
+    }
+}
+
+/// Returns true if applying `op` to two exact whole numbers gives a result too large for a
+/// floating point number to hold exactly. Numbers that are already rounded, such as `1e300`,
+/// stay floating point numbers.
+fn overflows(op: &TokenKind, a: f64, b: f64) -> bool {
+    let result = match op {
+        TokenKind::Plus => a + b,
+        TokenKind::Minus => a - b,
+        TokenKind::Multiply => a * b,
+        TokenKind::Power if b >= 0.0 => a.powf(b),
+        _ => return false,
+    };
+    integer::is_exact(a) && integer::is_exact(b) && result.abs() >= integer::MAX_SAFE_INTEGER
+}
+
+/// Applies an arithmetic or comparison operator to two numbers of the same kind. Results that
+/// can't be that kind of number, such as `7 / 2` for large integers or `1/0` for exact fractions,
+/// are calculated with floating point numbers instead.
+fn arithmetic<N: Number>(op: &TokenKind, a: N, b: N) -> Result<Value> {
+    let cannot = || ArgonError::type_error(format!("cannot {} {}", describe(op), N::NAME));
+    let result = match op {
+        TokenKind::EqualEqual => return Ok(Value::Bool(a == b)),
+        TokenKind::NotEqual => return Ok(Value::Bool(a != b)),
+        TokenKind::Less | TokenKind::LessEqual | TokenKind::Greater | TokenKind::GreaterEqual => {
+            if !N::ORDERED {
+                return Err(cannot());
+            }
+            let ordering = a.compare(&b);
+            return Ok(Value::Bool(match op {
+                TokenKind::Less => ordering == Some(Ordering::Less),
+                TokenKind::LessEqual => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                TokenKind::Greater => ordering == Some(Ordering::Greater),
+                _ => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+            }));
+        }
+        TokenKind::Plus => Some(a.add(&b)),
+        TokenKind::Minus => Some(a.sub(&b)),
+        TokenKind::Multiply => Some(a.mul(&b)),
+        TokenKind::Divide => a.try_div(&b),
+        TokenKind::Modulus => a.try_rem(&b),
+        TokenKind::Power => a.try_pow(&b),
+        _ => return Err(unsupported(op)),
+    };
+    match (result, a.to_f64(), b.to_f64()) {
+        (Some(result), _, _) => Ok(result.into()),
+        (None, Some(a), Some(b)) => arithmetic(op, a, b),
+        (None, _, _) => Err(cannot()),
+    }
+}
+
+/// Applies an arithmetic operator to a matrix and another value.
+///
+/// `*` multiplies matrices, and multiplying a matrix by a list of numbers treats the list as a
+/// vector. `+` and `-` work entry by entry on matrices of the same size, and a number can be
+/// added to, subtracted from, multiplied with or divided into every entry. `^` raises a square
+/// matrix to a whole power.
+fn matrix_op(op: &TokenKind, lhs: Value, rhs: Value) -> Result<Value> {
+    let vector = |items: &[Value]| {
+        items
+            .iter()
+            .map(Value::as_number)
+            .collect::<Option<Vec<_>>>()
+    };
+    let to_list = |matrix: Matrix| Value::List(matrix.values().iter().map(|&x| x.into()).collect());
+
+    match (op, &lhs, &rhs) {
+        (TokenKind::Multiply, Value::Matrix(a), Value::Matrix(b)) => a.mul(b).map(Value::Matrix),
+        (TokenKind::Plus, Value::Matrix(a), Value::Matrix(b)) => {
+            a.zip_with(b, "add", |x, y| x + y).map(Value::Matrix)
+        }
+        (TokenKind::Minus, Value::Matrix(a), Value::Matrix(b)) => {
+            a.zip_with(b, "subtract", |x, y| x - y).map(Value::Matrix)
+        }
+        (TokenKind::Multiply, Value::Matrix(a), Value::List(items)) if vector(items).is_some() => {
+            let b = Matrix::from_rows(
+                vector(items)
+                    .unwrap()
+                    .into_iter()
+                    .map(|x| vec![x])
+                    .collect(),
+            );
+            Ok(to_list(a.mul(&b?)?))
+        }
+        (TokenKind::Multiply, Value::List(items), Value::Matrix(b)) if vector(items).is_some() => {
+            let a = Matrix::from_rows(vec![vector(items).unwrap()]);
+            Ok(to_list(a?.mul(b)?))
+        }
+        (TokenKind::Power, Value::Matrix(a), Value::Number(n)) => {
+            if n.fract() != 0.0 {
+                return Err(ArgonError::type_error(format!(
+                    "matrix powers must be whole numbers, got {}",
+                    n
+                )));
+            }
+            a.pow(*n as i64).map(Value::Matrix)
+        }
+        (TokenKind::Plus, Value::Matrix(a), Value::Number(n)) => Ok(a.map(|x| x + n).into()),
+        (TokenKind::Minus, Value::Matrix(a), Value::Number(n)) => Ok(a.map(|x| x - n).into()),
+        (TokenKind::Multiply, Value::Matrix(a), Value::Number(n)) => Ok(a.map(|x| x * n).into()),
+        (TokenKind::Divide, Value::Matrix(a), Value::Number(n)) => Ok(a.map(|x| x / n).into()),
+        (TokenKind::Plus, Value::Number(n), Value::Matrix(b)) => Ok(b.map(|x| n + x).into()),
+        (TokenKind::Minus, Value::Number(n), Value::Matrix(b)) => Ok(b.map(|x| n - x).into()),
+        (TokenKind::Multiply, Value::Number(n), Value::Matrix(b)) => Ok(b.map(|x| n * x).into()),
+        _ => Err(ArgonError::type_error(format!(
+            "cannot {} {} and {}",
+            describe(op),
+            lhs.type_name(),
+            rhs.type_name()
+        ))),
+    }
+}
+
+/// Returns true if `op` applied to `lhs` and `rhs` is handled by [`quantity_op`], because one
+/// of them has a unit or `op` converts between units. Lists are left to work element-wise.
+fn is_quantity_op(op: &TokenKind, lhs: &Value, rhs: &Value) -> bool {
+    let is_list = |value: &Value| matches!(value, Value::List(_));
+    let is_quantity = |value: &Value| matches!(value, Value::Quantity(_));
+    let converts = matches!(op, TokenKind::To | TokenKind::In);
+    !is_list(lhs) && !is_list(rhs) && (converts || is_quantity(lhs) || is_quantity(rhs))
+}
+
+/// Applies an operator to a quantity with a unit and another value, where numbers are treated as
+/// quantities without a unit.
+///
+/// `*` and `/` combine the units, so `9.81 m/s^2 * 3 s` is in `m/s`, and `^` raises the unit to
+/// a whole power. `+`, `-`, `%` and comparisons need both sides to measure the same kind of
+/// thing, and give the result in the unit on the left, so `5 km + 300 m` is `5.3 km`. `to` and
+/// `in` convert the left hand side to the unit on the right, such as `60 mph to km/h`.
+fn quantity_op(op: &TokenKind, lhs: Value, rhs: Value) -> Result<Value> {
+    let quantity = |value: &Value| match value {
+        Value::Quantity(q) => Some((**q).clone()),
+        value => value.as_number().map(Quantity::from),
+    };
+    let (a, b) = match (quantity(&lhs), quantity(&rhs)) {
+        (Some(a), Some(b)) => (a, b),
+        _ => {
+            return Err(ArgonError::type_error(format!(
+                "cannot {} {} and {}",
+                describe(op),
+                lhs.type_name(),
+                rhs.type_name()
+            )))
+        }
+    };
+    let name = |q: &Quantity| {
+        if q.unit.is_dimensionless() {
+            "number".to_string()
+        } else {
+            q.unit.to_string()
+        }
+    };
+    let mismatch = || {
+        ArgonError::dimension(format!(
+            "cannot {} {} and {}",
+            describe(op),
+            name(&a),
+            name(&b)
+        ))
+    };
+
+    match op {
+        TokenKind::Multiply => Ok(a.mul(&b).into()),
+        TokenKind::Divide => Ok(a.mul(&b.recip()).into()),
+        TokenKind::Power if !b.unit.is_dimensionless() => Err(mismatch()),
+        TokenKind::Power => a.pow(b.value).map(Value::from).ok_or_else(|| {
+            ArgonError::dimension(format!("cannot raise {} to the power {}", a.unit, b.value))
+        }),
+        TokenKind::To | TokenKind::In => {
+            if !matches!(rhs, Value::Quantity(_)) || b.value != 1.0 {
+                return Err(ArgonError::type_error(format!(
+                    "cannot convert to {}, expected a unit",
+                    rhs
+                )));
+            }
+            a.convert(&b.unit).map(Value::from).ok_or_else(|| {
+                ArgonError::dimension(format!("cannot convert {} to {}", name(&a), b.unit))
+            })
+        }
+        TokenKind::EqualEqual | TokenKind::NotEqual => {
+            let equal = a.convert(&b.unit).is_some_and(|a| a.value == b.value);
+            Ok(Value::Bool(equal == (op == &TokenKind::EqualEqual)))
+        }
+        TokenKind::Less | TokenKind::LessEqual | TokenKind::Greater | TokenKind::GreaterEqual => {
+            let b = b.convert(&a.unit).ok_or_else(mismatch)?;
+            binary_op(op, Value::Number(a.value), Value::Number(b.value))
+        }
+        TokenKind::Plus | TokenKind::Minus | TokenKind::Modulus => {
+            let b = b.convert_interval(&a.unit).ok_or_else(mismatch)?;
+            let value = match op {
+                TokenKind::Plus => a.value + b.value,
+                TokenKind::Minus => a.value - b.value,
+                _ => a.value % b.value,
+            };
+            Ok(Quantity::new(value, a.unit).into())
+        }
+        _ => Err(unsupported(op)),
+    }
+}
+
+/// Evaluates the unit on the right of `to` or `in`. Names are looked up as units before
+/// variables and functions, so `5 h to min` converts to minutes rather than calling `min`.
+fn interpret_unit(expr: &Expr, environment: &mut Environment) -> Result<Value> {
+    match &expr.kind {
+        ExprKind::Variable(name) => match Unit::parse(name) {
+            Some(unit) => Ok(Quantity::new(1.0, unit).into()),
+            None => interpret(expr, environment),
+        },
+        ExprKind::BinaryOp { lhs, op, rhs }
+            if matches!(
+                op,
+                TokenKind::Multiply | TokenKind::Divide | TokenKind::Power
+            ) =>
+        {
+            let lhs = interpret_unit(lhs, environment)?;
+            let rhs = match op {
+                TokenKind::Power => interpret(rhs, environment)?,
+                _ => interpret_unit(rhs, environment)?,
+            };
+            binary_op(op, lhs, rhs).map_err(|e| e.with_span(expr.span))
+        }
+        _ => interpret(expr, environment),
+    }
+}
+
+/// Whether every name in the unit after a number is a unit rather than a variable, so the number
+/// and unit are a quantity. The power a unit is raised to can be any expression.
+fn is_unit(unit: &Expr, environment: &Environment) -> bool {
+    match &unit.kind {
+        ExprKind::Variable(name) => environment.get(name).is_none() && Unit::parse(name).is_some(),
+        ExprKind::BinaryOp {
+            lhs,
+            op: TokenKind::Power,
+            ..
+        } => is_unit(lhs, environment),
+        ExprKind::BinaryOp {
+            lhs,
+            op: TokenKind::Multiply | TokenKind::Divide,
+            rhs,
+        } => is_unit(lhs, environment) && is_unit(rhs, environment),
+        _ => false,
+    }
+}
+
+/// Evaluates one name in the unit after a number, with the power it is raised to. A variable wins
+/// over a unit with the same name, but a unit wins over a function, so `30 min` is half an hour.
+fn interpret_unit_name(expr: &Expr, environment: &mut Environment) -> Result<Value> {
+    match &expr.kind {
+        ExprKind::Variable(name) if environment.get(name).is_none() => match Unit::parse(name) {
+            Some(unit) => Ok(Quantity::new(1.0, unit).into()),
+            None => interpret(expr, environment),
+        },
+        ExprKind::BinaryOp {
+            lhs,
+            op: TokenKind::Power,
+            rhs,
+        } => {
+            let lhs = interpret_unit_name(lhs, environment)?;
+            let rhs = interpret(rhs, environment)?;
+            binary_op(&TokenKind::Power, lhs, rhs).map_err(|e| e.with_span(expr.span))
+        }
+        _ => interpret(expr, environment),
+    }
+}
+
+/// Multiplies `value` by the unit after a number, one name at a time like an explicit `*`, so
+/// `9.81 m/s^2` is `(9.81 * m) / s^2` and `2x/y` is `(2 * x) / y`.
+fn multiply_by(value: Value, unit: &Expr, environment: &mut Environment) -> Result<Value> {
+    let (value, op, rhs) = match &unit.kind {
+        ExprKind::BinaryOp {
+            lhs,
+            op: op @ (TokenKind::Multiply | TokenKind::Divide),
+            rhs,
+        } => (multiply_by(value, lhs, environment)?, op, &**rhs),
+        _ => (value, &TokenKind::Multiply, unit),
+    };
+    let rhs = interpret_unit_name(rhs, environment)?;
+    binary_op(op, value, rhs).map_err(|e| e.with_span(unit.span))
+}
+
+/// Applies a built-in function that also makes sense for a quantity with a unit, so `abs(-5 m)` is
+/// `5 m` and `sqrt(4 m^2)` is `2 m`. Returns `None` for every other function, which treats the
+/// quantity like any other value it doesn't accept.
+fn quantity_function(name: &str, args: &[Value]) -> Option<Result<Value>> {
+    let root = |q: &Quantity, n: f64, description: String| {
+        q.pow(1.0 / n).map(Value::from).ok_or_else(|| {
+            ArgonError::dimension(format!("cannot take {} of {}", description, q.unit))
+        })
+    };
+    match (name, args) {
+        ("abs", [Value::Quantity(q)]) => {
+            Some(Ok(Quantity::new(q.value.abs(), q.unit.clone()).into()))
+        }
+        ("sqrt", [Value::Quantity(q)]) => Some(root(q, 2.0, "the square root".to_string())),
+        ("root", [Value::Quantity(q), n]) => {
+            let n = n.as_number()?;
+            Some(root(q, n, format!("root {}", n)))
+        }
+        _ => None,
+    }
+}
+
+/// Negates a number, or every number in a list or matrix.
+fn negate(value: Value) -> Result<Value> {
+    match value {
+        Value::Quantity(q) => Ok(Quantity::new(-q.value, q.unit).into()),
+        Value::Integer(n) => Ok(Value::Integer(Box::new(-*n))),
+        Value::Rational(r) => Ok(Value::from(-*r)),
+        Value::Decimal(d) => Ok(Value::from(-&*d)),
+        Value::Complex(c) => Ok(Value::Complex(-c)),
+        Value::Matrix(matrix) => Ok(matrix.map(|x| -x).into()),
+        Value::List(items) => items
+            .into_iter()
+            .map(negate)
+            .collect::<Result<Vec<_>>>()
+            .map(Value::List),
+        value => Ok(Value::Number(-expect_number(&value, "negate")?)),
+    }
+}
+
+/// Returns the whole number in `value`, or a type error if it can't be used as an index.
+fn expect_index(value: &Value) -> Result<i64> {
+    match value.as_number() {
+        Some(n) if n.fract() == 0.0 => Ok(n as i64),
+        _ => Err(ArgonError::type_error(format!(
+            "indices must be whole numbers, got {}",
+            value
+        ))),
+    }
+}
+
+/// Returns the item of `target` at `index`. Negative indices count back from the end, so `-1` is
+/// the last item.
+fn index_value(target: Value, index: &Value) -> Result<Value> {
+    let index = expect_index(index)?;
+    let position = |len: usize| {
+        let position = if index < 0 { index + len as i64 } else { index };
+        if (0..len as i64).contains(&position) {
+            Ok(position as usize)
+        } else {
+            Err(ArgonError::IndexOutOfRange {
+                index,
+                len,
+                span: None,
+            })
+        }
+    };
+
+    match target {
+        Value::List(mut items) => Ok(items.swap_remove(position(items.len())?)),
+        // Indexing a matrix gives one of its rows
+        Value::Matrix(matrix) => {
+            let mut rows = matrix.to_rows();
+            let row = rows.swap_remove(position(rows.len())?);
+            Ok(Value::List(row.into_iter().map(Value::Number).collect()))
+        }
+        Value::String(s) => {
+            let chars: Vec<char> = s.chars().collect();
+            Ok(Value::String(chars[position(chars.len())?].to_string()))
+        }
+        target => Err(ArgonError::type_error(format!(
+            "cannot index {}",
+            target.type_name()
+        ))),
+    }
+}
+
+/// Returns the items of `target` from `start` up to but not including `end`. Like indices, the
+/// bounds can be negative, and bounds past either end of the list are moved back inside it.
+fn slice_value(target: Value, start: Option<Value>, end: Option<Value>) -> Result<Value> {
+    let start = start.as_ref().map(expect_index).transpose()?;
+    let end = end.as_ref().map(expect_index).transpose()?;
+    let bounds = |len: usize| {
+        let clamp = |bound: i64| {
+            let bound = if bound < 0 { bound + len as i64 } else { bound };
+            bound.clamp(0, len as i64) as usize
+        };
+        let start = start.map_or(0, clamp);
+        (start, end.map_or(len, clamp).max(start))
+    };
+
+    match target {
+        Value::List(items) => {
+            let (start, end) = bounds(items.len());
+            Ok(Value::List(items[start..end].to_vec()))
+        }
+        // Slicing a matrix gives a matrix of the chosen rows
+        Value::Matrix(matrix) => {
+            let rows = matrix.to_rows();
+            let (start, end) = bounds(rows.len());
+            Ok(Matrix::from_rows(rows[start..end].to_vec())
+                .map_or(Value::List(Vec::new()), Value::Matrix))
+        }
+        Value::String(s) => {
+            let chars: Vec<char> = s.chars().collect();
+            let (start, end) = bounds(chars.len());
+            Ok(Value::String(chars[start..end].iter().collect()))
+        }
+        target => Err(ArgonError::type_error(format!(
+            "cannot slice {}",
+            target.type_name()
+        ))),
+    }
+}
+
+/// Interprets the AST and returns the result. If an unexpected operator is found, or an operator
+/// is used with values of the wrong type, it returns an error.
+///
+/// Function calls and `let` expressions bind their variables in a new scope of `environment`,
+/// which is removed once they have been evaluated.
+///
+/// Function declarations are stored in the environment's functions and evaluate to `none`.
+pub fn interpret(expr: &Expr, environment: &mut Environment) -> Result<Value> {
+    match &expr.kind {
+        ExprKind::Number(n) => {
+            // A variable named like the SI suffix wins, so after `k = 5`, `2k` is `2 * k`
+            let variable = n
+                .suffix
+                .and_then(|suffix| environment.get(&suffix.to_string()));
+            match variable {
+                Some(variable) => binary_op(
+                    &TokenKind::Multiply,
+                    number(n, environment.mode()),
+                    variable,
+                )
+                .map_err(|e| e.with_span(expr.span)),
+                None => Ok(number(&n.scaled(), environment.mode())),
+            }
+        }
+        ExprKind::Bool(b) => Ok(Value::Bool(*b)),
+        ExprKind::String(s) => Ok(Value::String(s.clone())),
+        ExprKind::None => Ok(Value::None),
+        ExprKind::Variable(name) => match environment.get(name) {
+            Some(value) => Ok(value),
+            // Naming a function without calling it refers to the function itself
+            None if environment.functions().get(name).is_some() => {
+                Ok(Value::Function(name.clone()))
+            }
+            None => Err(ArgonError::UndefinedVariable {
+                name: name.clone(),
+                span: Some(expr.span),
+            }),
+        },
+        ExprKind::UnaryOp { op, rhs } => {
+            let rhs = interpret(rhs, environment)?;
+            unary_op(op, rhs).map_err(|e| e.with_span(expr.span))
+        }
+        ExprKind::PostfixOp { lhs, op } => {
+            let lhs = interpret(lhs, environment)?;
+            postfix_op(op, lhs).map_err(|e| e.with_span(expr.span))
+        }
+        ExprKind::BinaryOp { lhs, op, rhs } => {
+            interpret_binary(lhs, op, rhs, expr.span, environment)
+        }
+        ExprKind::Quantity { value, unit } => {
+            let value = interpret(value, environment)?;
+            multiply_by(value, unit, environment)
+        }
+        ExprKind::List(items) => {
+            let items = items
+                .iter()
+                .map(|item| interpret(item, environment))
+                .collect::<Result<Vec<_>>>()?;
+            // A list of rows of numbers is a matrix
+            Ok(Matrix::from_values(&items).map_or(Value::List(items), Value::Matrix))
+        }
+        ExprKind::Index { target, index } => {
+            let target = interpret(target, environment)?;
+            let index = interpret(index, environment)?;
+            index_value(target, &index).map_err(|e| e.with_span(expr.span))
+        }
+        ExprKind::Slice { target, start, end } => {
+            let target = interpret(target, environment)?;
+            let start = start
+                .as_ref()
+                .map(|start| interpret(start, environment))
+                .transpose()?;
+            let end = end
+                .as_ref()
+                .map(|end| interpret(end, environment))
+                .transpose()?;
+            slice_value(target, start, end).map_err(|e| e.with_span(expr.span))
+        }
+        ExprKind::Conditional {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            if interpret(condition, environment)?.is_truthy() {
+                interpret(then_branch, environment)
+            } else {
+                interpret(else_branch, environment)
+            }
+        }
+        ExprKind::VariableDeclaration { name, value } => {
+            if environment.is_constant(name) {
+                return Err(ArgonError::ConstantAssignment {
+                    name: name.clone(),
+                    span: Some(expr.span),
+                });
+            }
+            let value = interpret(value, environment)?;
+            environment.define(name, value.clone());
+            Ok(value)
+        }
+        ExprKind::Function { name, args } => {
             let args = args
                 .iter()
-                .map(|arg| interpret(arg, variables, functions))
-                .collect::<Result<Vec<f64>>>()?;
+                .map(|arg| interpret(arg, environment))
+                .collect::<Result<Vec<Value>>>()?;
+            call_function(name, args, expr.span, environment)
+        }
+        ExprKind::Let { bindings, body } => {
+            environment.push_scope(ScopeKind::Let)?;
+            let result = interpret_let(bindings, body, environment);
+            environment.pop_scope();
+            result
+        }
+        ExprKind::FunctionDeclaration { name, params, body } => {
+            declare_function(name, params, body, expr.span, environment)
+        }
+    }
+}
+
+/// Returns the value of a number literal, which is an exact fraction in exact mode and a decimal
+/// when a precision is set. Both are read from the digits the literal is written with, and so are
+/// whole number literals too large for a floating point number, so no digit is lost.
+fn number(literal: &Literal, mode: Mode) -> Value {
+    let n = literal.value;
+    let exact = || literal.to_rational().or_else(|| rational::from_f64(n));
+    match mode {
+        Mode::Exact => exact().map_or(Value::Number(n), Value::from),
+        Mode::Decimal(precision) => exact().map_or(Value::Number(n), |r| {
+            Decimal::from_rational(&r, precision).into()
+        }),
+        _ => literal.to_integer().map_or(Value::Number(n), Value::from),
+    }
+}
+
+/// Applies a prefix operator such as `-` to a value.
+fn unary_op(op: &TokenKind, rhs: Value) -> Result<Value> {
+    match op {
+        TokenKind::Minus => negate(rhs),
+        TokenKind::Not => Ok(Value::Bool(!rhs.is_truthy())),
+        _ => Err(unsupported(op)),
+    }
+}
+
+/// Applies a postfix operator such as `!` or `%` to a value. The factorial or percentage of an
+/// exact fraction or a decimal is a number of the same kind.
+fn postfix_op(op: &TokenKind, lhs: Value) -> Result<Value> {
+    let result = match op {
+        TokenKind::Not => functions::factorial(vec![expect_number(&lhs, "take the factorial of")?]),
+        TokenKind::DoubleFactorial => {
+            functions::double_factorial(vec![expect_number(&lhs, "take the double factorial of")?])
+        }
+        TokenKind::Percent => match &lhs {
+            Value::Rational(r) => Ok(Value::from(&**r / BigRational::from_integer(100.into()))),
+            Value::Decimal(d) => Ok(Value::from(
+                d.checked_div(&Decimal::from_integer(100.into(), d.precision()))
+                    .unwrap(),
+            )),
+            lhs => Ok(Value::Number(
+                expect_number(lhs, "take a percentage of")? / 100.0,
+            )),
+        },
+        _ => Err(unsupported(op)),
+    }?;
+    match lhs {
+        Value::Rational(_) => Ok(result.into_exact()),
+        Value::Decimal(d) => Ok(result.to_decimal(d.precision()).map_or(result, Value::from)),
+        _ => Ok(result),
+    }
+}
+
+/// Interprets a binary operation. `&&` and `||` only evaluate their right hand side when they
+/// need to. `span` is the span of the whole operation, which errors from the operator point at.
+fn interpret_binary(
+    lhs: &Expr,
+    op: &TokenKind,
+    rhs: &Expr,
+    span: Span,
+    environment: &mut Environment,
+) -> Result<Value> {
+    match op {
+        TokenKind::And => {
+            let lhs = interpret(lhs, environment)?.is_truthy();
+            return Ok(Value::Bool(lhs && interpret(rhs, environment)?.is_truthy()));
+        }
+        TokenKind::Or => {
+            let lhs = interpret(lhs, environment)?.is_truthy();
+            return Ok(Value::Bool(lhs || interpret(rhs, environment)?.is_truthy()));
+        }
+        _ => (),
+    }
+
+    // A number followed by a variable binds like `*`, so after `h = 2`, `1/2h` is `(1/2) * h`
+    if let (TokenKind::Divide | TokenKind::Modulus, ExprKind::Quantity { value, unit }) =
+        (op, &rhs.kind)
+    {
+        if !is_unit(unit, environment) {
+            let lhs = interpret_binary(lhs, op, value, span, environment)?;
+            return multiply_by(lhs, unit, environment);
+        }
+    }
+
+    // Adding or subtracting a percentage is relative to the left hand side, `200 + 10%` is 220
+    let is_percent = matches!(
+        rhs.kind,
+        ExprKind::PostfixOp {
+            op: TokenKind::Percent,
+            ..
+        }
+    );
+    let lhs = interpret(lhs, environment)?;
+    let rhs = match op {
+        TokenKind::To | TokenKind::In => interpret_unit(rhs, environment)?,
+        _ => interpret(rhs, environment)?,
+    };
+
+    match (&lhs, op, &rhs) {
+        (
+            Value::Number(_)
+            | Value::Integer(_)
+            | Value::Rational(_)
+            | Value::Decimal(_)
+            | Value::Complex(_)
+            | Value::Quantity(_)
+            | Value::List(_)
+            | Value::Matrix(_),
+            TokenKind::Plus | TokenKind::Minus,
+            _,
+        ) if is_percent => binary_op(&TokenKind::Multiply, lhs.clone(), rhs)
+            .and_then(|change| binary_op(op, lhs, change)),
+        // In complex mode, a fractional power of a negative number is complex instead of NaN
+        (Value::Number(a), TokenKind::Power, Value::Number(b))
+            if environment.mode() == Mode::Complex && *a < 0.0 && b.fract() != 0.0 =>
+        {
+            binary_op(op, Value::Complex(Complex64::from(*a)), rhs)
+        }
+        _ => binary_op(op, lhs, rhs),
+    }
+    .map_err(|e| e.with_span(span))
+}
+
+/// Declares a user defined function, returning an error if it would replace a built-in function
+/// or one of its parameters is a constant.
+fn declare_function(
+    name: &str,
+    params: &[String],
+    body: &Expr,
+    span: Span,
+    environment: &mut Environment,
+) -> Result<Value> {
+    if let Some(Function::BuiltIn { .. }) = environment.functions().get(name) {
+        return Err(ArgonError::BuiltInRedefinition {
+            name: name.to_string(),
+            span: Some(span),
+        });
+    }
+    if let Some(param) = params.iter().find(|param| environment.is_constant(param)) {
+        return Err(ArgonError::ConstantAssignment {
+            name: param.clone(),
+            span: Some(span),
+        });
+    }
+    environment.functions_mut().insert(
+        name.to_string(),
+        Function::UserDefined {
+            params: params.to_vec(),
+            body: body.clone(),
+        },
+    );
+    Ok(Value::None)
+}
+
+/// Calls the function called `name` with the already evaluated `args`. `span` is the span of the
+/// call, which errors raised by the function point at.
+fn call_function(
+    name: &str,
+    args: Vec<Value>,
+    span: Span,
+    environment: &mut Environment,
+) -> Result<Value> {
+    // A variable holding a function can be called like the function itself
+    let name = match environment.get(name) {
+        Some(Value::Function(target)) => target,
+        _ => name.to_string(),
+    };
+    let name = &name;
+
+    let function =
+        environment
+            .functions()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| ArgonError::UnknownFunction {
+                name: name.clone(),
+                span: Some(span),
+            })?;
+
+    function
+        .check_arity(name, args.len())
+        .map_err(|e| e.with_span(span))?;
+
+    match function {
+        Function::BuiltIn { function, .. } => {
+            if let Some(result) = quantity_function(name, &args) {
+                return result.map_err(|e| e.with_span(span));
+            }
+            let is_nan = |arg: &Value| arg.as_number().is_some_and(f64::is_nan);
+            match function(args.clone()) {
+                // In complex mode, arguments outside the real domain give a complex result, so
+                // `sqrt(-1)` is `i`. Functions without a complex result keep the real one.
+                Ok(Value::Number(n))
+                    if n.is_nan()
+                        && environment.mode() == Mode::Complex
+                        && !args.iter().any(is_nan) =>
+                {
+                    let args = args
+                        .iter()
+                        .map(|arg| arg.as_complex().map_or_else(|| arg.clone(), Value::Complex))
+                        .collect();
+                    Ok(function(args).unwrap_or(Value::Number(n)))
+                }
+                result => result,
+            }
+            .map_err(|e| e.with_span(span))
+        }
+        Function::UserDefined { params, body } => {
+            // Parameters shadow session variables only while the body runs
+            environment
+                .push_scope(ScopeKind::Function(name.clone()))
+                .map_err(|e| e.with_span(span))?;
+            for (param, arg) in params.iter().zip(args) {
+                environment.define(param, arg);
+            }
+            let result = interpret(&body, environment);
+            environment.pop_scope();
+
+            // The body was written on another line, so errors inside it point at the call
+            result.map_err(|e| match e {
+                // A runaway recursion is reported once, at the outermost call
+                ArgonError::RecursionLimit { name, .. } => ArgonError::RecursionLimit {
+                    name,
+                    span: Some(span),
+                },
+                e => ArgonError::InFunction {
+                    name: name.clone(),
+                    error: Box::new(e),
+                    span: Some(span),
+                },
+            })
+        }
+    }
+}
 
-            functions
-                .get(name)
-                .ok_or(format_err!("Function not found"))?(args)
+/// Binds each of a `let` expression's variables in the innermost scope, then evaluates its body.
+fn interpret_let(
+    bindings: &[(String, Expr)],
+    body: &Expr,
+    environment: &mut Environment,
+) -> Result<Value> {
+    for (name, value) in bindings {
+        if environment.is_constant(name) {
+            return Err(ArgonError::ConstantAssignment {
+                name: name.clone(),
+                span: Some(value.span),
+            });
         }
+        let value = interpret(value, environment)?;
+        environment.define(name, value);
+    }
+    interpret(body, environment)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        functions::FunctionInfo,
+        lexer::{generate_tokens, Span},
+    };
+
+    fn run(equation: &str, environment: &mut Environment) -> Result<Value> {
+        let tokens = generate_tokens(equation.to_string())?;
+        let mut result = Value::None;
+        for statement in crate::parser::parse_statements(&tokens)? {
+            result = interpret(&statement, environment)?;
+        }
+        Ok(result)
+    }
+
+    #[test]
+    fn test_user_defined_function() {
+        let mut environment = Environment::new();
+
+        run("f(x, y) = x^2 + y", &mut environment).unwrap();
+        assert_eq!(run("f(3, 4)", &mut environment).unwrap(), 13.0);
+        assert_eq!(run("1 + f(1, f(1, 1))", &mut environment).unwrap(), 4.0);
+
+        run("g() = f(2, 0) * 2", &mut environment).unwrap();
+        assert_eq!(run("g()", &mut environment).unwrap(), 8.0);
+    }
+
+    #[test]
+    fn test_user_defined_function_scope() {
+        let mut environment = Environment::new();
+
+        run("x = 10", &mut environment).unwrap();
+        run("k = 2", &mut environment).unwrap();
+        run("f(x) = x * k", &mut environment).unwrap();
+        assert_eq!(run("f(3)", &mut environment).unwrap(), 6.0);
+        assert_eq!(environment.get("x"), Some(Value::Number(10.0)));
+    }
+
+    #[test]
+    fn test_values() {
+        let mut environment = Environment::new();
+
+        assert_eq!(run("true && !false", &mut environment).unwrap(), true);
+        assert_eq!(
+            run("\"foo\" + \"bar\"", &mut environment).unwrap(),
+            Value::from("foobar")
+        );
+        assert_eq!(run("\"a\" < \"b\"", &mut environment).unwrap(), true);
+        assert_eq!(run("1 == \"1\"", &mut environment).unwrap(), false);
+        assert_eq!(run("none", &mut environment).unwrap(), Value::None);
+        assert_eq!(
+            run("s = \"hi\"; s == \"hi\" ? 1 : 2", &mut environment).unwrap(),
+            1.0
+        );
+
+        // Functions can be stored in variables and called through them
+        assert_eq!(
+            run("g = sqrt", &mut environment).unwrap(),
+            Value::Function("sqrt".to_string())
+        );
+        assert_eq!(run("g(16)", &mut environment).unwrap(), 4.0);
+        assert_eq!(run("f(x) = x", &mut environment).unwrap(), Value::None);
+    }
+
+    #[test]
+    fn test_type_errors() {
+        let mut environment = Environment::new();
+
+        let err = run("1 + (true + \"a\")", &mut environment).unwrap_err();
+        assert_eq!(err.to_string(), "cannot add bool and string");
+        assert_eq!(err.span(), Some(Span::new(4, 16)));
+
+        let error = |equation: &str, environment: &mut Environment| {
+            run(equation, environment).unwrap_err().to_string()
+        };
+        assert_eq!(error("-\"a\"", &mut environment), "cannot negate string");
+        assert_eq!(
+            error("true!", &mut environment),
+            "cannot take the factorial of bool"
+        );
+        assert_eq!(
+            error("\"a\" < 1", &mut environment),
+            "cannot compare string and number"
+        );
+        assert_eq!(
+            error("sqrt(\"4\")", &mut environment),
+            "sqrt expects numbers, got string"
+        );
+    }
+
+    #[test]
+    fn test_lists() {
+        let mut environment = Environment::new();
+        let list = |equation: &str, environment: &mut Environment| {
+            run(equation, environment).unwrap().to_string()
+        };
+
+        run("xs = [10, 20, 30, 40]", &mut environment).unwrap();
+        assert_eq!(run("xs[0]", &mut environment).unwrap(), 10.0);
+        assert_eq!(run("xs[-1]", &mut environment).unwrap(), 40.0);
+        assert_eq!(list("xs[1:3]", &mut environment), "[20, 30]");
+        assert_eq!(list("xs[:-1]", &mut environment), "[10, 20, 30]");
+        assert_eq!(list("xs[2:]", &mut environment), "[30, 40]");
+        assert_eq!(list("xs[3:1]", &mut environment), "[]");
+        assert_eq!(list("xs[-10:10]", &mut environment), "[10, 20, 30, 40]");
+        assert_eq!(list("\"hello\"[1:3]", &mut environment), "el");
+        assert_eq!(list("[[1, 2], [3]][0][1]", &mut environment), "2");
+
+        // Arithmetic is element-wise, and single values apply to every item
+        assert_eq!(list("xs / 10", &mut environment), "[1, 2, 3, 4]");
+        assert_eq!(list("1 - [1, 2]", &mut environment), "[0, -1]");
+        assert_eq!(list("[1, 2] * [3, 4]", &mut environment), "[3, 8]");
+        assert_eq!(list("-[1, -2]", &mut environment), "[-1, 2]");
+        assert_eq!(list("[100, 200] + 10%", &mut environment), "[110, 220]");
+        assert_eq!(list("[1, 5] > 2", &mut environment), "[false, true]");
+        assert_eq!(run("[1, 2] == [1, 2]", &mut environment).unwrap(), true);
+    }
+
+    #[test]
+    fn test_list_errors() {
+        let mut environment = Environment::new();
+        let error = |equation: &str, environment: &mut Environment| {
+            run(equation, environment).unwrap_err().to_string()
+        };
+
+        let err = run("1 + [1, 2, 3][5]", &mut environment).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "index 5 is out of range for a list of length 3"
+        );
+        assert_eq!(err.span(), Some(Span::new(4, 16)));
+        assert_eq!(
+            error("[1][-2]", &mut environment),
+            "index -2 is out of range for a list of length 1"
+        );
+        assert_eq!(
+            error("[1, 2][0.5]", &mut environment),
+            "indices must be whole numbers, got 0.5"
+        );
+        assert_eq!(error("5[0]", &mut environment), "cannot index number");
+        assert_eq!(
+            error("[1, 2] + [1, 2, 3]", &mut environment),
+            "cannot add lists of length 2 and 3"
+        );
+        assert_eq!(
+            error("[1, true] * 2", &mut environment),
+            "cannot multiply bool and number"
+        );
+    }
+
+    #[test]
+    fn test_matrices() {
+        let mut environment = Environment::new();
+        let value = |equation: &str, environment: &mut Environment| {
+            run(equation, environment).unwrap().to_string()
+        };
+
+        run("m = [[1, 2], [3, 4]]", &mut environment).unwrap();
+        assert_eq!(run("m", &mut environment).unwrap().type_name(), "matrix");
+        assert_eq!(value("m * m", &mut environment), "[[7, 10], [15, 22]]");
+        assert_eq!(value("m * [1, 1]", &mut environment), "[3, 7]");
+        assert_eq!(value("[1, 1] * m", &mut environment), "[4, 6]");
+        assert_eq!(value("m^2 == m * m", &mut environment), "true");
+        assert_eq!(value("2 * m - 1", &mut environment), "[[1, 3], [5, 7]]");
+        assert_eq!(value("m + m", &mut environment), "[[2, 4], [6, 8]]");
+        assert_eq!(value("m[1]", &mut environment), "[3, 4]");
+        assert_eq!(value("m[1][0]", &mut environment), "3");
+        assert_eq!(value("m[:1]", &mut environment), "[[1, 2]]");
+
+        // Rows of different lengths are a list of lists, not a matrix
+        assert_eq!(
+            run("[[1, 2], [3]]", &mut environment).unwrap().type_name(),
+            "list"
+        );
+    }
+
+    #[test]
+    fn test_matrix_errors() {
+        let mut environment = Environment::new();
+        let error =
+            |equation: &str, environment: &mut Environment| run(equation, environment).unwrap_err();
+
+        let err = error("[[1, 2]] * [[1, 2]]", &mut environment);
+        assert!(matches!(err, ArgonError::DimensionMismatch { .. }));
+        assert_eq!(
+            err.to_string(),
+            "cannot multiply a 1x2 matrix by a 1x2 matrix"
+        );
+        assert_eq!(err.span(), Some(Span::new(0, 19)));
+        assert_eq!(
+            error("[[1, 2]] + [[1], [2]]", &mut environment).to_string(),
+            "cannot add a 1x2 matrix and a 2x1 matrix"
+        );
+        assert_eq!(
+            error("[[1, 2]]^2", &mut environment).to_string(),
+            "cannot raise a 1x2 matrix to a power"
+        );
+        assert_eq!(
+            error("[[1, 2], [3, 4]] < 1", &mut environment).to_string(),
+            "cannot compare matrix and number"
+        );
+    }
+
+    #[test]
+    fn test_complex_mode() {
+        let mut environment = Environment::new();
+        let value = |equation: &str, environment: &mut Environment| {
+            run(equation, environment).unwrap().to_string()
+        };
+
+        assert_eq!(value("sqrt(-1)", &mut environment), "NaN");
+        assert!(run("i", &mut environment).is_err());
+
+        environment.set_mode(Mode::Complex);
+        assert_eq!(value("sqrt(-1)", &mut environment), "i");
+        assert_eq!(value("sqrt(4)", &mut environment), "2");
+        assert_eq!(value("i^2", &mut environment), "-1");
+        assert_eq!(value("(1 + 2i) * (3 - i)", &mut environment), "5 + 5i");
+        assert_eq!(value("(2 + 2i) / 2 - 1", &mut environment), "i");
+        assert_eq!(value("-(1 + i)", &mut environment), "-1 - i");
+        assert_eq!(
+            value("abs((-4)^0.5 - 2i) < 0.000001", &mut environment),
+            "true"
+        );
+        assert_eq!(value("ln(-1)", &mut environment), "3.141592653589793i");
+        assert_eq!(value("abs(3 + 4i)", &mut environment), "5");
+        assert_eq!(value("[1, i] * i", &mut environment), "[i, -1]");
+
+        let err = run("1 + (i < 2)", &mut environment).unwrap_err();
+        assert_eq!(err.to_string(), "cannot compare complex numbers");
+        assert_eq!(err.span(), Some(Span::new(4, 11)));
+        assert!(run("i = 1", &mut environment).is_err());
+        assert!(run("rad2deg(i)", &mut environment).is_err());
+    }
+
+    #[test]
+    fn test_big_integers() {
+        let mut environment = Environment::new();
+        let value = |equation: &str, environment: &mut Environment| {
+            run(equation, environment).unwrap().to_string()
+        };
+
+        assert_eq!(value("2^64 + 1", &mut environment), "18446744073709551617");
+        assert_eq!(value("2^53 + 1", &mut environment), "9007199254740993");
+        assert_eq!(
+            value("-(2^70)", &mut environment),
+            "-1180591620717411303424"
+        );
+        assert_eq!(value("1e20 * 3", &mut environment), "300000000000000000000");
+        assert_eq!(value("2^64 / 2^62", &mut environment), "4");
+        assert_eq!(value("2^64 % 7", &mut environment), "2");
+        assert_eq!(value("2^65 > 2^64 + 1", &mut environment), "true");
+        assert_eq!(
+            value("2^64 == 18446744073709551616", &mut environment),
+            "true"
+        );
+        assert_eq!(
+            value("[2^64, 1] * 2", &mut environment),
+            "[36893488147419103232, 2]"
+        );
+        assert_eq!(
+            value("factorial(25) / factorial(23)", &mut environment),
+            "600"
+        );
+        assert_eq!(value("2^1024 > 1", &mut environment), "true");
+        assert_eq!(
+            value("nCr(2^64, 2)", &mut environment),
+            "170141183460469231722463931679029329920"
+        );
+        assert_eq!(
+            value("nCr(2^60 + 1, 1)", &mut environment),
+            "1152921504606846977"
+        );
+        assert_eq!(
+            value("nCr(2^70, 1)", &mut environment),
+            "1180591620717411303424"
+        );
+        assert_eq!(
+            value("nPr(2^64, 2)", &mut environment),
+            "340282366920938463444927863358058659840"
+        );
+        assert_eq!(
+            value("nCr(2^64, 2^64 - 1) == 2^64", &mut environment),
+            "true"
+        );
+        assert_eq!(value("nCr(2^64, 2^32)", &mut environment), "inf");
+        assert_eq!(value("factorial(2^64)", &mut environment), "inf");
+        assert_eq!(
+            run("nCr(1e20, 2)", &mut environment)
+                .unwrap_err()
+                .to_string(),
+            "nCr is only defined for integers small enough to be exact"
+        );
+        assert_eq!(
+            value("123456789012345678901234567890", &mut environment),
+            "123456789012345678901234567890"
+        );
+        assert_eq!(value("18446744073709551617 - 2^64", &mut environment), "1");
+        assert_eq!(value("0x1_0000_0000_0000_0001 % 10", &mut environment), "7");
+
+        // Anything that isn't a whole number falls back to floating point
+        assert_eq!(value("2^64 / 3", &mut environment), "6148914691236517000");
+        assert_eq!(value("2^64 * 0.5", &mut environment), "9223372036854776000");
+        assert_eq!(value("sqrt(2^64)", &mut environment), "4294967296");
+        assert_eq!(value("2^1e7", &mut environment), "inf");
+        assert_eq!(value("1e300 * 1e300", &mut environment), "inf");
+        assert_eq!(value("1e20 + 1 == 1e20", &mut environment), "true");
+        assert_eq!(value("2^-70 < 1", &mut environment), "true");
+
+        environment.set_mode(Mode::Exact);
+        assert_eq!(
+            value("1/3 + 2^70", &mut environment),
+            "3541774862152233910273/3"
+        );
+        assert_eq!(value("30! / 28!", &mut environment), "870");
+        assert_eq!(value("nCr(10, 3) / 7", &mut environment), "120/7");
+        assert_eq!(
+            value("nCr(2^60 + 1, 1)", &mut environment),
+            "1152921504606846977"
+        );
+        assert!(run("nPr(2^64 + 1/2, 1)", &mut environment).is_err());
+    }
+
+    #[test]
+    fn test_exact_mode() {
+        let mut environment = Environment::new();
+        let value = |equation: &str, environment: &mut Environment| {
+            run(equation, environment).unwrap().to_string()
+        };
+
+        assert_eq!(value("0.1 + 0.2", &mut environment), "0.30000000000000004");
+
+        environment.set_mode(Mode::Exact);
+        assert_eq!(value("0.1 + 0.2", &mut environment), "3/10");
+        assert_eq!(value("0.1 + 0.2 == 0.3", &mut environment), "true");
+        assert_eq!(value("1/3 * 3", &mut environment), "1");
+        assert_eq!(value("-1/3 - 1/6", &mut environment), "-1/2");
+        assert_eq!(
+            value("1.2345678901234567890123456789 * 10^28", &mut environment),
+            "12345678901234567890123456789"
+        );
+        assert_eq!(value("(2/3)^-2", &mut environment), "9/4");
+        assert_eq!(
+            value("2^100", &mut environment),
+            "1267650600228229401496703205376"
+        );
+        assert_eq!(value("7/2 % 1", &mut environment), "1/2");
+        assert_eq!(value("200 + 10%", &mut environment), "220");
+        assert_eq!(value("1/3 < 0.34", &mut environment), "true");
+        assert_eq!(value("[1, 2][1/1]", &mut environment), "2");
+
+        // Anything that can't be a fraction falls back to floating point
+        assert_eq!(value("1/0", &mut environment), "inf");
+        assert_eq!(value("4^0.5", &mut environment), "2");
+        assert_eq!(value("sqrt(4) + 1/2", &mut environment), "2.5");
+        assert_eq!(value("sin(0)", &mut environment), "0");
+        assert_eq!(value("1/4 + pi > 3", &mut environment), "true");
+
+        // Functions with exact results keep them exact
+        assert_eq!(value("abs(-1/3)", &mut environment), "1/3");
+        assert_eq!(value("round(5/2)", &mut environment), "3");
+        assert_eq!(value("sum([1/3, 1/6], 1/2)", &mut environment), "1");
+        assert_eq!(value("avg(1/3, 0.5)", &mut environment), "5/12");
+        assert_eq!(value("median([1/2, 1/3])", &mut environment), "5/12");
+        assert_eq!(value("pow(2/3, 2)", &mut environment), "4/9");
+        assert_eq!(value("recip(0)", &mut environment), "inf");
+        assert_eq!(value("sum(1/2, sqrt(4))", &mut environment), "2.5");
+        assert_eq!(
+            value("sort([1/2, 1/3, sqrt(0.16)])", &mut environment),
+            "[1/3, 0.4, 1/2]"
+        );
+    }
+
+    #[test]
+    fn test_decimal_mode() {
+        let mut environment = Environment::new();
+        let value = |equation: &str, environment: &mut Environment| {
+            run(equation, environment).unwrap().to_string()
+        };
+
+        environment.set_mode(Mode::Decimal(40));
+        assert_eq!(
+            value("sqrt(2)", &mut environment),
+            "1.41421356237309504880168872420969807857"
+        );
+        assert_eq!(
+            value("pi", &mut environment),
+            "3.141592653589793238462643383279502884197"
+        );
+        assert_eq!(
+            value("1/3", &mut environment),
+            "0.3333333333333333333333333333333333333333"
+        );
+        assert_eq!(value("0.1 + 0.2 == 0.3", &mut environment), "true");
+        assert_eq!(
+            value("sin(1)", &mut environment),
+            "0.8414709848078965066525023216302989996226"
+        );
+        assert_eq!(value("exp(1) == e", &mut environment), "true");
+        assert_eq!(value("log(8, 2)", &mut environment), "3");
+        assert_eq!(value("7.5 % 2", &mut environment), "1.5");
+        assert_eq!(value("200 + 10%", &mut environment), "220");
+        assert_eq!(value("2^100 / 2^99", &mut environment), "2");
+        assert_eq!(value("-e < -2.7", &mut environment), "true");
+
+        // Literals are read from their digits, not from the closest floating point number
+        environment.set_mode(Mode::Decimal(50));
+        assert_eq!(
+            value("1.2345678901234567890123456789", &mut environment),
+            "1.2345678901234567890123456789"
+        );
+        assert_eq!(
+            value("98765432109876543210.5 + 0.5", &mut environment),
+            "98765432109876543211"
+        );
+        assert_eq!(value("5! / 4", &mut environment), "30");
+
+        // Results that aren't real numbers and functions without a decimal result fall back to
+        // floating point
+        assert_eq!(value("1/0", &mut environment), "inf");
+        assert_eq!(value("sqrt(-1)", &mut environment), "NaN");
+        assert_eq!(value("sinh(1) > 1.17", &mut environment), "true");
+
+        environment.set_mode(Mode::Decimal(5));
+        assert_eq!(value("2/3", &mut environment), "0.66667");
+        assert_eq!(value("123456789", &mut environment), "1.2346e8");
+    }
+
+    #[test]
+    fn test_units() {
+        let mut environment = Environment::new();
+        let mut value = |equation: &str| run(equation, &mut environment).unwrap().to_string();
+
+        assert_eq!(value("5 km + 300 m"), "5.3 km");
+        assert_eq!(value("9.81 m/s^2 * 3 s"), "29.43 m/s");
+        assert_eq!(value("60 mph to km/h"), "96.56064 km/h");
+        assert_eq!(value("20 °C to °F"), "68 °F");
+        assert_eq!(value("300 K in degC"), "26.85 degC");
+        assert_eq!(value("100 km / 2 h"), "50 km/h");
+        assert_eq!(value("10 m / 5 m"), "2");
+        assert_eq!(value("5 km / 300 m > 16"), "true");
+        assert_eq!(value("1 / 2 s"), "0.5 s^-1");
+        assert_eq!(value("3 m^2 / 2"), "1.5 m^2");
+        assert_eq!(value("1 kWh to MJ"), "3.6 MJ");
+        assert_eq!(value("(2 m)^2"), "4 m^2");
+        assert_eq!(value("1 km == 1000 m"), "true");
+        assert_eq!(value("1 ft < 1 m"), "true");
+        assert_eq!(value("[1, 2] * 1 km"), "[1 km, 2 km]");
+        assert_eq!(value("-5 s + 10%"), "-5.5 s");
+        assert_eq!(value("let d = 1 mi in d in m"), "1609.344 m");
+
+        // After `to` and `in`, names are units first, so `min` is a minute and `in` an inch
+        assert_eq!(value("5 h to min"), "300 min");
+        assert_eq!(value("1 ft to in"), "12 in");
+        assert_eq!(value("2 ft in in"), "24 in");
+        assert_eq!(value("1 m^2 to in^2"), "1550.0031000062 in^2");
+        assert_eq!(value("90 km/h to m/min"), "1500 m/min");
+
+        // `m` isn't an SI suffix, so a unit can be written straight after the number
+        assert_eq!(value("5m"), "5 m");
+        assert_eq!(value("5m + 1 m"), "6 m");
+        assert_eq!(value("2kg + 500 g"), "2.5 kg");
+        assert_eq!(value("2min to s"), "120 s");
+
+        // Quantities work with the functions that keep their unit meaningful
+        assert_eq!(value("sqrt(4 m^2)"), "2 m");
+        assert_eq!(value("abs(-5 km)"), "5 km");
+        assert_eq!(value("root(27 m^3, 3)"), "3 m");
+
+        // After a number, a unit is found before a function but after a variable
+        assert_eq!(value("12 in"), "12 in");
+        assert_eq!(value("2 ft + 3 in"), "2.25 ft");
+        assert_eq!(value("60 in/s to ft/s"), "5 ft/s");
+        assert_eq!(value("1 h + 30 min"), "1.5 h");
+        assert_eq!(value("12 in to ft"), "1 ft");
+        assert_eq!(value("let d = 2 in d"), "2");
+        assert_eq!(value("m = 2; 3 m"), "6");
+        assert_eq!(value("min(1, 2)"), "1");
+        assert_eq!(value("3 km to m"), "3000 m");
+
+        // A number followed by a variable binds like `*`, even if a unit has the same name
+        assert_eq!(value("1/2h"), "0.5 h^-1");
+        assert_eq!(value("h = 2; x = 2; [1/2h, 1/2x]"), "[1, 1]");
+        assert_eq!(value("7 % 2h"), "2");
+        assert_eq!(value("100 km / 2h"), "100 km");
+    }
+
+    #[test]
+    fn test_unit_errors() {
+        let mut environment = Environment::new();
+        let mut error = |equation: &str| run(equation, &mut environment).unwrap_err().to_string();
+
+        assert_eq!(error("3 m + 2 s"), "cannot add m and s");
+        assert_eq!(error("2 + 3 kg"), "cannot add number and kg");
+        assert_eq!(error("1 km > 2 s"), "cannot compare km and s");
+        assert_eq!(error("5 mph to kg"), "cannot convert mph to kg");
+        assert_eq!(error("5 to km"), "cannot convert number to km");
+        assert_eq!(
+            error("5 km to 2 m"),
+            "cannot convert to 2 m, expected a unit"
+        );
+        assert_eq!(error("(2 m)^0.5"), "cannot raise m to the power 0.5");
+        assert_eq!(error("true * (1 m)"), "cannot multiply bool and quantity");
+        assert_eq!(error("parsec"), "undefined variable 'parsec'");
+        assert_eq!(error("sqrt(2 m)"), "cannot take the square root of m");
+        assert_eq!(error("sin(2 m)"), "sin expects numbers, got quantity");
+
+        // A name is only a unit after a number or after `to` and `in`
+        assert_eq!(error("3 + m"), "undefined variable 'm'");
+        assert_eq!(error("C"), "undefined variable 'C'");
+        assert_eq!(error("2 * km"), "undefined variable 'km'");
+        assert_eq!(error("min + 1 h"), "cannot add function and quantity");
+    }
+
+    #[test]
+    fn test_let() {
+        let mut environment = Environment::new();
+
+        assert_eq!(run("let x = 2 in x^2", &mut environment).unwrap(), 4.0);
+        assert_eq!(
+            run("let x = 2, y = x + 1 in x * y", &mut environment).unwrap(),
+            6.0
+        );
+        assert_eq!(
+            run("1 + (let x = 2 in let x = x * 10 in x)", &mut environment).unwrap(),
+            21.0
+        );
+        assert!(run("x", &mut environment).is_err());
+
+        // Bindings shadow session variables without changing them
+        run("y = 1", &mut environment).unwrap();
+        assert_eq!(run("let y = 5 in y * 2", &mut environment).unwrap(), 10.0);
+        assert_eq!(environment.get("y"), Some(Value::Number(1.0)));
+
+        // A function can't see the local variables of its caller
+        run("g() = secret", &mut environment).unwrap();
+        assert!(run("let secret = 1 in g()", &mut environment).is_err());
+
+        assert!(run("let pi = 3 in pi", &mut environment).is_err());
+        assert!(run("let x = 1 x", &mut environment).is_err());
+        assert!(run("let = 1 in 2", &mut environment).is_err());
+    }
+
+    #[test]
+    fn test_recursion_limit() {
+        let mut environment = Environment::new();
+
+        run("f(n) = f(n + 1)", &mut environment).unwrap();
+        let err = run("1 + f(0)", &mut environment).unwrap_err();
+        assert_eq!(err.to_string(), "maximum recursion depth exceeded in 'f'");
+        assert_eq!(err.span(), Some(Span::new(4, 8)));
+
+        // The environment is usable again afterwards
+        run("fact(n) = n <= 1 ? 1 : n * fact(n - 1)", &mut environment).unwrap();
+        assert_eq!(run("fact(10)", &mut environment).unwrap(), 3628800.0);
+    }
+
+    #[test]
+    fn test_user_defined_function_errors() {
+        let mut environment = Environment::new();
+
+        run("f(x) = x + 1", &mut environment).unwrap();
+        assert!(matches!(
+            run("f(1, 2)", &mut environment),
+            Err(ArgonError::ArityMismatch {
+                min: 1,
+                max: Some(1),
+                got: 2,
+                ..
+            })
+        ));
+        assert!(matches!(
+            run("sqrt(x) = x", &mut environment),
+            Err(ArgonError::BuiltInRedefinition { .. })
+        ));
+        assert!(run("h(x, x) = x", &mut environment).is_err());
+        assert!(run("h(x) = y", &mut environment).is_ok());
+        assert!(run("h(1)", &mut environment).is_err());
+    }
+
+    #[test]
+    fn test_builtin_arity() {
+        let mut environment = Environment::new();
+
+        let err = run("log(8)", &mut environment).unwrap_err();
+        assert_eq!(err.to_string(), "log expects 2 arguments, got 1");
+        assert_eq!(err.span(), Some(Span::new(0, 6)));
+
+        assert!(run("sqrt()", &mut environment).is_err());
+        assert!(run("sqrt(1, 2, 3)", &mut environment).is_err());
+        assert_eq!(
+            run("max()", &mut environment).unwrap_err().to_string(),
+            "max expects at least 1 argument, got 0"
+        );
+        assert_eq!(run("max(1, 5, 2)", &mut environment).unwrap(), 5.0);
+        assert_eq!(run("sum()", &mut environment).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn test_registered_closure() {
+        let mut environment = Environment::new();
+
+        let table = [1.0, 4.0, 9.0];
+        environment.functions_mut().register_numeric(
+            FunctionInfo::new("lookup", &["index"], "a value from the table"),
+            move |args| {
+                table
+                    .get(args[0] as usize)
+                    .copied()
+                    .ok_or_else(|| ArgonError::domain("lookup", "index is out of range"))
+            },
+        );
+
+        assert_eq!(run("lookup(2) + 1", &mut environment).unwrap(), 10.0);
+        assert!(run("lookup(1, 2)", &mut environment).is_err());
+
+        let err = run("1 + lookup(5)", &mut environment).unwrap_err();
+        assert_eq!(err.to_string(), "lookup index is out of range");
+        assert_eq!(err.span(), Some(Span::new(4, 13)));
+
+        // Registered functions can't be redefined from an equation either
+        assert!(run("lookup(x) = x", &mut environment).is_err());
+    }
+
+    #[test]
+    fn test_only_chosen_branch_is_evaluated() {
+        let mut environment = Environment::new();
+
+        assert_eq!(run("1 ? 2 : undefined", &mut environment).unwrap(), 2.0);
+        assert_eq!(run("0 && undefined", &mut environment).unwrap(), false);
+        assert_eq!(run("1 || undefined", &mut environment).unwrap(), true);
+        assert!(run("1 && undefined", &mut environment).is_err());
+
+        run("f(x) = x < 0 ? -x : x", &mut environment).unwrap();
+        assert_eq!(run("f(-3) + f(2)", &mut environment).unwrap(), 5.0);
+
+        run("fact(n) = n <= 1 ? 1 : n * fact(n - 1)", &mut environment).unwrap();
+        assert_eq!(run("fact(5)", &mut environment).unwrap(), 120.0);
+    }
+
+    #[test]
+    fn test_constants() {
+        let mut environment = Environment::new();
+
+        assert_eq!(run("2PI", &mut environment).unwrap(), std::f64::consts::TAU);
+        assert_eq!(
+            run("pi == PI && tau == 2pi", &mut environment).unwrap(),
+            true
+        );
+
+        // Names that contain a constant are ordinary variables
+        assert_eq!(run("SPEED = 3", &mut environment).unwrap(), 3.0);
+        assert_eq!(run("PIPE = 4", &mut environment).unwrap(), 4.0);
+        assert_eq!(run("SPEED * PIPE", &mut environment).unwrap(), 12.0);
+
+        assert!(run("pi = 3", &mut environment).is_err());
+        assert!(run("E = 3", &mut environment).is_err());
+        assert!(run("f(e) = e", &mut environment).is_err());
+    }
+
+    #[test]
+    fn test_error_spans() {
+        let mut environment = Environment::new();
+
+        let span = |result: Result<Value>| result.unwrap_err().span().unwrap();
+
+        assert_eq!(span(run("1 + foo * 2", &mut environment)), Span::new(4, 7));
+        assert_eq!(span(run("2 * nope(1)", &mut environment)), Span::new(4, 11));
+        assert_eq!(span(run("1 + (-1)!", &mut environment)), Span::new(4, 9));
+
+        // Errors inside a user defined function point at the call
+        run("f(x) = x + y", &mut environment).unwrap();
+        let err = run("1 + f(2)", &mut environment).unwrap_err();
+        assert_eq!(err.to_string(), "undefined variable 'y' (in 'f')");
+        assert_eq!(span(run("1 + f(2)", &mut environment)), Span::new(4, 8));
+    }
+
+    #[test]
+    fn test_variables_resolved_at_evaluation() {
+        let mut environment = Environment::new();
+
+        assert_eq!(run("x = 2 + 3", &mut environment).unwrap(), 5.0);
+        assert_eq!(run("y = x * 2", &mut environment).unwrap(), 10.0);
+        assert_eq!(run("y + x", &mut environment).unwrap(), 15.0);
+
+        // Functions see the value of a variable at the time they are called
+        run("f() = y", &mut environment).unwrap();
+        run("y = 1", &mut environment).unwrap();
+        assert_eq!(run("f()", &mut environment).unwrap(), 1.0);
+
+        assert_eq!(
+            run("r = 3; a = r * 2; a + r", &mut environment).unwrap(),
+            9.0
+        );
+
+        let err = run("foo + 1", &mut environment).unwrap_err();
+        assert_eq!(err.to_string(), "undefined variable 'foo'");
+    }
+
+    #[test]
+    fn test_variables_beat_si_suffixes() {
+        let mut environment = Environment::new();
+
+        assert_eq!(run("2k", &mut environment).unwrap(), 2000.0);
+        run("k = 5", &mut environment).unwrap();
+        assert_eq!(run("2k", &mut environment).unwrap(), 10.0);
+        assert_eq!(run("1.5k + 1", &mut environment).unwrap(), 8.5);
+
+        run("m = 4", &mut environment).unwrap();
+        assert_eq!(run("3m", &mut environment).unwrap(), 12.0);
+
+        run("f(n) = 2n + 1", &mut environment).unwrap();
+        assert_eq!(run("f(3)", &mut environment).unwrap(), 7.0);
+        assert_eq!(run("3n", &mut environment).unwrap(), 3e-9);
+        assert_eq!(run("let u = 2 in 3u", &mut environment).unwrap(), 6.0);
     }
 }
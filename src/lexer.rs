@@ -1,9 +1,159 @@
-use anyhow::{format_err, Result};
+use std::fmt;
 
-/// Enum that represents a token in the lexer.
+use num_bigint::BigInt;
+use num_traits::ToPrimitive;
+
+use crate::error::{ArgonError, Result};
+use crate::integer;
+use crate::rational::BigRational;
+
+/// A range of bytes in the source equation, used to point at the part of the equation an error
+/// comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    /// Returns the smallest span that covers both spans.
+    pub fn merge(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+/// A token in the lexer along with where it appears in the equation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+/// A number literal. Along with the closest floating point number, it keeps the exact value it was
+/// written as, so whole numbers with more digits than a floating point number holds keep them.
+///
+/// # Example
+/// ```
+/// # use argon::lexer::{self, TokenKind};
+/// let tokens = lexer::generate_tokens("123456789012345678901234567890".to_string()).unwrap();
+/// let TokenKind::Number(literal) = &tokens[0].kind else { panic!() };
+/// assert_eq!(literal.value, 1.2345678901234568e29);
+/// assert_eq!(literal.to_integer().unwrap().to_string(), "123456789012345678901234567890");
+/// ```
+#[derive(Clone, PartialEq)]
+pub struct Literal {
+    /// The closest floating point number to the literal, without its SI suffix.
+    pub value: f64,
+    /// The digits of the literal without its decimal point, so `1.25e3` is `125`.
+    pub digits: BigInt,
+    /// The power of ten `digits` is multiplied by, so `1.25e3` is `1`.
+    pub exponent: i64,
+    /// True if the literal is written with only digits, such as `42` or `0xFF`.
+    pub is_integer: bool,
+    /// The SI prefix written straight after the number, such as the `k` in `4.7k`. It only scales
+    /// the number if no variable has its name, so after `k = 5`, `2k` is `2 * k`.
+    pub suffix: Option<char>,
+}
+
+impl Literal {
+    /// Creates the literal `digits * 10^exponent`.
+    pub fn new(digits: BigInt, exponent: i64, is_integer: bool) -> Self {
+        let value = format!("{}e{}", digits, exponent).parse().unwrap();
+        Literal {
+            value,
+            digits,
+            exponent,
+            is_integer,
+            suffix: None,
+        }
+    }
+
+    /// Returns the literal multiplied by the power of ten its SI suffix stands for, so `4.7k` is
+    /// `4700`.
+    pub fn scaled(&self) -> Literal {
+        let power = SI_SUFFIXES
+            .iter()
+            .find(|(suffix, _)| Some(*suffix) == self.suffix)
+            .map(|(_, power)| *power);
+        match power {
+            Some(power) => Literal::new(self.digits.clone(), self.exponent + power, false),
+            None => self.clone(),
+        }
+    }
+
+    /// Reads a decimal literal such as `12.5` or `6.02e23`, written without digit separators.
+    /// Returns `None` if `text` isn't a number.
+    fn parse(text: &str) -> Option<Literal> {
+        let (mantissa, exponent) = text.split_once(['e', 'E']).unwrap_or((text, "0"));
+        let (whole, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let digits: BigInt = format!("{}{}", whole, fraction).parse().ok()?;
+        // Exponents too large for an `i64` are far past infinity or zero anyway
+        let exponent = exponent
+            .parse::<i64>()
+            .unwrap_or(if exponent.starts_with('-') {
+                i64::MIN / 2
+            } else {
+                i64::MAX / 2
+            });
+        let is_integer = !text.contains(['.', 'e', 'E']);
+        Some(Literal::new(
+            digits,
+            exponent - fraction.len() as i64,
+            is_integer,
+        ))
+    }
+
+    /// Returns the whole number the literal holds if it is written with only digits, so `42` is
+    /// exact but `4.2e1` is not.
+    pub fn to_integer(&self) -> Option<BigInt> {
+        self.is_integer.then(|| self.digits.clone())
+    }
+
+    /// Returns the exact fraction the literal is written as, so `0.1` is `1/10` and every digit
+    /// of a long literal is kept. Returns `None` if the power of ten would have more than
+    /// [`integer::MAX_EXACT_BITS`] bits, such as for `1e999999999`.
+    pub fn to_rational(&self) -> Option<BigRational> {
+        let bits = self.exponent.unsigned_abs() as f64 * std::f64::consts::LOG2_10;
+        if bits > integer::MAX_EXACT_BITS as f64 {
+            return None;
+        }
+        let power = BigInt::from(10).pow(self.exponent.unsigned_abs() as u32);
+        Some(if self.exponent < 0 {
+            BigRational::new(self.digits.clone(), power)
+        } else {
+            BigRational::from_integer(&self.digits * power)
+        })
+    }
+}
+
+impl From<f64> for Literal {
+    /// Converts a finite floating point number to the literal it is written as, so `0.1` is
+    /// `1 * 10^-1`.
+    fn from(n: f64) -> Self {
+        Literal::parse(&n.to_string()).unwrap()
+    }
+}
+
+impl fmt::Debug for Literal {
+    /// Formats the literal as its floating point number and SI suffix, which is how tokens are
+    /// shown in errors.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.value)?;
+        match self.suffix {
+            Some(suffix) => write!(f, "{}", suffix),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Enum that represents the kind of a token in the lexer.
 #[derive(Debug, Clone, PartialEq)]
-pub enum Token {
-    Number(f64),
+pub enum TokenKind {
+    Number(Literal),
     Plus,
     Minus,
     Multiply,
@@ -12,127 +162,563 @@ pub enum Token {
     Modulus,
     RightParen,
     LeftParen,
+    RightBracket,
+    LeftBracket,
     Identifier(String),
     Equals,
     Comma,
+    Semicolon,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    EqualEqual,
+    NotEqual,
+    And,
+    Or,
+    Not,
+    Question,
+    Colon,
+    DoubleFactorial,
+    Percent,
+    Let,
+    In,
+    To,
+    Bool(bool),
+    String(String),
+    None,
 }
 
-/// Preprocesses the equation by replacing constants with their values.
-///
-/// # Example
-/// ```
-/// let equation = "PI + E".to_string();
-/// let processed_equation = lexer::preprocessor(equation);
-/// assert_eq!(processed_equation, "3.14159265358979 + 2.718281828459045");
-fn preprocessor(equation: String) -> String {
-    let mut processed_equation = equation.replace("PI", "3.14159265358979");
-    processed_equation = processed_equation.replace("E", "2.718281828459045");
-    processed_equation = processed_equation.replace("TAU", "6.283185307179586");
-    return processed_equation;
+/// SI prefixes that can be written straight after a number, such as `4.7k` or `10n`, and the
+/// power of ten they multiply by. `m` isn't one, so `5m` is five metres.
+const SI_SUFFIXES: &[(char, i64)] = &[
+    ('f', -15),
+    ('p', -12),
+    ('n', -9),
+    ('u', -6),
+    ('k', 3),
+    ('M', 6),
+    ('G', 9),
+    ('T', 12),
+    ('P', 15),
+];
+
+/// Reads a run of digits in the given radix starting at `index`, skipping `_` digit separators.
+/// Separators are only allowed between two digits.
+fn read_digits(chars: &[char], index: &mut usize, radix: u32) -> Result<String> {
+    let mut digits = String::new();
+
+    while let Some(&char) = chars.get(*index) {
+        if char == '_' {
+            let next_is_digit = chars.get(*index + 1).is_some_and(|c| c.is_digit(radix));
+            if digits.is_empty() || !next_is_digit {
+                return Err(ArgonError::lex(
+                    "Malformed number literal: '_' must be between two digits",
+                ));
+            }
+        } else if char.is_digit(radix) {
+            digits.push(char);
+        } else {
+            break;
+        }
+        *index += 1;
+    }
+
+    Ok(digits)
+}
+
+/// Generates a number token for a hexadecimal (`0xFF`), binary (`0b1010`) or octal (`0o17`)
+/// literal. `index` must point just after the prefix.
+fn make_radix_number(
+    chars: &[char],
+    index: &mut usize,
+    radix: u32,
+    name: &str,
+) -> Result<TokenKind> {
+    let start = *index - 2;
+    let digits = read_digits(chars, index, radix)?;
+
+    if let Some(char) = chars
+        .get(*index)
+        .filter(|c| c.is_alphanumeric() || **c == '.')
+    {
+        return Err(ArgonError::lex(format!(
+            "Malformed {} literal: invalid digit '{}'",
+            name, char
+        )));
+    }
+    if digits.is_empty() {
+        let literal: String = chars[start..*index].iter().collect();
+        return Err(ArgonError::lex(format!(
+            "Malformed {} literal '{}': expected digits",
+            name, literal
+        )));
+    }
+
+    let digits = BigInt::parse_bytes(digits.as_bytes(), radix).unwrap();
+    Ok(TokenKind::Number(Literal {
+        value: digits.to_f64().unwrap_or(f64::INFINITY),
+        digits,
+        exponent: 0,
+        is_integer: true,
+        suffix: None,
+    }))
 }
 
 /// Generates a number token from the equation.
 ///
+/// Numbers can be written as decimals (`123.456`, `.5`), in scientific notation (`6.02e23`), in
+/// hexadecimal, binary or octal (`0xFF`, `0b1010`, `0o17`), with `_` digit separators
+/// (`1_000_000`) and with an SI prefix as a suffix (`4.7k`).
+///
 /// # Example
-/// ```
-/// let equation = "123.456".to_string();
+/// ```ignore
+/// let chars: Vec<char> = "123.456".chars().collect();
 /// let mut index = 0;
-/// let token = lexer::make_number(&equation, &mut index).unwrap();
-/// assert_eq!(token, lexer::Token::Number(123.456));
-fn make_number(equation: &String, index: &mut usize) -> Result<Token> {
-    let mut char = equation.chars().nth(*index).unwrap_or(' ');
-    let mut dot_count = 0;
-    let mut num_string = String::new();
+/// let token = lexer::make_number(&chars, &mut index).unwrap();
+/// assert_eq!(token, lexer::TokenKind::Number(123.456.into()));
+/// ```
+fn make_number(chars: &[char], index: &mut usize) -> Result<TokenKind> {
+    let start = *index;
 
-    loop {
-        if char == '.' {
-            if dot_count == 1 {
-                return Err(format_err!("Unexpected token '.'"));
-            }
-            dot_count += 1;
-            num_string.push(char);
-        } else if char.is_numeric() {
-            num_string.push(char)
-        } else {
-            break;
+    if chars[start] == '0' {
+        let radix = match chars.get(start + 1) {
+            Some('x' | 'X') => Some((16, "hexadecimal")),
+            Some('b' | 'B') => Some((2, "binary")),
+            Some('o' | 'O') => Some((8, "octal")),
+            _ => None,
+        };
+        if let Some((radix, name)) = radix {
+            *index += 2;
+            return make_radix_number(chars, index, radix, name);
         }
+    }
+
+    let mut num_string = read_digits(chars, index, 10)?;
+    if chars.get(*index) == Some(&'.') {
+        num_string.push('.');
         *index += 1;
-        char = equation.chars().nth(*index).unwrap_or(' ');
+        num_string.push_str(&read_digits(chars, index, 10)?);
+        if chars.get(*index) == Some(&'.') {
+            return Err(ArgonError::lex("Unexpected token '.'"));
+        }
+    }
+    if num_string == "." {
+        return Err(ArgonError::lex("Unexpected token '.'"));
+    }
+
+    // An exponent is only read if it is followed by a digit, or a sign and a digit, so `2exp(1)`
+    // and `2e` are still an implicit multiplication
+    if let Some('e' | 'E') = chars.get(*index) {
+        let is_digit = |i: usize| chars.get(i).is_some_and(|c| c.is_ascii_digit());
+        let sign = chars.get(*index + 1).filter(|c| matches!(c, '+' | '-'));
+        let digits_start = *index + 1 + usize::from(sign.is_some());
+        if is_digit(digits_start) {
+            num_string.push('e');
+            num_string.extend(sign);
+            *index = digits_start;
+            num_string.push_str(&read_digits(chars, index, 10)?);
+        }
+    }
+
+    let mut literal = match Literal::parse(&num_string) {
+        Some(literal) => literal,
+        None => {
+            return Err(ArgonError::lex(format!(
+                "Malformed number literal '{}'",
+                num_string
+            )))
+        }
+    };
+
+    // An SI suffix has to end the literal, so `2kg` is two kilograms
+    if let Some(char) = chars.get(*index) {
+        let ends_literal = !chars.get(*index + 1).is_some_and(|c| c.is_alphanumeric());
+        if ends_literal && SI_SUFFIXES.iter().any(|(suffix, _)| suffix == char) {
+            literal.suffix = Some(*char);
+            *index += 1;
+        }
     }
 
-    let num: f64 = num_string.parse().unwrap();
-    return Ok(Token::Number(num));
+    Ok(TokenKind::Number(literal))
 }
 
-fn make_identifier(equation: &String, index: &mut usize) -> Result<Token> {
-    let mut char = equation.chars().nth(*index).unwrap_or(' ');
+fn make_identifier(chars: &[char], index: &mut usize) -> Result<TokenKind> {
     let mut identifier = String::new();
 
-    loop {
-        if char.is_alphanumeric() {
+    while let Some(&char) = chars.get(*index) {
+        if char.is_alphanumeric() || char == '°' {
             identifier.push(char);
         } else {
             break;
         }
         *index += 1;
-        char = equation.chars().nth(*index).unwrap_or(' ');
     }
 
-    return Ok(Token::Identifier(identifier));
+    match identifier.as_str() {
+        "let" => Ok(TokenKind::Let),
+        "in" => Ok(TokenKind::In),
+        "to" => Ok(TokenKind::To),
+        "true" => Ok(TokenKind::Bool(true)),
+        "false" => Ok(TokenKind::Bool(false)),
+        "none" => Ok(TokenKind::None),
+        _ => Ok(TokenKind::Identifier(identifier)),
+    }
+}
+
+/// Generates a string token such as `"hello"`. `index` must point at the opening quote.
+///
+/// `\"`, `\\`, `\n` and `\t` can be used to write a quote, backslash, newline or tab.
+fn make_string(chars: &[char], index: &mut usize) -> Result<TokenKind> {
+    let mut string = String::new();
+    *index += 1;
+
+    loop {
+        match chars.get(*index) {
+            Some('"') => break,
+            Some('\\') => {
+                *index += 1;
+                match chars.get(*index) {
+                    Some('"') => string.push('"'),
+                    Some('\\') => string.push('\\'),
+                    Some('n') => string.push('\n'),
+                    Some('t') => string.push('\t'),
+                    Some(char) => {
+                        return Err(ArgonError::lex(format!(
+                            "Unknown escape sequence '\\{}'",
+                            char
+                        )))
+                    }
+                    None => return Err(ArgonError::lex("Unterminated string")),
+                }
+            }
+            Some(char) => string.push(*char),
+            None => return Err(ArgonError::lex("Unterminated string")),
+        }
+        *index += 1;
+    }
+
+    *index += 1;
+    Ok(TokenKind::String(string))
+}
+
+/// Decides whether the `%` at `index` is a percentage such as `10%` rather than a modulus such as
+/// `10 % 3`. It is a percentage unless the next character (ignoring whitespace) starts an operand,
+/// or is a minus sign written straight before one, so `10 % -3` is a modulus while `10% - 3` is a
+/// percentage followed by a subtraction.
+fn is_percent(chars: &[char], index: usize) -> bool {
+    let starts_operand = |c: &char| c.is_alphanumeric() || matches!(c, '.' | '(' | '[');
+    let next = chars[index + 1..]
+        .iter()
+        .position(|c| !c.is_whitespace())
+        .map(|offset| index + 1 + offset);
+    match next.map(|next| (chars[next], chars.get(next + 1))) {
+        Some(('-', Some(c))) => !starts_operand(c),
+        Some((c, _)) => !starts_operand(&c),
+        None => true,
+    }
 }
 
 /// The main lexer function that generates tokens from the equation.
 ///
 /// # Example
 /// ```
+/// # use argon::lexer;
 /// let equation = "1 + 2".to_string();
 /// let tokens = lexer::generate_tokens(equation).unwrap();
-/// assert_eq!(tokens, vec![lexer::Token::Number(1.0), lexer::Token::Plus, lexer::Token::Number(2.0)]);
+/// let kinds: Vec<lexer::TokenKind> = tokens.into_iter().map(|token| token.kind).collect();
+/// assert_eq!(kinds, vec![lexer::TokenKind::Number(1.0.into()), lexer::TokenKind::Plus, lexer::TokenKind::Number(2.0.into())]);
+/// ```
 pub fn generate_tokens(equation: String) -> Result<Vec<Token>> {
     let mut result = Vec::new();
-    let equation = preprocessor(equation);
+    let chars: Vec<char> = equation.chars().collect();
+
+    // Byte offset of every character, plus the end of the equation, for building spans
+    let offsets: Vec<usize> = equation
+        .char_indices()
+        .map(|(offset, _)| offset)
+        .chain(std::iter::once(equation.len()))
+        .collect();
+    let span = |start: usize, end: usize| Span::new(offsets[start], offsets[end.min(chars.len())]);
 
     let mut index: usize = 0;
-    while index < equation.len() {
-        let char = equation.chars().nth(index).unwrap_or(' ');
-        if char.is_numeric() {
-            let token = make_number(&equation, &mut index);
-            match token {
-                Ok(token) => result.push(token),
-                Err(err) => {
-                    return Err(err);
-                }
-            }
-            continue;
+    while index < chars.len() {
+        let start = index;
+        let char = chars[index];
+        let starts_fraction =
+            char == '.' && chars.get(index + 1).is_some_and(|c| c.is_ascii_digit());
+
+        let token = if char.is_ascii_digit() || starts_fraction {
+            make_number(&chars, &mut index).map(Some)
+        } else if char.is_alphabetic() || char == '°' {
+            make_identifier(&chars, &mut index).map(Some)
+        } else if char == '"' {
+            make_string(&chars, &mut index).map(Some)
+        } else {
+            make_operator(&chars, &mut index)
+        };
+
+        match token {
+            Ok(Some(kind)) => result.push(Token {
+                kind,
+                span: span(start, index),
+            }),
+            Ok(None) => (),
+            // Point at everything read so far, including the character that caused the error
+            Err(err) => return Err(err.with_span(span(start, index + 1))),
         }
+    }
 
-        if char.is_alphabetic() {
-            let token = make_identifier(&equation, &mut index);
-            match token {
-                Ok(token) => result.push(token),
-                Err(err) => {
-                    return Err(err);
-                }
-            }
-            continue;
+    Ok(result)
+}
+
+/// Generates an operator or punctuation token from the equation. Returns `None` for whitespace.
+fn make_operator(chars: &[char], index: &mut usize) -> Result<Option<TokenKind>> {
+    let char = chars[*index];
+
+    // Operators made of two characters, such as `<=` or `&&`
+    let double = match (char, chars.get(*index + 1)) {
+        ('<', Some('=')) => Some(TokenKind::LessEqual),
+        ('>', Some('=')) => Some(TokenKind::GreaterEqual),
+        ('=', Some('=')) => Some(TokenKind::EqualEqual),
+        ('!', Some('=')) => Some(TokenKind::NotEqual),
+        ('&', Some('&')) => Some(TokenKind::And),
+        ('|', Some('|')) => Some(TokenKind::Or),
+        ('!', Some('!')) => Some(TokenKind::DoubleFactorial),
+        _ => None,
+    };
+    if double.is_some() {
+        *index += 2;
+        return Ok(double);
+    }
+
+    let token = match char {
+        '+' => TokenKind::Plus,
+        '-' => TokenKind::Minus,
+        '*' => TokenKind::Multiply,
+        '/' => TokenKind::Divide,
+        '(' => TokenKind::LeftParen,
+        ')' => TokenKind::RightParen,
+        '[' => TokenKind::LeftBracket,
+        ']' => TokenKind::RightBracket,
+        '^' => TokenKind::Power,
+        '%' if is_percent(chars, *index) => TokenKind::Percent,
+        '%' => TokenKind::Modulus,
+        '=' => TokenKind::Equals,
+        ',' => TokenKind::Comma,
+        ';' => TokenKind::Semicolon,
+        '<' => TokenKind::Less,
+        '>' => TokenKind::Greater,
+        '!' => TokenKind::Not,
+        '?' => TokenKind::Question,
+        ':' => TokenKind::Colon,
+        ' ' | '\n' | '\t' | '\r' => {
+            *index += 1;
+            return Ok(None);
         }
+        _ => return Err(ArgonError::lex(format!("Unknown character '{}'", char))),
+    };
+    *index += 1;
+    Ok(Some(token))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(equation: &str) -> Vec<TokenKind> {
+        generate_tokens(equation.to_string())
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect()
+    }
+
+    fn number(equation: &str) -> f64 {
+        match kinds(equation).as_slice() {
+            [TokenKind::Number(literal)] => literal.scaled().value,
+            tokens => panic!("Expected a single number, got {:?}", tokens),
+        }
+    }
+
+    #[test]
+    fn test_decimal_literals() {
+        assert_eq!(number("123.456"), 123.456);
+        assert_eq!(number(".5"), 0.5);
+        assert_eq!(number("5."), 5.0);
+        assert_eq!(number("1_000_000"), 1_000_000.0);
+        assert_eq!(number("0.000_1"), 0.0001);
+        assert!(generate_tokens("1.2.3".to_string()).is_err());
+        assert!(generate_tokens("1__0".to_string()).is_err());
+        assert!(generate_tokens("1_".to_string()).is_err());
+    }
+
+    #[test]
+    fn test_scientific_notation() {
+        assert_eq!(number("6.02e23"), 6.02e23);
+        assert_eq!(number("1e-3"), 0.001);
+        assert_eq!(number("2.5e+2"), 250.0);
+
+        // Not an exponent, so these are an implicit multiplication
+        assert_eq!(
+            kinds("2exp"),
+            vec![
+                TokenKind::Number(2.0.into()),
+                TokenKind::Identifier("exp".to_string())
+            ]
+        );
+        assert_eq!(
+            kinds("2e*3"),
+            vec![
+                TokenKind::Number(2.0.into()),
+                TokenKind::Identifier("e".to_string()),
+                TokenKind::Multiply,
+                TokenKind::Number(3.0.into()),
+            ]
+        );
+        assert_eq!(
+            kinds("1e+x"),
+            vec![
+                TokenKind::Number(1.0.into()),
+                TokenKind::Identifier("e".to_string()),
+                TokenKind::Plus,
+                TokenKind::Identifier("x".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_radix_literals() {
+        assert_eq!(number("0xFF"), 255.0);
+        assert_eq!(number("0xff_ff"), 65535.0);
+        assert_eq!(number("0b1010"), 10.0);
+        assert_eq!(number("0o17"), 15.0);
+        assert!(generate_tokens("0xZZ".to_string()).is_err());
+        assert!(generate_tokens("0x".to_string()).is_err());
+        assert!(generate_tokens("0b102".to_string()).is_err());
+        assert!(generate_tokens("0o8".to_string()).is_err());
+        assert!(generate_tokens("0x1.5".to_string()).is_err());
+        assert_eq!(number("0x1_0000_0000_0000_0000"), 2f64.powi(64));
+    }
+
+    #[test]
+    fn test_integer_literals_are_exact() {
+        let literal = |equation: &str| match kinds(equation).as_slice() {
+            [TokenKind::Number(literal)] => literal.clone(),
+            tokens => panic!("Expected a single number, got {:?}", tokens),
+        };
+        assert_eq!(
+            literal("18_446_744_073_709_551_617").to_integer(),
+            Some(BigInt::from(u64::MAX) + 2)
+        );
+        assert_eq!(literal("0xFF").to_integer(), Some(BigInt::from(255)));
+        assert_eq!(literal("1e3").to_integer(), None);
+        assert_eq!(literal("100.0").to_integer(), None);
+        assert_eq!(literal("2k").scaled().to_integer(), None);
+        assert_eq!(literal("1.25e3"), Literal::new(BigInt::from(125), 1, false));
+        assert_eq!(
+            literal("0.1").to_rational(),
+            Some(BigRational::new(1.into(), 10.into()))
+        );
+        assert_eq!(
+            literal("2.5k").scaled().to_rational(),
+            Some(BigRational::from_integer(2500.into()))
+        );
+        assert_eq!(literal("1e999999999").to_rational(), None);
+    }
 
-        match char {
-            '+' => result.push(Token::Plus),
-            '-' => result.push(Token::Minus),
-            '*' => result.push(Token::Multiply),
-            '/' => result.push(Token::Divide),
-            '(' => result.push(Token::LeftParen),
-            ')' => result.push(Token::RightParen),
-            '^' => result.push(Token::Power),
-            '%' => result.push(Token::Modulus),
-            '=' => result.push(Token::Equals),
-            ',' => result.push(Token::Comma),
-            ' ' | '\n' | '\t' | '\r' => (),
-            _ => return Err(format_err!("Unknown character '{}'", char)),
+    #[test]
+    fn test_si_suffixes() {
+        assert_eq!(number("4.7k"), 4700.0);
+        assert_eq!(number("3M"), 3e6);
+        assert_eq!(number("10n"), 10e-9);
+        assert_eq!(number("1_500u"), 1.5e-3);
+        match kinds("2k").as_slice() {
+            [TokenKind::Number(literal)] => {
+                assert_eq!(literal.value, 2.0);
+                assert_eq!(literal.suffix, Some('k'));
+            }
+            tokens => panic!("Expected a single number, got {:?}", tokens),
         }
-        index += 1;
+
+        // Only a suffix when it ends the literal
+        assert_eq!(
+            kinds("2min"),
+            vec![
+                TokenKind::Number(2.0.into()),
+                TokenKind::Identifier("min".to_string())
+            ]
+        );
+
+        // `m` is left for metres
+        assert_eq!(
+            kinds("5m"),
+            vec![
+                TokenKind::Number(5.0.into()),
+                TokenKind::Identifier("m".to_string())
+            ]
+        );
     }
 
-    return Ok(result);
+    #[test]
+    fn test_spans() {
+        let tokens = generate_tokens("12 + sqrt(x)".to_string()).unwrap();
+        let spans: Vec<Span> = tokens.iter().map(|token| token.span).collect();
+        assert_eq!(
+            spans,
+            vec![
+                Span::new(0, 2),
+                Span::new(3, 4),
+                Span::new(5, 9),
+                Span::new(9, 10),
+                Span::new(10, 11),
+                Span::new(11, 12),
+            ]
+        );
+
+        // Spans are in bytes, not characters
+        let tokens = generate_tokens("π + 1".to_string()).unwrap();
+        assert_eq!(tokens[0].span, Span::new(0, 2));
+        assert_eq!(tokens[2].span, Span::new(5, 6));
+    }
+
+    #[test]
+    fn test_strings_and_keywords() {
+        assert_eq!(
+            kinds("20 °C to °F"),
+            vec![
+                TokenKind::Number(20.0.into()),
+                TokenKind::Identifier("°C".to_string()),
+                TokenKind::To,
+                TokenKind::Identifier("°F".to_string()),
+            ]
+        );
+
+        let kinds: Vec<TokenKind> = generate_tokens(r#""a \"b\"\n" + true"#.to_string())
+            .unwrap()
+            .into_iter()
+            .map(|token| token.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::String("a \"b\"\n".to_string()),
+                TokenKind::Plus,
+                TokenKind::Bool(true),
+            ]
+        );
+
+        assert!(generate_tokens("\"open".to_string()).is_err());
+        assert!(generate_tokens(r#""\q""#.to_string()).is_err());
+    }
+
+    #[test]
+    fn test_error_spans() {
+        let err = generate_tokens("1 + 2 $".to_string()).unwrap_err();
+        assert_eq!(err.span(), Some(Span::new(6, 7)));
+
+        let err = generate_tokens("x = 0x".to_string()).unwrap_err();
+        assert!(matches!(err, ArgonError::LexError { .. }));
+        assert_eq!(err.span(), Some(Span::new(4, 6)));
+    }
 }
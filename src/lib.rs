@@ -0,0 +1,347 @@
+//! Argon is a calculator that evaluates equations such as `2x^2 + sqrt(9)`.
+//!
+//! [`Calculator`] is the entry point for using Argon from another program. It keeps the variables
+//! and functions of a session between calls to [`Calculator::eval`].
+//!
+//! # Example
+//! ```
+//! use argon::{Calculator, FunctionInfo};
+//!
+//! let mut calculator = Calculator::new();
+//! calculator.set_var("r", 2.0).unwrap();
+//! calculator.register_function(
+//!     FunctionInfo::new("double", &["number"], "the number times two"),
+//!     |args| Ok(args[0] * 2.0),
+//! );
+//! assert_eq!(calculator.eval("double(r) + 1").unwrap(), 5.0);
+//! assert_eq!(calculator.eval("r > 1").unwrap().to_string(), "true");
+//! ```
+
+pub mod constants;
+pub mod decimal;
+pub mod environment;
+pub mod error;
+pub mod functions;
+pub mod integer;
+pub mod interpreter;
+pub mod lexer;
+pub mod matrix;
+pub mod number;
+pub mod parser;
+pub mod rational;
+pub mod units;
+pub mod value;
+
+pub use environment::{Environment, Mode};
+pub use error::{ArgonError, Result};
+pub use functions::{FunctionInfo, FunctionRegistry};
+pub use matrix::Matrix;
+pub use num_complex::Complex64;
+pub use parser::{Expr, ExprKind};
+pub use value::{ComplexFormat, Format, RationalFormat, Value};
+
+/// A calculator session. Variables and functions declared in one equation can be used by the
+/// equations evaluated after it.
+#[derive(Debug, Clone)]
+pub struct Calculator {
+    environment: Environment,
+    format: Format,
+}
+
+impl Default for Calculator {
+    fn default() -> Self {
+        Calculator::new()
+    }
+}
+
+impl Calculator {
+    /// Creates a calculator with the built-in functions and no variables.
+    pub fn new() -> Self {
+        Calculator {
+            environment: Environment::new(),
+            format: Format::default(),
+        }
+    }
+
+    /// Evaluates every statement in `equation` and returns the result of the last one, or
+    /// `Value::None` if there are no statements.
+    ///
+    /// `equation` can also be a command that changes a setting: `mode real`, `mode complex`,
+    /// `mode exact`, `mode decimal`, `format rectangular`, `format polar`, `format fraction`,
+    /// `format decimal`, `digits` followed by the most digits to print whole numbers with or
+    /// `all`, or `precision` followed by the number of significant digits to calculate decimals
+    /// with. Commands evaluate to `Value::None`.
+    ///
+    /// # Example
+    /// ```
+    /// let mut calculator = argon::Calculator::new();
+    /// assert_eq!(calculator.eval("x = 3; f(n) = n^2; f(x)").unwrap(), 9.0);
+    /// assert_eq!(calculator.eval("").unwrap(), argon::Value::None);
+    ///
+    /// calculator.eval("mode complex").unwrap();
+    /// assert_eq!(calculator.eval("sqrt(-4)").unwrap().to_string(), "2i");
+    ///
+    /// calculator.eval("mode exact").unwrap();
+    /// assert_eq!(calculator.eval("1/3 + 0.5").unwrap().to_string(), "5/6");
+    ///
+    /// calculator.eval("precision 30").unwrap();
+    /// assert_eq!(
+    ///     calculator.eval("sqrt(2)").unwrap().to_string(),
+    ///     "1.41421356237309504880168872421"
+    /// );
+    /// ```
+    pub fn eval(&mut self, equation: &str) -> Result<Value> {
+        let command = self.run_command(equation);
+        if let Some(Ok(())) = command {
+            return Ok(Value::None);
+        }
+
+        // A command name with a setting it doesn't have may still be an equation, such as
+        // `mode x` after `mode = 2`. The command's error is only reported if it isn't.
+        match (self.run_statements(equation), command) {
+            (Err(_), Some(Err(error))) => Err(error),
+            (result, _) => result,
+        }
+    }
+
+    /// Evaluates every statement in `equation`, returning the value of the last one.
+    fn run_statements(&mut self, equation: &str) -> Result<Value> {
+        let mut result = Value::None;
+        for statement in &self.parse(equation)? {
+            result = self.evaluate(statement)?;
+        }
+        Ok(result)
+    }
+
+    /// Runs `equation` if it is a command such as `mode complex`, which is a command name and a
+    /// setting. Returns `None` if it isn't a command name and a setting, and an error if the
+    /// command doesn't have that setting.
+    fn run_command(&mut self, equation: &str) -> Option<Result<()>> {
+        let mut words = equation.split_whitespace();
+        let (command, setting) = (words.next()?, words.next()?);
+        if words.next().is_some() {
+            return None;
+        }
+
+        // Spans are in bytes, like the spans of tokens
+        let start = setting.as_ptr() as usize - equation.as_ptr() as usize;
+        let unknown = |kind: &str, expected: &str| {
+            ArgonError::parse(
+                lexer::Span::new(start, start + setting.len()),
+                format!("unknown {} '{}', expected {}", kind, setting, expected),
+            )
+        };
+
+        match command {
+            "mode" => Some(
+                Mode::from_name(setting)
+                    .map(|mode| self.set_mode(mode))
+                    .ok_or_else(|| unknown("mode", "real, complex, exact or decimal")),
+            ),
+            "format" => Some(
+                self.format
+                    .with_setting(setting)
+                    .map(|format| self.set_format(format))
+                    .ok_or_else(|| unknown("format", "rectangular, polar, fraction or decimal")),
+            ),
+            "digits" => Some(
+                match setting {
+                    "all" => Some(None),
+                    setting => setting.parse().ok().filter(|&n| n > 0).map(Some),
+                }
+                .map(|max_digits| self.format.max_digits = max_digits)
+                .ok_or_else(|| unknown("digit limit", "a positive whole number or all")),
+            ),
+            "precision" => Some(
+                setting
+                    .parse()
+                    .ok()
+                    .filter(|precision| (1..=decimal::MAX_PRECISION).contains(precision))
+                    .map(|precision| self.set_mode(Mode::Decimal(precision)))
+                    .ok_or_else(|| {
+                        unknown(
+                            "precision",
+                            &format!("a number of digits from 1 to {}", decimal::MAX_PRECISION),
+                        )
+                    }),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Returns how numbers are evaluated.
+    pub fn mode(&self) -> Mode {
+        self.environment.mode()
+    }
+
+    /// Changes how numbers are evaluated. In [`Mode::Complex`], `i` is the imaginary unit, in
+    /// [`Mode::Exact`], numbers are exact fractions, and in [`Mode::Decimal`], numbers are
+    /// decimals with the given number of significant digits.
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.environment.set_mode(mode);
+    }
+
+    /// Returns how values should be printed. Use it with [`Value::display`].
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Changes how values should be printed.
+    pub fn set_format(&mut self, format: Format) {
+        self.format = format;
+    }
+
+    /// Parses `equation` into one expression for each statement, without evaluating them.
+    pub fn parse(&self, equation: &str) -> Result<Vec<Expr>> {
+        let tokens = lexer::generate_tokens(equation.to_string())?;
+        parser::parse_statements(&tokens)
+    }
+
+    /// Evaluates an expression returned by [`Calculator::parse`].
+    pub fn evaluate(&mut self, expr: &Expr) -> Result<Value> {
+        interpreter::interpret(expr, &mut self.environment)
+    }
+
+    /// Sets a variable, returning an error if `name` is a constant such as `pi`.
+    pub fn set_var(&mut self, name: &str, value: impl Into<Value>) -> Result<()> {
+        if self.environment.is_constant(name) {
+            return Err(ArgonError::ConstantAssignment {
+                name: name.to_string(),
+                span: None,
+            });
+        }
+        self.environment.define(name, value.into());
+        Ok(())
+    }
+
+    /// Returns the value of a variable or constant.
+    pub fn get_var(&self, name: &str) -> Option<Value> {
+        self.environment.get(name)
+    }
+
+    /// Registers a function that takes and returns numbers, replacing any function with the same
+    /// name. See [`FunctionRegistry::register_numeric`].
+    pub fn register_function<F>(&mut self, info: FunctionInfo, function: F)
+    where
+        F: Fn(Vec<f64>) -> Result<f64> + Send + Sync + 'static,
+    {
+        self.environment
+            .functions_mut()
+            .register_numeric(info, function);
+    }
+
+    /// Registers a function that takes and returns any kind of value, replacing any function with
+    /// the same name. See [`FunctionRegistry::register`].
+    pub fn register_value_function<F>(&mut self, info: FunctionInfo, function: F)
+    where
+        F: Fn(Vec<Value>) -> Result<Value> + Send + Sync + 'static,
+    {
+        self.environment.functions_mut().register(info, function);
+    }
+
+    /// Returns the functions that can be called from equations.
+    pub fn functions(&self) -> &FunctionRegistry {
+        self.environment.functions()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_keeps_state() {
+        let mut calculator = Calculator::new();
+        calculator.eval("x = 4").unwrap();
+        calculator.eval("f(n) = n + x").unwrap();
+        assert_eq!(calculator.eval("f(1)").unwrap(), 5.0);
+        assert_eq!(calculator.get_var("x"), Some(Value::Number(4.0)));
+        assert_eq!(
+            calculator.get_var("PI"),
+            Some(Value::Number(std::f64::consts::PI))
+        );
+    }
+
+    #[test]
+    fn test_set_var() {
+        let mut calculator = Calculator::new();
+        calculator.set_var("rate", 0.5).unwrap();
+        assert_eq!(calculator.eval("rate * 10").unwrap(), 5.0);
+        assert!(matches!(
+            calculator.set_var("pi", 3.0),
+            Err(ArgonError::ConstantAssignment { .. })
+        ));
+    }
+
+    #[test]
+    fn test_commands() {
+        let mut calculator = Calculator::new();
+        assert_eq!(calculator.eval("i = 2; i").unwrap(), 2.0);
+
+        assert_eq!(calculator.eval("mode complex").unwrap(), Value::None);
+        assert_eq!(calculator.mode(), Mode::Complex);
+        assert_eq!(calculator.eval("(1 + i) * (1 - i)").unwrap(), 2.0);
+        assert!(calculator.eval("i = 2").is_err());
+
+        calculator.eval("  format   polar ").unwrap();
+        assert_eq!(calculator.format().complex, ComplexFormat::Polar);
+
+        calculator.eval("format decimal").unwrap();
+        assert_eq!(calculator.format().complex, ComplexFormat::Polar);
+        assert_eq!(calculator.format().rational, RationalFormat::Decimal);
+
+        calculator.eval("digits 10").unwrap();
+        assert_eq!(calculator.format().max_digits, Some(10));
+        calculator.eval("digits all").unwrap();
+        assert_eq!(calculator.format().max_digits, None);
+        assert_eq!(
+            calculator.eval("digits 0").unwrap_err().to_string(),
+            "unknown digit limit '0', expected a positive whole number or all"
+        );
+
+        calculator.eval("mode decimal").unwrap();
+        assert_eq!(calculator.mode(), Mode::Decimal(decimal::DEFAULT_PRECISION));
+        calculator.eval("precision 25").unwrap();
+        assert_eq!(calculator.mode(), Mode::Decimal(25));
+        assert_eq!(
+            calculator.eval("precision 0").unwrap_err().to_string(),
+            "unknown precision '0', expected a number of digits from 1 to 1000"
+        );
+
+        let err = calculator.eval("mode imaginary").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown mode 'imaginary', expected real, complex, exact or decimal"
+        );
+        assert_eq!(err.span(), Some(lexer::Span::new(5, 14)));
+
+        // Spans are in bytes
+        let err = calculator.eval("format  ∠").unwrap_err();
+        assert_eq!(err.span(), Some(lexer::Span::new(8, 11)));
+
+        // Anything else is an ordinary equation, including a command name and an unknown setting
+        // that work as an equation
+        calculator.eval("mode = 3").unwrap();
+        assert_eq!(calculator.eval("mode").unwrap(), 3.0);
+        calculator.eval("x = 2").unwrap();
+        assert_eq!(calculator.eval("mode x").unwrap(), 6.0);
+        assert_eq!(calculator.mode(), Mode::Decimal(25));
+    }
+
+    #[test]
+    fn test_errors_are_typed() {
+        let mut calculator = Calculator::new();
+        assert!(matches!(
+            calculator.eval("1 + $"),
+            Err(ArgonError::LexError { .. })
+        ));
+        assert!(matches!(
+            calculator.eval("1 +"),
+            Err(ArgonError::ParseError { .. })
+        ));
+        assert!(matches!(
+            calculator.eval("nope(1)"),
+            Err(ArgonError::UnknownFunction { .. })
+        ));
+    }
+}
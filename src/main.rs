@@ -1,11 +1,4 @@
-mod functions;
-mod interpreter;
-mod lexer;
-mod parser;
-
-use std::collections::HashMap;
-
-use anyhow::Result;
+use argon::{error, Calculator, Value};
 use rustyline::{error::ReadlineError, DefaultEditor};
 
 const HISTORY_PATH: &str = "./.argon-history";
@@ -18,8 +11,7 @@ fn repl() {
     let mut rl = DefaultEditor::new().unwrap();
     let _ = rl.load_history(HISTORY_PATH);
 
-    let mut variables: HashMap<String, f64> = HashMap::new();
-    let mut functions = functions::load_functions();
+    let mut calculator = Calculator::new();
 
     loop {
         let readline = rl.readline("(argon)>> ");
@@ -30,29 +22,11 @@ fn repl() {
                     break;
                 }
 
-                let tokens = match lexer::generate_tokens(equation) {
-                    Ok(tokens) => tokens,
-                    Err(e) => {
-                        eprintln!("Error: {}", e);
-                        continue;
-                    }
-                };
-
-                let ast = match parser::parse_expr(&tokens, &mut variables) {
-                    Ok(ast) => ast,
-                    Err(e) => {
-                        eprintln!("Error: {}", e);
-                        continue;
-                    }
-                };
-                let result = match interpreter::interpret(&ast, &mut variables, &mut functions) {
-                    Ok(result) => result,
-                    Err(e) => {
-                        eprintln!("Error: {}", e);
-                        continue;
-                    }
-                };
-                println!("{}", result);
+                match calculator.eval(&equation) {
+                    Ok(Value::None) => (),
+                    Ok(result) => println!("{}", result.display(calculator.format())),
+                    Err(e) => eprintln!("{}", error::report(&e, &equation, 1)),
+                }
             }
             Err(ReadlineError::Interrupted) => {
                 println!("CTRL-C");
@@ -70,20 +44,23 @@ fn repl() {
     rl.save_history(HISTORY_PATH).unwrap();
 }
 
-fn run_file(file: &str) -> Result<()> {
+fn run_file(file: &str) -> Result<(), Box<dyn std::error::Error>> {
     // Execute a file if one was provided
     let file = std::fs::read_to_string(file)?;
-    let mut variables: HashMap<String, f64> = HashMap::new();
-    let mut functions = functions::load_functions();
+    let mut calculator = Calculator::new();
 
-    for line in file.lines() {
-        let tokens = lexer::generate_tokens(line.to_string())?;
-        let ast = parser::parse_expr(&tokens, &mut variables)?;
-        let result = interpreter::interpret(&ast, &mut variables, &mut functions)?;
-        println!("{}", result);
+    for (index, line) in file.lines().enumerate() {
+        match calculator.eval(line) {
+            Ok(Value::None) => (),
+            Ok(result) => println!("{}", result.display(calculator.format())),
+            Err(e) => {
+                eprintln!("{}", error::report(&e, line, index + 1));
+                break;
+            }
+        }
     }
 
-    return Ok(());
+    Ok(())
 }
 
 fn main() {
@@ -0,0 +1,673 @@
+use std::{
+    fmt,
+    ops::{Index, IndexMut},
+};
+
+use crate::error::{ArgonError, Result};
+use crate::value::Value;
+
+/// Entries smaller than this, relative to the largest entry of the matrix, are treated as zero
+/// when looking for pivots and checking whether eigenvalues have converged.
+const TOLERANCE: f64 = 1e-10;
+
+/// The most QR iterations `eigenvalues` runs for a single eigenvalue before giving up.
+const MAX_EIGEN_ITERATIONS: usize = 1000;
+
+/// A dense matrix of numbers, stored row by row.
+///
+/// A list whose items are lists of numbers of the same length, such as `[[1, 2], [3, 4]]`,
+/// evaluates to a matrix. A matrix always has at least one row and one column.
+///
+/// # Example
+/// ```
+/// # use argon::matrix::Matrix;
+/// let a = Matrix::from_rows(vec![vec![1.0, 2.0], vec![3.0, 4.0]]).unwrap();
+/// assert_eq!(a.det().unwrap(), -2.0);
+/// assert_eq!(a.mul(&Matrix::identity(2)).unwrap(), a);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix {
+    cols: usize,
+    data: Vec<f64>,
+}
+
+impl Matrix {
+    /// Creates a matrix from its rows, returning an error if there are none or they have
+    /// different lengths.
+    pub fn from_rows(rows: Vec<Vec<f64>>) -> Result<Self> {
+        let cols = rows.first().map_or(0, Vec::len);
+        if cols == 0 {
+            return Err(ArgonError::dimension(
+                "a matrix needs at least one row and one column",
+            ));
+        }
+        if let Some(row) = rows.iter().find(|row| row.len() != cols) {
+            return Err(ArgonError::dimension(format!(
+                "matrix rows must all have the same length, got {} and {}",
+                cols,
+                row.len()
+            )));
+        }
+        Ok(Matrix {
+            cols,
+            data: rows.concat(),
+        })
+    }
+
+    /// Creates a matrix from a list of values if every value is a list of numbers and the lists
+    /// all have the same length.
+    pub fn from_values(items: &[Value]) -> Option<Self> {
+        let rows = items
+            .iter()
+            .map(|item| match item {
+                Value::List(row) => row.iter().map(Value::as_number).collect(),
+                _ => None,
+            })
+            .collect::<Option<Vec<Vec<f64>>>>()?;
+        Matrix::from_rows(rows).ok()
+    }
+
+    /// Creates a `rows` by `cols` matrix whose entry at `(row, col)` is `f(row, col)`.
+    fn from_fn(rows: usize, cols: usize, mut f: impl FnMut(usize, usize) -> f64) -> Self {
+        Matrix {
+            cols,
+            data: (0..rows * cols).map(|i| f(i / cols, i % cols)).collect(),
+        }
+    }
+
+    /// Creates the `n` by `n` identity matrix.
+    pub fn identity(n: usize) -> Self {
+        Matrix::from_fn(n, n, |row, col| if row == col { 1.0 } else { 0.0 })
+    }
+
+    /// Returns the number of rows.
+    pub fn rows(&self) -> usize {
+        self.data.len() / self.cols
+    }
+
+    /// Returns the number of columns.
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Returns the entries, row by row.
+    pub fn values(&self) -> &[f64] {
+        &self.data
+    }
+
+    /// Returns the rows of the matrix as separate vectors.
+    pub fn to_rows(&self) -> Vec<Vec<f64>> {
+        self.data.chunks(self.cols).map(<[f64]>::to_vec).collect()
+    }
+
+    /// Describes the size of the matrix for error messages, such as `2x3`.
+    fn shape(&self) -> String {
+        format!("{}x{}", self.rows(), self.cols)
+    }
+
+    /// Returns the largest absolute value of any entry, or 1 if the matrix is all zeros, as the
+    /// scale that [`TOLERANCE`] is relative to.
+    fn scale(&self) -> f64 {
+        let largest = self
+            .data
+            .iter()
+            .fold(0.0, |largest: f64, x| largest.max(x.abs()));
+        if largest > 0.0 {
+            largest
+        } else {
+            1.0
+        }
+    }
+
+    /// Returns a dimension error unless the matrix is square.
+    fn require_square(&self, function: &str) -> Result<()> {
+        if self.rows() != self.cols {
+            return Err(ArgonError::dimension(format!(
+                "{} expects a square matrix, got a {} matrix",
+                function,
+                self.shape()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Returns the top left `n` by `n` part of the matrix.
+    fn leading(&self, n: usize) -> Self {
+        Matrix::from_fn(n, n, |row, col| self[(row, col)])
+    }
+
+    /// Swaps two rows in place.
+    fn swap_rows(&mut self, a: usize, b: usize) {
+        for col in 0..self.cols {
+            self.data.swap(a * self.cols + col, b * self.cols + col);
+        }
+    }
+
+    /// Returns the row at or below `start` with the largest entry in `col`, for partial pivoting.
+    fn pivot_row(&self, start: usize, col: usize) -> usize {
+        (start..self.rows())
+            .max_by(|&a, &b| self[(a, col)].abs().total_cmp(&self[(b, col)].abs()))
+            .unwrap_or(start)
+    }
+
+    /// Returns the matrix with its rows and columns swapped.
+    pub fn transpose(&self) -> Self {
+        Matrix::from_fn(self.cols, self.rows(), |row, col| self[(col, row)])
+    }
+
+    /// Applies `f` to every entry.
+    pub fn map(&self, f: impl Fn(f64) -> f64) -> Self {
+        Matrix {
+            cols: self.cols,
+            data: self.data.iter().map(|&x| f(x)).collect(),
+        }
+    }
+
+    /// Combines the entries of two matrices of the same size with `f`. `operation` describes `f`
+    /// in the error returned when the sizes differ, such as "add".
+    pub fn zip_with(
+        &self,
+        other: &Matrix,
+        operation: &str,
+        f: impl Fn(f64, f64) -> f64,
+    ) -> Result<Self> {
+        if self.rows() != other.rows() || self.cols != other.cols {
+            return Err(ArgonError::dimension(format!(
+                "cannot {} a {} matrix and a {} matrix",
+                operation,
+                self.shape(),
+                other.shape()
+            )));
+        }
+        Ok(Matrix {
+            cols: self.cols,
+            data: self
+                .data
+                .iter()
+                .zip(&other.data)
+                .map(|(&a, &b)| f(a, b))
+                .collect(),
+        })
+    }
+
+    /// Multiplies two matrices, returning an error unless `self` has as many columns as `other`
+    /// has rows.
+    pub fn mul(&self, other: &Matrix) -> Result<Self> {
+        if self.cols != other.rows() {
+            return Err(ArgonError::dimension(format!(
+                "cannot multiply a {} matrix by a {} matrix",
+                self.shape(),
+                other.shape()
+            )));
+        }
+        Ok(Matrix::from_fn(self.rows(), other.cols, |row, col| {
+            (0..self.cols)
+                .map(|k| self[(row, k)] * other[(k, col)])
+                .sum()
+        }))
+    }
+
+    /// Raises a square matrix to a whole power. Negative powers are powers of the inverse.
+    pub fn pow(&self, exponent: i64) -> Result<Self> {
+        if self.rows() != self.cols {
+            return Err(ArgonError::dimension(format!(
+                "cannot raise a {} matrix to a power",
+                self.shape()
+            )));
+        }
+        let mut base = if exponent < 0 {
+            self.inverse()?
+        } else {
+            self.clone()
+        };
+        let mut exponent = exponent.unsigned_abs();
+        let mut result = Matrix::identity(self.rows());
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.mul(&base)?;
+            }
+            base = base.mul(&base)?;
+            exponent >>= 1;
+        }
+        Ok(result)
+    }
+
+    /// Returns the sum of the entries on the diagonal of a square matrix.
+    pub fn trace(&self) -> Result<f64> {
+        self.require_square("trace")?;
+        Ok((0..self.rows()).map(|i| self[(i, i)]).sum())
+    }
+
+    /// Splits a square matrix into `(L, U, P)`, where `L` is lower triangular with ones on its
+    /// diagonal, `U` is upper triangular and `P` is a permutation matrix, such that
+    /// `P * self = L * U`.
+    pub fn lu(&self) -> Result<(Matrix, Matrix, Matrix)> {
+        self.require_square("lu")?;
+        let (l, u, permutation, _) = self.decompose();
+        let p = Matrix::from_fn(self.rows(), self.rows(), |row, col| {
+            if permutation[row] == col {
+                1.0
+            } else {
+                0.0
+            }
+        });
+        Ok((l, u, p))
+    }
+
+    /// Runs Gaussian elimination with partial pivoting on a square matrix. Returns `L`, `U`, the
+    /// original row that ended up in each row, and whether an odd number of rows were swapped.
+    fn decompose(&self) -> (Matrix, Matrix, Vec<usize>, bool) {
+        let n = self.rows();
+        let mut l = Matrix::identity(n);
+        let mut u = self.clone();
+        let mut permutation: Vec<usize> = (0..n).collect();
+        let mut odd = false;
+
+        for col in 0..n {
+            let pivot = u.pivot_row(col, col);
+            if pivot != col {
+                u.swap_rows(col, pivot);
+                permutation.swap(col, pivot);
+                // The multipliers already found move with their rows
+                for k in 0..col {
+                    l.data.swap(col * n + k, pivot * n + k);
+                }
+                odd = !odd;
+            }
+            if u[(col, col)] == 0.0 {
+                continue;
+            }
+            for row in col + 1..n {
+                let factor = u[(row, col)] / u[(col, col)];
+                l[(row, col)] = factor;
+                u[(row, col)] = 0.0;
+                for k in col + 1..n {
+                    u[(row, k)] -= factor * u[(col, k)];
+                }
+            }
+        }
+        (l, u, permutation, odd)
+    }
+
+    /// Returns the determinant of a square matrix.
+    pub fn det(&self) -> Result<f64> {
+        self.require_square("det")?;
+        let (_, u, _, odd) = self.decompose();
+        let product: f64 = (0..self.rows()).map(|i| u[(i, i)]).product();
+        Ok(if odd { -product } else { product })
+    }
+
+    /// Returns the inverse of a square matrix, or a domain error if the matrix is singular.
+    pub fn inverse(&self) -> Result<Self> {
+        self.require_square("inv")?;
+        let n = self.rows();
+        let tolerance = TOLERANCE * self.scale();
+        let mut a = self.clone();
+        let mut inverse = Matrix::identity(n);
+
+        for col in 0..n {
+            let pivot = a.pivot_row(col, col);
+            if a[(pivot, col)].abs() <= tolerance {
+                return Err(ArgonError::domain(
+                    "inv",
+                    "is not defined for singular matrices",
+                ));
+            }
+            a.swap_rows(col, pivot);
+            inverse.swap_rows(col, pivot);
+
+            let divisor = a[(col, col)];
+            for k in 0..n {
+                a[(col, k)] /= divisor;
+                inverse[(col, k)] /= divisor;
+            }
+            for row in (0..n).filter(|&row| row != col) {
+                let factor = a[(row, col)];
+                for k in 0..n {
+                    a[(row, k)] -= factor * a[(col, k)];
+                    inverse[(row, k)] -= factor * inverse[(col, k)];
+                }
+            }
+        }
+        Ok(inverse)
+    }
+
+    /// Returns the number of linearly independent rows.
+    pub fn rank(&self) -> usize {
+        let tolerance = TOLERANCE * self.scale();
+        let mut a = self.clone();
+        let mut rank = 0;
+
+        for col in 0..self.cols {
+            if rank == self.rows() {
+                break;
+            }
+            let pivot = a.pivot_row(rank, col);
+            if a[(pivot, col)].abs() <= tolerance {
+                continue;
+            }
+            a.swap_rows(rank, pivot);
+            for row in rank + 1..self.rows() {
+                let factor = a[(row, col)] / a[(rank, col)];
+                for k in col..self.cols {
+                    a[(row, k)] -= factor * a[(rank, k)];
+                }
+            }
+            rank += 1;
+        }
+        rank
+    }
+
+    /// Splits the matrix into `(Q, R)`, where `Q` is orthogonal and `R` is upper triangular with
+    /// no negative numbers on its diagonal, such that `self = Q * R`.
+    pub fn qr(&self) -> (Matrix, Matrix) {
+        let (m, n) = (self.rows(), self.cols);
+        let mut q = Matrix::identity(m);
+        let mut r = self.clone();
+
+        // Each Householder reflection clears the entries below the diagonal in one column
+        for k in 0..n.min(m.saturating_sub(1)) {
+            let norm = (k..m).map(|i| r[(i, k)].powi(2)).sum::<f64>().sqrt();
+            if norm == 0.0 {
+                continue;
+            }
+            let alpha = if r[(k, k)] > 0.0 { -norm } else { norm };
+            let mut v = vec![0.0; m];
+            v[k] = r[(k, k)] - alpha;
+            for i in k + 1..m {
+                v[i] = r[(i, k)];
+            }
+            let length = v.iter().map(|x| x * x).sum::<f64>();
+
+            for col in 0..n {
+                let dot = (k..m).map(|i| v[i] * r[(i, col)]).sum::<f64>();
+                for i in k..m {
+                    r[(i, col)] -= 2.0 * dot / length * v[i];
+                }
+            }
+            for row in 0..m {
+                let dot = (k..m).map(|j| q[(row, j)] * v[j]).sum::<f64>();
+                for j in k..m {
+                    q[(row, j)] -= 2.0 * dot / length * v[j];
+                }
+            }
+            for i in k + 1..m {
+                r[(i, k)] = 0.0;
+            }
+        }
+
+        for k in 0..n.min(m) {
+            if r[(k, k)] < 0.0 {
+                for col in k..n {
+                    r[(k, col)] = -r[(k, col)];
+                }
+                for row in 0..m {
+                    q[(row, k)] = -q[(row, k)];
+                }
+            }
+        }
+        (q, r)
+    }
+
+    /// Returns the eigenvalues of a square matrix from smallest to largest, using the shifted QR
+    /// algorithm. Returns a domain error if any of them are complex.
+    pub fn eigenvalues(&self) -> Result<Vec<f64>> {
+        self.require_square("eig")?;
+        let tolerance = TOLERANCE * self.scale();
+        let is_zero = |x: f64| x.abs() <= tolerance;
+        let mut a = self.clone();
+        let mut eigenvalues = Vec::with_capacity(self.rows());
+        let mut iterations = 0;
+        let mut n = self.rows();
+
+        while n > 0 {
+            // An eigenvalue has converged once the rest of its row is zero
+            if (0..n - 1).all(|col| is_zero(a[(n - 1, col)])) {
+                eigenvalues.push(a[(n - 1, n - 1)]);
+                a = a.leading(n - 1);
+                n -= 1;
+                iterations = 0;
+                continue;
+            }
+            if (0..n - 2).all(|col| is_zero(a[(n - 1, col)]) && is_zero(a[(n - 2, col)])) {
+                let (first, second) = a.bottom_eigenvalues().ok_or_else(|| {
+                    ArgonError::domain("eig", "is only defined for real eigenvalues")
+                })?;
+                eigenvalues.extend([first, second]);
+                a = a.leading(n - 2);
+                n -= 2;
+                iterations = 0;
+                continue;
+            }
+            if iterations == MAX_EIGEN_ITERATIONS {
+                return Err(ArgonError::domain("eig", "did not converge"));
+            }
+
+            // Shift by the eigenvalue of the bottom corner closest to the last diagonal entry
+            let last = a[(n - 1, n - 1)];
+            let mut shift = match a.bottom_eigenvalues() {
+                Some((first, second)) if (first - last).abs() < (second - last).abs() => first,
+                Some((_, second)) => second,
+                None => last,
+            };
+            // Every so often, shift somewhere else in case the iteration has got stuck
+            if iterations % 10 == 9 {
+                shift += a[(n - 1, n - 2)].abs();
+            }
+            let (q, r) = a.clone().sub_identity(shift).qr();
+            a = r.mul(&q)?.sub_identity(-shift);
+            iterations += 1;
+        }
+
+        eigenvalues.sort_by(f64::total_cmp);
+        Ok(eigenvalues)
+    }
+
+    /// Returns the eigenvalues of the bottom right 2x2 corner of a square matrix, or `None` if
+    /// they are complex.
+    fn bottom_eigenvalues(&self) -> Option<(f64, f64)> {
+        let n = self.rows();
+        let (a, b) = (self[(n - 2, n - 2)], self[(n - 2, n - 1)]);
+        let (c, d) = (self[(n - 1, n - 2)], self[(n - 1, n - 1)]);
+        let half_trace = (a + d) / 2.0;
+        let discriminant = ((a - d) / 2.0).powi(2) + b * c;
+        if discriminant < -TOLERANCE * self.scale().powi(2) {
+            return None;
+        }
+        let root = discriminant.max(0.0).sqrt();
+        Some((half_trace - root, half_trace + root))
+    }
+
+    /// Subtracts `shift` times the identity matrix from a square matrix.
+    fn sub_identity(mut self, shift: f64) -> Self {
+        for i in 0..self.rows() {
+            self[(i, i)] -= shift;
+        }
+        self
+    }
+}
+
+impl Index<(usize, usize)> for Matrix {
+    type Output = f64;
+
+    fn index(&self, (row, col): (usize, usize)) -> &f64 {
+        &self.data[row * self.cols + col]
+    }
+}
+
+impl IndexMut<(usize, usize)> for Matrix {
+    fn index_mut(&mut self, (row, col): (usize, usize)) -> &mut f64 {
+        &mut self.data[row * self.cols + col]
+    }
+}
+
+impl fmt::Display for Matrix {
+    /// Formats the matrix as a list of its rows, such as `[[1, 2], [3, 4]]`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+        for (i, row) in self.data.chunks(self.cols).enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "[")?;
+            for (j, x) in row.iter().enumerate() {
+                if j > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}", x)?;
+            }
+            write!(f, "]")?;
+        }
+        write!(f, "]")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matrix(rows: &[&[f64]]) -> Matrix {
+        Matrix::from_rows(rows.iter().map(|row| row.to_vec()).collect()).unwrap()
+    }
+
+    fn assert_close(a: &Matrix, b: &Matrix) {
+        assert_eq!((a.rows(), a.cols), (b.rows(), b.cols));
+        for (x, y) in a.data.iter().zip(&b.data) {
+            assert!((x - y).abs() < 1e-9, "{} is not close to {}", a, b);
+        }
+    }
+
+    #[test]
+    fn test_from_rows() {
+        assert!(Matrix::from_rows(vec![]).is_err());
+        assert!(Matrix::from_rows(vec![vec![]]).is_err());
+        let err = Matrix::from_rows(vec![vec![1.0, 2.0], vec![3.0]]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "matrix rows must all have the same length, got 2 and 1"
+        );
+        assert_eq!(
+            matrix(&[&[1.0, 2.0], &[3.0, 4.0]]).to_string(),
+            "[[1, 2], [3, 4]]"
+        );
+    }
+
+    #[test]
+    fn test_mul() {
+        let a = matrix(&[&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]]);
+        let b = matrix(&[&[1.0], &[0.0], &[-1.0]]);
+        assert_eq!(a.mul(&b).unwrap(), matrix(&[&[-2.0], &[-2.0]]));
+        assert_eq!(
+            a.mul(&a).unwrap_err().to_string(),
+            "cannot multiply a 2x3 matrix by a 2x3 matrix"
+        );
+        assert_eq!(a.transpose().rows(), 3);
+
+        let c = matrix(&[&[1.0, 1.0], &[1.0, 0.0]]);
+        assert_eq!(c.pow(10).unwrap()[(0, 1)], 55.0);
+        assert_close(&c.pow(-1).unwrap().mul(&c).unwrap(), &Matrix::identity(2));
+        assert!(a.pow(2).is_err());
+    }
+
+    #[test]
+    fn test_det_and_inverse() {
+        let a = matrix(&[&[2.0, 0.0, 1.0], &[1.0, 3.0, 2.0], &[1.0, 1.0, 2.0]]);
+        assert!((a.det().unwrap() - 6.0).abs() < 1e-12);
+        assert_close(&a.mul(&a.inverse().unwrap()).unwrap(), &Matrix::identity(3));
+        assert_eq!(matrix(&[&[0.0, 1.0], &[1.0, 0.0]]).det().unwrap(), -1.0);
+
+        let singular = matrix(&[&[1.0, 2.0], &[2.0, 4.0]]);
+        assert_eq!(singular.det().unwrap(), 0.0);
+        assert_eq!(
+            singular.inverse().unwrap_err().to_string(),
+            "inv is not defined for singular matrices"
+        );
+        assert_eq!(
+            matrix(&[&[1.0, 2.0]]).det().unwrap_err().to_string(),
+            "det expects a square matrix, got a 1x2 matrix"
+        );
+    }
+
+    #[test]
+    fn test_rank_and_trace() {
+        assert_eq!(matrix(&[&[1.0, 2.0], &[2.0, 4.0]]).rank(), 1);
+        assert_eq!(matrix(&[&[1.0, 2.0, 3.0], &[0.0, 1.0, 1.0]]).rank(), 2);
+        assert_eq!(matrix(&[&[0.0, 0.0]]).rank(), 0);
+        assert_eq!(Matrix::identity(4).rank(), 4);
+        assert_eq!(matrix(&[&[1.0, 2.0], &[3.0, 4.0]]).trace().unwrap(), 5.0);
+    }
+
+    #[test]
+    fn test_lu() {
+        let a = matrix(&[&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0], &[7.0, 8.0, 10.0]]);
+        let (l, u, p) = a.lu().unwrap();
+        assert_close(&p.mul(&a).unwrap(), &l.mul(&u).unwrap());
+        for row in 0..3 {
+            assert_eq!(l[(row, row)], 1.0);
+            for col in 0..row {
+                assert_eq!(u[(row, col)], 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_qr() {
+        let a = matrix(&[
+            &[12.0, -51.0, 4.0],
+            &[6.0, 167.0, -68.0],
+            &[-4.0, 24.0, -41.0],
+        ]);
+        let (q, r) = a.qr();
+        assert_close(&q.mul(&r).unwrap(), &a);
+        assert_close(&q.transpose().mul(&q).unwrap(), &Matrix::identity(3));
+        assert!((r[(0, 0)] - 14.0).abs() < 1e-9);
+
+        let tall = matrix(&[&[1.0, 2.0], &[3.0, 4.0], &[5.0, 6.0]]);
+        let (q, r) = tall.qr();
+        assert_close(&q.mul(&r).unwrap(), &tall);
+    }
+
+    #[test]
+    fn test_eigenvalues() {
+        let close = |values: Vec<f64>, expected: &[f64]| {
+            assert_eq!(values.len(), expected.len());
+            for (x, y) in values.iter().zip(expected) {
+                assert!(
+                    (x - y).abs() < 1e-8,
+                    "{:?} is not close to {:?}",
+                    values,
+                    expected
+                );
+            }
+        };
+        close(
+            matrix(&[&[2.0, 1.0], &[1.0, 2.0]]).eigenvalues().unwrap(),
+            &[1.0, 3.0],
+        );
+        close(
+            matrix(&[&[2.0, 0.0, 0.0], &[0.0, 3.0, 4.0], &[0.0, 4.0, 9.0]])
+                .eigenvalues()
+                .unwrap(),
+            &[1.0, 2.0, 11.0],
+        );
+        close(
+            matrix(&[&[4.0, 1.0, 2.0], &[0.0, 3.0, 1.0], &[0.0, 0.0, 5.0]])
+                .eigenvalues()
+                .unwrap(),
+            &[3.0, 4.0, 5.0],
+        );
+        close(
+            matrix(&[&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0], &[7.0, 8.0, 10.0]])
+                .eigenvalues()
+                .unwrap()
+                .into_iter()
+                .map(|x| (x * 1e4).round() / 1e4)
+                .collect(),
+            &[-0.9057, 0.1982, 16.7075],
+        );
+
+        // A rotation only has complex eigenvalues
+        let rotation = matrix(&[&[0.0, -1.0, 0.0], &[1.0, 0.0, 0.0], &[0.0, 0.0, 1.0]]);
+        assert!(rotation.eigenvalues().is_err());
+    }
+}
@@ -0,0 +1,784 @@
+//! The operations shared by every kind of number, so operators and built-in functions are written
+//! once rather than once for each kind.
+//!
+//! A [`Value`] holds one of several kinds of number: a floating point number, a large integer, an
+//! exact fraction, a decimal or a complex number. Which kind a number literal becomes is chosen
+//! at runtime by the [`Mode`](crate::Mode). Each kind implements [`Number`], which the
+//! interpreter uses to apply operators, and [`Elementary`], which the built-in functions such as
+//! `sqrt` and `sin` are written against.
+//!
+//! Adding a new kind of number means adding a `Value` variant and implementing these traits for
+//! it, rather than writing another version of every operator and function.
+
+use std::cmp::Ordering;
+
+use num_complex::Complex64;
+use num_traits::{Signed, Zero};
+
+use crate::decimal::Decimal;
+use crate::functions;
+use crate::integer::{self, BigInt};
+use crate::rational::{self, BigRational};
+use crate::value::Value;
+
+/// Arithmetic and comparison on one kind of number.
+///
+/// Operations that can have no result of the same kind return `None`, such as `1/0` for exact
+/// fractions or `7/2` for integers. The interpreter then calculates them with floating point
+/// numbers instead, using [`Number::to_f64`].
+///
+/// # Example
+/// ```
+/// # use argon::number::Number;
+/// # use argon::rational::BigRational;
+/// let half = BigRational::new(1.into(), 2.into());
+/// assert_eq!(half.add(&half), BigRational::from_integer(1.into()));
+/// assert_eq!(half.try_div(&BigRational::from_integer(0.into())), None);
+/// ```
+pub trait Number: Clone + PartialEq + Into<Value> {
+    /// What numbers of this kind are called in error messages, such as "complex numbers".
+    const NAME: &'static str;
+
+    /// Whether numbers of this kind can be compared with `<` and `>`.
+    const ORDERED: bool = true;
+
+    fn add(&self, other: &Self) -> Self;
+
+    fn sub(&self, other: &Self) -> Self;
+
+    fn mul(&self, other: &Self) -> Self;
+
+    fn try_div(&self, other: &Self) -> Option<Self>;
+
+    /// Returns the remainder of dividing by `other`, which has the same sign as `self`.
+    fn try_rem(&self, other: &Self) -> Option<Self>;
+
+    fn try_pow(&self, exponent: &Self) -> Option<Self>;
+
+    /// Compares two numbers, returning `None` if they can't be ordered, such as `NaN`.
+    fn compare(&self, other: &Self) -> Option<Ordering>;
+
+    /// Converts the number to the closest floating point number, or `None` if it isn't real.
+    fn to_f64(&self) -> Option<f64>;
+
+    /// Returns the whole number `n` as the same kind of number, with the same precision as `self`
+    /// for decimals.
+    fn whole(&self, n: usize) -> Self;
+}
+
+/// The functions of one number that the built-in functions are written against. Each returns
+/// `None` if it has no result of the same kind, such as the square root of a negative decimal or
+/// the sine of a fraction, in which case it is calculated with floating point numbers instead.
+///
+/// # Example
+/// ```
+/// # use argon::number::Elementary;
+/// # use argon::rational::BigRational;
+/// let half = BigRational::new(1.into(), 2.into());
+/// assert_eq!(Elementary::recip(&half), Some(BigRational::from_integer(2.into())));
+/// assert_eq!(half.sin(), None);
+/// assert_eq!(Elementary::sqrt(&4.0), Some(2.0));
+/// ```
+pub trait Elementary: Number {
+    fn sqrt(&self) -> Option<Self>;
+
+    fn root(&self, n: &Self) -> Option<Self>;
+
+    fn exp(&self) -> Option<Self>;
+
+    fn ln(&self) -> Option<Self>;
+
+    fn log10(&self) -> Option<Self>;
+
+    fn log2(&self) -> Option<Self>;
+
+    fn log(&self, base: &Self) -> Option<Self>;
+
+    fn abs(&self) -> Option<Self>;
+
+    fn recip(&self) -> Option<Self>;
+
+    fn sin(&self) -> Option<Self>;
+
+    fn cos(&self) -> Option<Self>;
+
+    fn tan(&self) -> Option<Self>;
+
+    fn asin(&self) -> Option<Self>;
+
+    fn acos(&self) -> Option<Self>;
+
+    fn atan(&self) -> Option<Self>;
+
+    fn sinh(&self) -> Option<Self>;
+
+    fn cosh(&self) -> Option<Self>;
+
+    fn tanh(&self) -> Option<Self>;
+
+    fn asinh(&self) -> Option<Self>;
+
+    fn acosh(&self) -> Option<Self>;
+
+    fn atanh(&self) -> Option<Self>;
+
+    fn floor(&self) -> Option<Self>;
+
+    fn ceil(&self) -> Option<Self>;
+
+    fn round(&self) -> Option<Self>;
+}
+
+impl Number for f64 {
+    const NAME: &'static str = "numbers";
+
+    fn add(&self, other: &Self) -> Self {
+        self + other
+    }
+
+    fn sub(&self, other: &Self) -> Self {
+        self - other
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        self * other
+    }
+
+    fn try_div(&self, other: &Self) -> Option<Self> {
+        Some(self / other)
+    }
+
+    fn try_rem(&self, other: &Self) -> Option<Self> {
+        Some(self % other)
+    }
+
+    fn try_pow(&self, exponent: &Self) -> Option<Self> {
+        Some(self.powf(*exponent))
+    }
+
+    fn compare(&self, other: &Self) -> Option<Ordering> {
+        self.partial_cmp(other)
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        Some(*self)
+    }
+
+    fn whole(&self, n: usize) -> Self {
+        n as f64
+    }
+}
+
+impl Elementary for f64 {
+    fn sqrt(&self) -> Option<Self> {
+        Some(f64::sqrt(*self))
+    }
+
+    fn root(&self, n: &Self) -> Option<Self> {
+        Some(self.powf(1.0 / n))
+    }
+
+    fn exp(&self) -> Option<Self> {
+        Some(f64::exp(*self))
+    }
+
+    fn ln(&self) -> Option<Self> {
+        Some(f64::ln(*self))
+    }
+
+    fn log10(&self) -> Option<Self> {
+        Some(f64::log10(*self))
+    }
+
+    fn log2(&self) -> Option<Self> {
+        Some(f64::log2(*self))
+    }
+
+    fn log(&self, base: &Self) -> Option<Self> {
+        Some(f64::log(*self, *base))
+    }
+
+    fn abs(&self) -> Option<Self> {
+        Some(f64::abs(*self))
+    }
+
+    fn recip(&self) -> Option<Self> {
+        Some(f64::recip(*self))
+    }
+
+    fn sin(&self) -> Option<Self> {
+        Some(f64::sin(*self))
+    }
+
+    fn cos(&self) -> Option<Self> {
+        Some(f64::cos(*self))
+    }
+
+    fn tan(&self) -> Option<Self> {
+        Some(f64::tan(*self))
+    }
+
+    fn asin(&self) -> Option<Self> {
+        Some(f64::asin(*self))
+    }
+
+    fn acos(&self) -> Option<Self> {
+        Some(f64::acos(*self))
+    }
+
+    fn atan(&self) -> Option<Self> {
+        Some(f64::atan(*self))
+    }
+
+    fn sinh(&self) -> Option<Self> {
+        Some(f64::sinh(*self))
+    }
+
+    fn cosh(&self) -> Option<Self> {
+        Some(f64::cosh(*self))
+    }
+
+    fn tanh(&self) -> Option<Self> {
+        Some(f64::tanh(*self))
+    }
+
+    fn asinh(&self) -> Option<Self> {
+        Some(f64::asinh(*self))
+    }
+
+    fn acosh(&self) -> Option<Self> {
+        Some(f64::acosh(*self))
+    }
+
+    fn atanh(&self) -> Option<Self> {
+        Some(f64::atanh(*self))
+    }
+
+    fn floor(&self) -> Option<Self> {
+        Some(f64::floor(*self))
+    }
+
+    fn ceil(&self) -> Option<Self> {
+        Some(f64::ceil(*self))
+    }
+
+    fn round(&self) -> Option<Self> {
+        Some(f64::round(*self))
+    }
+}
+
+impl Number for BigInt {
+    const NAME: &'static str = "numbers";
+
+    fn add(&self, other: &Self) -> Self {
+        self + other
+    }
+
+    fn sub(&self, other: &Self) -> Self {
+        self - other
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        self * other
+    }
+
+    /// Divides exactly, returning `None` unless the result is a whole number.
+    fn try_div(&self, other: &Self) -> Option<Self> {
+        (!other.is_zero() && (self % other).is_zero()).then(|| self / other)
+    }
+
+    fn try_rem(&self, other: &Self) -> Option<Self> {
+        (!other.is_zero()).then(|| self % other)
+    }
+
+    fn try_pow(&self, exponent: &Self) -> Option<Self> {
+        integer::pow(self, exponent)
+    }
+
+    fn compare(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        Some(integer::to_f64(self))
+    }
+
+    fn whole(&self, n: usize) -> Self {
+        BigInt::from(n)
+    }
+}
+
+impl Number for BigRational {
+    const NAME: &'static str = "numbers";
+
+    fn add(&self, other: &Self) -> Self {
+        self + other
+    }
+
+    fn sub(&self, other: &Self) -> Self {
+        self - other
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        self * other
+    }
+
+    fn try_div(&self, other: &Self) -> Option<Self> {
+        (!other.is_zero()).then(|| self / other)
+    }
+
+    fn try_rem(&self, other: &Self) -> Option<Self> {
+        rational::remainder(self, other)
+    }
+
+    fn try_pow(&self, exponent: &Self) -> Option<Self> {
+        rational::pow(self, exponent)
+    }
+
+    fn compare(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        Some(rational::to_f64(self))
+    }
+
+    fn whole(&self, n: usize) -> Self {
+        BigRational::from_integer(n.into())
+    }
+}
+
+/// Fractions only have the functions whose result is always a fraction. The others, such as
+/// `sqrt` and `sin`, are calculated with floating point numbers.
+impl Elementary for BigRational {
+    fn sqrt(&self) -> Option<Self> {
+        None
+    }
+
+    fn root(&self, _: &Self) -> Option<Self> {
+        None
+    }
+
+    fn exp(&self) -> Option<Self> {
+        None
+    }
+
+    fn ln(&self) -> Option<Self> {
+        None
+    }
+
+    fn log10(&self) -> Option<Self> {
+        None
+    }
+
+    fn log2(&self) -> Option<Self> {
+        None
+    }
+
+    fn log(&self, _: &Self) -> Option<Self> {
+        None
+    }
+
+    fn abs(&self) -> Option<Self> {
+        Some(Signed::abs(self))
+    }
+
+    /// The reciprocal of zero is infinite, which isn't a fraction.
+    fn recip(&self) -> Option<Self> {
+        (!self.is_zero()).then(|| BigRational::recip(self))
+    }
+
+    fn sin(&self) -> Option<Self> {
+        None
+    }
+
+    fn cos(&self) -> Option<Self> {
+        None
+    }
+
+    fn tan(&self) -> Option<Self> {
+        None
+    }
+
+    fn asin(&self) -> Option<Self> {
+        None
+    }
+
+    fn acos(&self) -> Option<Self> {
+        None
+    }
+
+    fn atan(&self) -> Option<Self> {
+        None
+    }
+
+    fn sinh(&self) -> Option<Self> {
+        None
+    }
+
+    fn cosh(&self) -> Option<Self> {
+        None
+    }
+
+    fn tanh(&self) -> Option<Self> {
+        None
+    }
+
+    fn asinh(&self) -> Option<Self> {
+        None
+    }
+
+    fn acosh(&self) -> Option<Self> {
+        None
+    }
+
+    fn atanh(&self) -> Option<Self> {
+        None
+    }
+
+    fn floor(&self) -> Option<Self> {
+        Some(BigRational::floor(self))
+    }
+
+    fn ceil(&self) -> Option<Self> {
+        Some(BigRational::ceil(self))
+    }
+
+    fn round(&self) -> Option<Self> {
+        Some(BigRational::round(self))
+    }
+}
+
+impl Number for Decimal {
+    const NAME: &'static str = "numbers";
+
+    fn add(&self, other: &Self) -> Self {
+        self + other
+    }
+
+    fn sub(&self, other: &Self) -> Self {
+        self - other
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        self * other
+    }
+
+    fn try_div(&self, other: &Self) -> Option<Self> {
+        Decimal::checked_div(self, other)
+    }
+
+    fn try_rem(&self, other: &Self) -> Option<Self> {
+        self.remainder(other)
+    }
+
+    fn try_pow(&self, exponent: &Self) -> Option<Self> {
+        self.pow(exponent)
+    }
+
+    fn compare(&self, other: &Self) -> Option<Ordering> {
+        self.partial_cmp(other)
+    }
+
+    fn to_f64(&self) -> Option<f64> {
+        Some(Decimal::to_f64(self))
+    }
+
+    fn whole(&self, n: usize) -> Self {
+        Decimal::from_integer(n.into(), self.precision())
+    }
+}
+
+/// Decimals round through an exact fraction, so the result keeps every digit. They have no
+/// hyperbolic functions, which are calculated with floating point numbers.
+impl Elementary for Decimal {
+    fn sqrt(&self) -> Option<Self> {
+        Decimal::sqrt(self)
+    }
+
+    fn root(&self, n: &Self) -> Option<Self> {
+        let one = Decimal::from_integer(1.into(), n.precision());
+        self.pow(&one.checked_div(n)?)
+    }
+
+    fn exp(&self) -> Option<Self> {
+        Decimal::exp(self)
+    }
+
+    fn ln(&self) -> Option<Self> {
+        Decimal::ln(self)
+    }
+
+    fn log10(&self) -> Option<Self> {
+        Decimal::log(self, &Decimal::from_integer(10.into(), self.precision()))
+    }
+
+    fn log2(&self) -> Option<Self> {
+        Decimal::log(self, &Decimal::from_integer(2.into(), self.precision()))
+    }
+
+    fn log(&self, base: &Self) -> Option<Self> {
+        Decimal::log(self, base)
+    }
+
+    fn abs(&self) -> Option<Self> {
+        Some(Decimal::abs(self))
+    }
+
+    fn recip(&self) -> Option<Self> {
+        Decimal::from_integer(1.into(), self.precision()).checked_div(self)
+    }
+
+    fn sin(&self) -> Option<Self> {
+        Decimal::sin(self)
+    }
+
+    fn cos(&self) -> Option<Self> {
+        Decimal::cos(self)
+    }
+
+    fn tan(&self) -> Option<Self> {
+        Decimal::tan(self)
+    }
+
+    fn asin(&self) -> Option<Self> {
+        Decimal::asin(self)
+    }
+
+    fn acos(&self) -> Option<Self> {
+        Decimal::acos(self)
+    }
+
+    fn atan(&self) -> Option<Self> {
+        Decimal::atan(self)
+    }
+
+    fn sinh(&self) -> Option<Self> {
+        None
+    }
+
+    fn cosh(&self) -> Option<Self> {
+        None
+    }
+
+    fn tanh(&self) -> Option<Self> {
+        None
+    }
+
+    fn asinh(&self) -> Option<Self> {
+        None
+    }
+
+    fn acosh(&self) -> Option<Self> {
+        None
+    }
+
+    fn atanh(&self) -> Option<Self> {
+        None
+    }
+
+    fn floor(&self) -> Option<Self> {
+        Some(Decimal::from_rational(
+            &self.to_rational().floor(),
+            self.precision(),
+        ))
+    }
+
+    fn ceil(&self) -> Option<Self> {
+        Some(Decimal::from_rational(
+            &self.to_rational().ceil(),
+            self.precision(),
+        ))
+    }
+
+    fn round(&self) -> Option<Self> {
+        Some(Decimal::from_rational(
+            &self.to_rational().round(),
+            self.precision(),
+        ))
+    }
+}
+
+impl Number for Complex64 {
+    const NAME: &'static str = "complex numbers";
+    const ORDERED: bool = false;
+
+    fn add(&self, other: &Self) -> Self {
+        self + other
+    }
+
+    fn sub(&self, other: &Self) -> Self {
+        self - other
+    }
+
+    fn mul(&self, other: &Self) -> Self {
+        self * other
+    }
+
+    fn try_div(&self, other: &Self) -> Option<Self> {
+        Some(self / other)
+    }
+
+    /// Complex numbers have no remainder.
+    fn try_rem(&self, _: &Self) -> Option<Self> {
+        None
+    }
+
+    fn try_pow(&self, exponent: &Self) -> Option<Self> {
+        Some(functions::complex_pow(*self, *exponent))
+    }
+
+    fn compare(&self, _: &Self) -> Option<Ordering> {
+        None
+    }
+
+    /// Complex values always have an imaginary part, so they are never real.
+    fn to_f64(&self) -> Option<f64> {
+        None
+    }
+
+    fn whole(&self, n: usize) -> Self {
+        Complex64::from(n as f64)
+    }
+}
+
+/// Complex numbers have every function except rounding, which isn't defined for them.
+impl Elementary for Complex64 {
+    fn sqrt(&self) -> Option<Self> {
+        Some(Complex64::sqrt(*self))
+    }
+
+    fn root(&self, n: &Self) -> Option<Self> {
+        Some(functions::complex_pow(*self, Complex64::from(1.0) / n))
+    }
+
+    fn exp(&self) -> Option<Self> {
+        Some(Complex64::exp(*self))
+    }
+
+    fn ln(&self) -> Option<Self> {
+        Some(Complex64::ln(*self))
+    }
+
+    fn log10(&self) -> Option<Self> {
+        Some(Complex64::log10(*self))
+    }
+
+    fn log2(&self) -> Option<Self> {
+        Some(Complex64::log2(*self))
+    }
+
+    fn log(&self, base: &Self) -> Option<Self> {
+        Some(Complex64::ln(*self) / Complex64::ln(*base))
+    }
+
+    fn abs(&self) -> Option<Self> {
+        Some(Complex64::from(self.norm()))
+    }
+
+    fn recip(&self) -> Option<Self> {
+        Some(self.inv())
+    }
+
+    fn sin(&self) -> Option<Self> {
+        Some(Complex64::sin(*self))
+    }
+
+    fn cos(&self) -> Option<Self> {
+        Some(Complex64::cos(*self))
+    }
+
+    fn tan(&self) -> Option<Self> {
+        Some(Complex64::tan(*self))
+    }
+
+    fn asin(&self) -> Option<Self> {
+        Some(Complex64::asin(*self))
+    }
+
+    fn acos(&self) -> Option<Self> {
+        Some(Complex64::acos(*self))
+    }
+
+    fn atan(&self) -> Option<Self> {
+        Some(Complex64::atan(*self))
+    }
+
+    fn sinh(&self) -> Option<Self> {
+        Some(Complex64::sinh(*self))
+    }
+
+    fn cosh(&self) -> Option<Self> {
+        Some(Complex64::cosh(*self))
+    }
+
+    fn tanh(&self) -> Option<Self> {
+        Some(Complex64::tanh(*self))
+    }
+
+    fn asinh(&self) -> Option<Self> {
+        Some(Complex64::asinh(*self))
+    }
+
+    fn acosh(&self) -> Option<Self> {
+        Some(Complex64::acosh(*self))
+    }
+
+    fn atanh(&self) -> Option<Self> {
+        Some(Complex64::atanh(*self))
+    }
+
+    fn floor(&self) -> Option<Self> {
+        None
+    }
+
+    fn ceil(&self) -> Option<Self> {
+        None
+    }
+
+    fn round(&self) -> Option<Self> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_integer_division_is_exact() {
+        let seven = BigInt::from(7);
+        assert_eq!(seven.try_div(&BigInt::from(7)), Some(BigInt::from(1)));
+        assert_eq!(seven.try_div(&BigInt::from(2)), None);
+        assert_eq!(seven.try_rem(&BigInt::from(0)), None);
+        assert_eq!(
+            BigInt::from(-7).try_rem(&BigInt::from(2)),
+            Some(BigInt::from(-1))
+        );
+    }
+
+    #[test]
+    fn test_complex_numbers_are_not_ordered() {
+        let i = Complex64::i();
+        assert_eq!(i.compare(&i), None);
+        assert_eq!(i.try_rem(&i), None);
+        assert_eq!(i.to_f64(), None);
+        assert_eq!(i.mul(&i), Complex64::from(-1.0));
+        assert_eq!(Elementary::abs(&i), Some(Complex64::from(1.0)));
+    }
+
+    #[test]
+    fn test_fractions_only_have_exact_functions() {
+        let n = BigRational::new(BigInt::from(-5), BigInt::from(2));
+        let whole = |n: i64| BigRational::from_integer(n.into());
+        assert_eq!(Elementary::floor(&n), Some(whole(-3)));
+        assert_eq!(Elementary::round(&n), Some(whole(-3)));
+        assert_eq!(
+            Elementary::abs(&n),
+            Some(BigRational::new(5.into(), 2.into()))
+        );
+        assert_eq!(Elementary::recip(&whole(0)), None);
+        assert_eq!(Elementary::sqrt(&whole(2)), None);
+        assert_eq!(n.whole(4), whole(4));
+    }
+}
@@ -1,320 +1,1344 @@
-use std::collections::HashMap;
+use crate::error::{ArgonError, Result};
+use crate::lexer::{Literal, Span, Token, TokenKind};
+use crate::units::Unit;
 
-use crate::lexer::Token;
-use anyhow::{format_err, Result};
+/// An expression in the AST along with the part of the equation it was parsed from.
+///
+/// Spans are only used for error messages, so two expressions are equal if their kinds are equal
+/// no matter where they came from.
+#[derive(Debug, Clone)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+impl Expr {
+    pub fn new(kind: ExprKind, span: Span) -> Self {
+        Expr { kind, span }
+    }
+}
+
+impl PartialEq for Expr {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind
+    }
+}
 
-/// Enum that represents an expression in the AST.
+/// Enum that represents the kind of an expression in the AST.
 ///
 /// `lhs` stands for left hand side, `rhs` stands for right hand side.
 ///
-/// `UnaryOp` represents unary operations such as negation.
+/// `UnaryOp` represents prefix operations such as negation.
+///
+/// `PostfixOp` represents postfix operations such as factorial `5!` and percent `10%`.
 ///
 /// `BinaryOp` represents binary operations such as addition and subtraction.
 ///
-/// `Number` represents a number.
-#[derive(Debug, Clone)]
-pub enum Expr {
-    Number(f64),
+/// `Number` represents a number literal, which keeps the exact value it was written as.
+///
+/// `Bool`, `String` and `None` represent the literals `true`, `"text"` and `none`.
+///
+/// `Variable` represents a reference to a variable, which is looked up when the expression is
+/// interpreted.
+///
+/// `VariableDeclaration` represents an assignment such as `x = 2 + 3`.
+///
+/// `Conditional` represents `condition ? then_branch : else_branch`, where only the chosen branch is
+/// evaluated.
+///
+/// `FunctionDeclaration` represents a user defined function such as `f(x) = x^2`. The body is kept
+/// unevaluated until the function is called.
+///
+/// `Let` represents `let x = 2, y = 3 in x * y`, where the bindings only exist while the body is
+/// evaluated.
+///
+/// `List` represents a list literal such as `[1, 2, 3]`.
+///
+/// `Index` represents `xs[i]` and `Slice` represents `xs[start:end]`, where either end of the slice
+/// can be left out.
+///
+/// `Quantity` represents a number followed by the name of a unit, such as `2 h` or `9.81 m/s^2`.
+/// If a name in `unit` is a variable when it is interpreted, it is an implicit multiplication
+/// instead, which binds like `*`, so after `h = 2`, `1/2h` is `(1/2) * h`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind {
+    Number(Literal),
+    Bool(bool),
+    String(String),
+    None,
     UnaryOp {
-        op: Token,
+        op: TokenKind,
         rhs: Box<Expr>,
     },
+    PostfixOp {
+        lhs: Box<Expr>,
+        op: TokenKind,
+    },
     BinaryOp {
         lhs: Box<Expr>,
-        op: Token,
+        op: TokenKind,
         rhs: Box<Expr>,
     },
+    Variable(String),
     VariableDeclaration {
         name: String,
-        value: f64,
+        value: Box<Expr>,
     },
     Function {
         name: String,
         args: Vec<Expr>,
     },
+    Conditional {
+        condition: Box<Expr>,
+        then_branch: Box<Expr>,
+        else_branch: Box<Expr>,
+    },
+    FunctionDeclaration {
+        name: String,
+        params: Vec<String>,
+        body: Box<Expr>,
+    },
+    Let {
+        bindings: Vec<(String, Expr)>,
+        body: Box<Expr>,
+    },
+    List(Vec<Expr>),
+    Index {
+        target: Box<Expr>,
+        index: Box<Expr>,
+    },
+    Slice {
+        target: Box<Expr>,
+        start: Option<Box<Expr>>,
+        end: Option<Box<Expr>>,
+    },
+    Quantity {
+        value: Box<Expr>,
+        unit: Box<Expr>,
+    },
 }
 
-/// Parses a list of tokens into an AST.
+/// Returns the kind of the token at `i`, or `None` if it is past the end of the tokens.
+fn kind_at(tokens: &[Token], i: usize) -> Option<&TokenKind> {
+    tokens.get(i).map(|token| &token.kind)
+}
+
+/// Returns an empty span just past the last token, for errors about running out of tokens.
+fn end_span(tokens: &[Token]) -> Span {
+    let end = tokens
+        .last()
+        .map(|token| token.span.end)
+        .unwrap_or_default();
+    Span::new(end, end)
+}
+
+/// Creates an error for running out of tokens.
+fn end_of_input(tokens: &[Token]) -> ArgonError {
+    ArgonError::parse(end_span(tokens), "Unexpected end of input")
+}
+
+/// Creates an error for finding something other than `expected` at `i`, pointing at the token
+/// that was found instead.
+fn unexpected(tokens: &[Token], i: usize, expected: &str) -> ArgonError {
+    match tokens.get(i) {
+        Some(token) => {
+            ArgonError::parse(token.span, format!("{}, found {:?}", expected, token.kind))
+        }
+        None => ArgonError::parse(
+            end_span(tokens),
+            format!("{}, found end of input", expected),
+        ),
+    }
+}
+
+/// Parses a list of tokens into a list of ASTs, one for each statement. Statements are separated by
+/// semicolons, such as `r = 3; area = PI * r^2; area`, and empty statements are skipped. Any tokens
+/// left over after a statement are an error.
 ///
 /// # Example
 /// ```
-/// let tokens = vec![
-///   lexer::Token::Number(1.0),
-///   lexer::Token::Plus,
-///   lexer::Token::Number(2.0),
-/// ];
-/// let ast = parser::parse_expr(&tokens);
-/// assert_eq!(ast, parser::Expr::BinaryOp {
-///     lhs: Box::new(parser::Expr::Number(1.0)),
-///     op: lexer::Token::Plus,
-///     rhs: Box::new(parser::Expr::Number(2.0)),
-/// });
+/// # use argon::{lexer, parser};
+/// let tokens = lexer::generate_tokens("x = 1; x".to_string()).unwrap();
+/// let statements = parser::parse_statements(&tokens).unwrap();
+/// assert_eq!(statements[1].kind, parser::ExprKind::Variable("x".to_string()));
 /// ```
-pub fn parse_expr(tokens: &[Token], variables: &mut HashMap<String, f64>) -> Result<Expr> {
-    let (_, expr) = parse_variable_declaration(tokens, 0, variables)?;
-    return Ok(expr);
+pub fn parse_statements(tokens: &[Token]) -> Result<Vec<Expr>> {
+    let mut statements = Vec::new();
+    let mut i = 0;
+
+    while i < tokens.len() {
+        if tokens[i].kind == TokenKind::Semicolon {
+            i += 1;
+            continue;
+        }
+
+        let (index, statement) = parse_variable_declaration(tokens, i)?;
+        match tokens.get(index) {
+            None => (),
+            Some(token) if token.kind == TokenKind::Semicolon => (),
+            Some(token) => {
+                return Err(ArgonError::parse(
+                    token.span,
+                    format!("Unexpected token: {:?}", token.kind),
+                ))
+            }
+        }
+        statements.push(statement);
+        i = index;
+    }
+
+    Ok(statements)
+}
+
+/// Which side an operator groups from when it is chained, e.g. `1 - 2 - 3` or `2 ^ 3 ^ 2`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Associativity {
+    Left,
+    Right,
+}
+
+/// An entry in one of the operator tables.
+///
+/// `precedence` decides how tightly the operator binds, higher numbers bind tighter.
+struct Operator {
+    token: TokenKind,
+    precedence: u8,
+    associativity: Associativity,
+}
+
+/// Binary operators understood by the parser. To add a new operator, register it here and
+/// handle its token in `interpreter::interpret`.
+///
+/// The unit conversions `to` and `in` bind loosest, so `1 mi + 1 km to m` converts the sum.
+const INFIX_OPERATORS: &[Operator] = &[
+    Operator {
+        token: TokenKind::To,
+        precedence: 1,
+        associativity: Associativity::Left,
+    },
+    Operator {
+        token: TokenKind::In,
+        precedence: 1,
+        associativity: Associativity::Left,
+    },
+    Operator {
+        token: TokenKind::Or,
+        precedence: 2,
+        associativity: Associativity::Left,
+    },
+    Operator {
+        token: TokenKind::And,
+        precedence: 3,
+        associativity: Associativity::Left,
+    },
+    Operator {
+        token: TokenKind::EqualEqual,
+        precedence: 4,
+        associativity: Associativity::Left,
+    },
+    Operator {
+        token: TokenKind::NotEqual,
+        precedence: 4,
+        associativity: Associativity::Left,
+    },
+    Operator {
+        token: TokenKind::Less,
+        precedence: 5,
+        associativity: Associativity::Left,
+    },
+    Operator {
+        token: TokenKind::LessEqual,
+        precedence: 5,
+        associativity: Associativity::Left,
+    },
+    Operator {
+        token: TokenKind::Greater,
+        precedence: 5,
+        associativity: Associativity::Left,
+    },
+    Operator {
+        token: TokenKind::GreaterEqual,
+        precedence: 5,
+        associativity: Associativity::Left,
+    },
+    Operator {
+        token: TokenKind::Plus,
+        precedence: 6,
+        associativity: Associativity::Left,
+    },
+    Operator {
+        token: TokenKind::Minus,
+        precedence: 6,
+        associativity: Associativity::Left,
+    },
+    Operator {
+        token: TokenKind::Multiply,
+        precedence: 7,
+        associativity: Associativity::Left,
+    },
+    Operator {
+        token: TokenKind::Divide,
+        precedence: 7,
+        associativity: Associativity::Left,
+    },
+    Operator {
+        token: TokenKind::Modulus,
+        precedence: 7,
+        associativity: Associativity::Left,
+    },
+    Operator {
+        token: TokenKind::Power,
+        precedence: 9,
+        associativity: Associativity::Right,
+    },
+];
+
+/// Prefix operators understood by the parser. Negation binds tighter than multiplication but
+/// looser than exponents, so `-2^2` is `-(2^2)` while `-2+3` is `(-2)+3`.
+const PREFIX_OPERATORS: &[Operator] = &[
+    Operator {
+        token: TokenKind::Minus,
+        precedence: 8,
+        associativity: Associativity::Right,
+    },
+    Operator {
+        token: TokenKind::Not,
+        precedence: 8,
+        associativity: Associativity::Right,
+    },
+];
+
+/// Postfix operators understood by the parser: factorial `5!`, double factorial `5!!` and percent
+/// `10%`. They bind tighter than any other operator, so `2^3!` is `2^(3!)` and `-3!` is `-(3!)`.
+const POSTFIX_OPERATORS: &[Operator] = &[
+    Operator {
+        token: TokenKind::Not,
+        precedence: 10,
+        associativity: Associativity::Left,
+    },
+    Operator {
+        token: TokenKind::DoubleFactorial,
+        precedence: 10,
+        associativity: Associativity::Left,
+    },
+    Operator {
+        token: TokenKind::Percent,
+        precedence: 10,
+        associativity: Associativity::Left,
+    },
+];
+
+/// Binding power of the conditional operator `cond ? a : b`, which binds looser than every binary
+/// operator and groups from the right.
+const CONDITIONAL_POWER: u8 = 1;
+
+/// Binding power of a number directly followed by a unit, such as `2 h`. It binds tighter than
+/// `*` and `/` but looser than `^`, so `100 km / 2 h` is `(100 km) / (2 h)` and `3 m^2` is
+/// `3 * (m^2)`. Whether it is a quantity is only known once it is interpreted, see
+/// [`ExprKind::Quantity`].
+const QUANTITY_POWER: u8 = 16;
+
+/// Returns the left and right binding power of a binary operator, or `None` if the token is not
+/// a binary operator.
+///
+/// The side with the higher binding power holds on to the operand between two operators, which
+/// is how associativity is encoded.
+fn infix_binding_power(token: &TokenKind) -> Option<(u8, u8)> {
+    let operator = INFIX_OPERATORS.iter().find(|op| &op.token == token)?;
+    let power = operator.precedence * 2;
+    match operator.associativity {
+        Associativity::Left => Some((power, power + 1)),
+        Associativity::Right => Some((power + 1, power)),
+    }
 }
 
-/// Parses the addition and subtraction operations. Returning the index and the AST.
+/// Returns the binding power used for the operand of a prefix operator, or `None` if the token
+/// is not a prefix operator.
+fn prefix_binding_power(token: &TokenKind) -> Option<u8> {
+    let operator = PREFIX_OPERATORS.iter().find(|op| &op.token == token)?;
+    Some(operator.precedence * 2)
+}
+
+/// Returns the binding power of a postfix operator, or `None` if the token is not a postfix
+/// operator.
+fn postfix_binding_power(token: &TokenKind) -> Option<u8> {
+    let operator = POSTFIX_OPERATORS.iter().find(|op| &op.token == token)?;
+    Some(operator.precedence * 2)
+}
+
+/// Parses a binary expression using precedence climbing. Returning the index and the AST.
+///
+/// Only operators with a left binding power of at least `min_power` are consumed, the rest are
+/// left for the caller. Passing `0` parses a complete expression.
 ///
 /// # Example
-/// ```
-/// let tokens = vec![
-///     lexer::Token::Number(1.0),
-///     lexer::Token::Plus,
-///     lexer::Token::Number(2.0),
-///     lexer::Token::Minus,
-///     lexer::Token::Number(3.0),
-/// ];
-/// let (index, ast) = parser::parse_addition_subtraction(&tokens, 0);
+/// ```ignore
+/// let tokens = lexer::generate_tokens("1 + 2 * 3".to_string()).unwrap();
+/// let (index, ast) = parser::parse_binary(&tokens, 0, 0).unwrap();
 /// assert_eq!(index, 5);
-/// assert_eq!(ast, parser::Expr::BinaryOp {
-///     lhs: Box::new(parser::Expr::BinaryOp {
-///     lhs: Box::new(parser::Expr::Number(1.0)),
-///     op: lexer::Token::Plus,
-///     rhs: Box::new(parser::Expr::Number(2.0)),
-///     }),
-///     op: lexer::Token::Minus,
-///     rhs: Box::new(parser::Expr::Number(3.0)),
-/// });
+/// assert!(matches!(ast.kind, parser::ExprKind::BinaryOp { op: lexer::TokenKind::Plus, .. }));
 /// ```
-fn parse_addition_subtraction(
-    tokens: &[Token],
-    i: usize,
-    variables: &mut HashMap<String, f64>,
-) -> Result<(usize, Expr)> {
-    if i >= tokens.len() {
-        return Err(format_err!("Unexpected end of input"));
-    }
-    let (mut i, mut lhs) = parse_multiplication_division(tokens, i, variables)?;
+fn parse_binary(tokens: &[Token], i: usize, min_power: u8) -> Result<(usize, Expr)> {
+    let (mut i, mut lhs) = parse_unary(tokens, i)?;
 
-    while i < tokens.len() {
-        match tokens[i] {
-            Token::Plus | Token::Minus => {
-                let op = tokens[i].clone();
-                let (new_index, rhs) = parse_multiplication_division(tokens, i + 1, variables)?;
-                i = new_index;
-                lhs = Expr::BinaryOp {
+    while let Some(token) = tokens.get(i) {
+        // Indexing binds tighter than any operator, so `-xs[0]` is `-(xs[0])`
+        if token.kind == TokenKind::LeftBracket {
+            (i, lhs) = parse_index(tokens, i, lhs)?;
+            continue;
+        }
+
+        if let Some(power) = postfix_binding_power(&token.kind) {
+            if power < min_power {
+                break;
+            }
+            let span = lhs.span.merge(token.span);
+            lhs = Expr::new(
+                ExprKind::PostfixOp {
                     lhs: Box::new(lhs),
-                    op,
-                    rhs: Box::new(rhs),
-                };
+                    op: token.kind.clone(),
+                },
+                span,
+            );
+            i += 1;
+            continue;
+        }
+
+        if token.kind == TokenKind::Question && min_power <= CONDITIONAL_POWER {
+            let (index, then_branch) = parse_binary(tokens, i + 1, 0)?;
+            if kind_at(tokens, index) != Some(&TokenKind::Colon) {
+                return Err(unexpected(
+                    tokens,
+                    index,
+                    "Expected ':' in conditional expression",
+                ));
             }
-            _ => break,
+            let (index, else_branch) = parse_binary(tokens, index + 1, CONDITIONAL_POWER)?;
+            let span = lhs.span.merge(else_branch.span);
+            lhs = Expr::new(
+                ExprKind::Conditional {
+                    condition: Box::new(lhs),
+                    then_branch: Box::new(then_branch),
+                    else_branch: Box::new(else_branch),
+                },
+                span,
+            );
+            i = index;
+            continue;
         }
+
+        // A number followed by the name of a unit may be a quantity, which holds on to its unit.
+        // `in` is the inch there, unless an operand follows it to convert to, so `12 in` works
+        let unit_name = match &token.kind {
+            TokenKind::Identifier(name) if !is_call(tokens, i) => Some(name.as_str()),
+            TokenKind::In if !starts_operand(kind_at(tokens, i + 1)) => Some("in"),
+            _ => None,
+        };
+        if let (ExprKind::Number(_), Some(name)) = (&lhs.kind, unit_name) {
+            if min_power <= QUANTITY_POWER && Unit::parse(name).is_some() {
+                let (index, unit) = parse_unit(&unit_tokens(tokens, i), i)?;
+                let span = lhs.span.merge(unit.span);
+                lhs = Expr::new(
+                    ExprKind::Quantity {
+                        value: Box::new(lhs),
+                        unit: Box::new(unit),
+                    },
+                    span,
+                );
+                i = index;
+                continue;
+            }
+        }
+
+        // An operand directly after another one, such as `2x`, `3(4+5)` or `(a)(b)`, is an implicit
+        // multiplication. It binds exactly like `*`, so `2^3x` is `(2^3)*x` and `1/2x` is `(1/2)*x`,
+        // unless it is a number followed by a unit.
+        // A number on the right is never implicit, so `1 2` is still an error.
+        let (op, rhs_start) = match &token.kind {
+            TokenKind::Identifier(_) | TokenKind::LeftParen => (TokenKind::Multiply, i),
+            kind => (kind.clone(), i + 1),
+        };
+
+        let (left_power, right_power) = match infix_binding_power(&op) {
+            Some(power) => power,
+            None => break,
+        };
+        if left_power < min_power {
+            break;
+        }
+
+        let (new_index, rhs) = match op {
+            TokenKind::To | TokenKind::In => {
+                parse_binary(&unit_tokens(tokens, rhs_start), rhs_start, right_power)?
+            }
+            _ => parse_binary(tokens, rhs_start, right_power)?,
+        };
+        let span = lhs.span.merge(rhs.span);
+        lhs = Expr::new(
+            ExprKind::BinaryOp {
+                lhs: Box::new(lhs),
+                op,
+                rhs: Box::new(rhs),
+            },
+            span,
+        );
+        i = new_index;
     }
 
     Ok((i, lhs))
 }
-/// Parses the multiplication and division operations. Returning the index and the AST.
+
+/// Whether the identifier at `i` is the name of a function being called, such as `h(3)`.
+fn is_call(tokens: &[Token], i: usize) -> bool {
+    kind_at(tokens, i + 1) == Some(&TokenKind::LeftParen)
+}
+
+/// Whether a token of this kind can start an operand, such as a name, number or bracket.
+fn starts_operand(kind: Option<&TokenKind>) -> bool {
+    matches!(
+        kind,
+        Some(
+            TokenKind::Identifier(_)
+                | TokenKind::Number(_)
+                | TokenKind::LeftParen
+                | TokenKind::LeftBracket
+        )
+    )
+}
+
+/// Parses the unit after a number, such as `m/s^2`: names that can be raised to a power, joined by
+/// `*` and `/`. Returning the index and the AST.
+fn parse_unit(tokens: &[Token], i: usize) -> Result<(usize, Expr)> {
+    let (mut i, mut unit) = parse_binary(tokens, i, QUANTITY_POWER + 1)?;
+    while let (
+        Some(op @ (TokenKind::Multiply | TokenKind::Divide)),
+        Some(TokenKind::Identifier(_)),
+    ) = (kind_at(tokens, i), kind_at(tokens, i + 1))
+    {
+        if is_call(tokens, i + 1) {
+            break;
+        }
+        let (index, rhs) = parse_binary(tokens, i + 1, QUANTITY_POWER + 1)?;
+        let span = unit.span.merge(rhs.span);
+        unit = Expr::new(
+            ExprKind::BinaryOp {
+                lhs: Box::new(unit),
+                op: op.clone(),
+                rhs: Box::new(rhs),
+            },
+            span,
+        );
+        i = index;
+    }
+    Ok((i, unit))
+}
+
+/// Returns the tokens with every `in` that starts a unit, after `to` or `in` or after a number,
+/// turned into the name of the inch, so `12 ft to in` and `30 lb/in^2` can be written.
+fn unit_tokens(tokens: &[Token], start: usize) -> Vec<Token> {
+    let mut tokens = tokens.to_vec();
+    for i in start..tokens.len() {
+        let starts_unit = i == start
+            || matches!(
+                tokens[i - 1].kind,
+                TokenKind::Multiply | TokenKind::Divide | TokenKind::LeftParen
+            );
+        if tokens[i].kind == TokenKind::In && starts_unit {
+            tokens[i].kind = TokenKind::Identifier("in".to_string());
+        }
+    }
+    tokens
+}
+
+/// Parses a single operand: a number, variable, function call, parenthesised expression or a
+/// prefix operation such as negation. Returning the index and the AST.
 ///
 /// # Example
+/// ```ignore
+/// let tokens = lexer::generate_tokens("-1".to_string()).unwrap();
+/// let (index, ast) = parser::parse_unary(&tokens, 0).unwrap();
+/// assert_eq!(index, 2);
+/// assert!(matches!(ast.kind, parser::ExprKind::UnaryOp { op: lexer::TokenKind::Minus, .. }));
 /// ```
-/// let tokens = vec![
-///     lexer::Token::Number(1.0),
-///     lexer::Token::Multiply,
-///     lexer::Token::Number(2.0),
-///     lexer::Token::Divide,
-///     lexer::Token::Number(3.0),
-/// ];
-/// let (index, ast) = parser::parse_multiplication_division(&tokens, 0);
-/// assert_eq!(index, 5);
-/// assert_eq!(ast, parser::Expr::BinaryOp {
-///     lhs: Box::new(parser::Expr::BinaryOp {
-///     lhs: Box::new(parser::Expr::Number(1.0)),
-///     op: lexer::Token::Multiply,
-///     rhs: Box::new(parser::Expr::Number(2.0)),
-///     }),
-///     op: lexer::Token::Divide,
-///     rhs: Box::new(parser::Expr::Number(3.0)),
-/// });
-/// ```
-fn parse_multiplication_division(
-    tokens: &[Token],
-    i: usize,
-    variables: &mut HashMap<String, f64>,
-) -> Result<(usize, Expr)> {
-    if i >= tokens.len() {
-        return Err(format_err!("Unexpected end of input"));
+fn parse_unary(tokens: &[Token], i: usize) -> Result<(usize, Expr)> {
+    let token = match tokens.get(i) {
+        Some(token) => token,
+        None => return Err(end_of_input(tokens)),
+    };
+
+    // A prefix `!!` is two negations, `!!x` is `!(!x)`
+    if token.kind == TokenKind::DoubleFactorial {
+        let power = prefix_binding_power(&TokenKind::Not).unwrap_or_default();
+        let (index, rhs) = parse_binary(tokens, i + 1, power)?;
+        let span = token.span.merge(rhs.span);
+        let rhs = Expr::new(
+            ExprKind::UnaryOp {
+                op: TokenKind::Not,
+                rhs: Box::new(rhs),
+            },
+            span,
+        );
+        return Ok((
+            index,
+            Expr::new(
+                ExprKind::UnaryOp {
+                    op: TokenKind::Not,
+                    rhs: Box::new(rhs),
+                },
+                span,
+            ),
+        ));
     }
-    let (mut i, mut lhs) = parse_exponents(tokens, i, variables)?;
 
-    while i < tokens.len() {
-        match tokens[i] {
-            Token::Multiply | Token::Divide | Token::Modulus => {
-                let op = tokens[i].clone();
-                let (new_index, rhs) = parse_exponents(tokens, i + 1, variables)?;
-                i = new_index;
-                lhs = Expr::BinaryOp {
-                    lhs: Box::new(lhs),
-                    op,
+    if let Some(power) = prefix_binding_power(&token.kind) {
+        let (index, rhs) = parse_binary(tokens, i + 1, power)?;
+        let span = token.span.merge(rhs.span);
+        return Ok((
+            index,
+            Expr::new(
+                ExprKind::UnaryOp {
+                    op: token.kind.clone(),
                     rhs: Box::new(rhs),
-                };
+                },
+                span,
+            ),
+        ));
+    }
+
+    match &token.kind {
+        TokenKind::Number(n) => Ok((i + 1, Expr::new(ExprKind::Number(n.clone()), token.span))),
+        TokenKind::Bool(b) => Ok((i + 1, Expr::new(ExprKind::Bool(*b), token.span))),
+        TokenKind::String(s) => Ok((i + 1, Expr::new(ExprKind::String(s.clone()), token.span))),
+        TokenKind::None => Ok((i + 1, Expr::new(ExprKind::None, token.span))),
+        TokenKind::Identifier(_) if kind_at(tokens, i + 1) == Some(&TokenKind::LeftParen) => {
+            parse_function_call(tokens, i)
+        }
+        TokenKind::Identifier(name) => Ok((
+            i + 1,
+            Expr::new(ExprKind::Variable(name.clone()), token.span),
+        )),
+        TokenKind::Let => parse_let(tokens, i),
+        TokenKind::LeftBracket => parse_list(tokens, i),
+        TokenKind::LeftParen => {
+            let (index, mut expr) = parse_binary(tokens, i + 1, 0)?;
+            match tokens.get(index) {
+                Some(close) if close.kind == TokenKind::RightParen => {
+                    expr.span = token.span.merge(close.span);
+                    Ok((index + 1, expr))
+                }
+                _ => Err(unexpected(tokens, index, "Expected right parenthesis")),
             }
-            _ => break,
         }
+        kind => Err(ArgonError::parse(
+            token.span,
+            format!("Unexpected token: {:?}", kind),
+        )),
     }
-
-    Ok((i, lhs))
 }
 
-/// Parses the exponentiation operations. Returning the index and the AST.
+/// Parses a list literal such as `[1, 2, 3]` or `[]`. Returning the index and the AST.
 ///
 /// # Example
+/// ```ignore
+/// let tokens = lexer::generate_tokens("[1, 2]".to_string()).unwrap();
+/// let (index, ast) = parser::parse_list(&tokens, 0).unwrap();
+/// assert_eq!(index, 5);
+/// assert!(matches!(ast.kind, parser::ExprKind::List(_)));
 /// ```
-/// let tokens = vec![
-///     lexer::Token::Number(2.0),
-///     lexer::Token::Power,
-///     lexer::Token::Number(3.0),
-/// ];
-/// let (index, ast) = parser::parse_exponents(&tokens, 0);
-/// assert_eq!(index, 3);
-/// assert_eq!(ast, parser::Expr::BinaryOp {
-///     lhs: Box::new(parser::Expr::Number(2.0)),
-///     op: lexer::Token::Power,
-///     rhs: Box::new(parser::Expr::Number(3.0)),
-/// });
-/// ```
-fn parse_exponents(
-    tokens: &[Token],
-    i: usize,
-    variables: &mut HashMap<String, f64>,
-) -> Result<(usize, Expr)> {
-    if i >= tokens.len() {
-        return Err(format_err!("Unexpected end of input"));
-    }
-    let (mut i, mut lhs) = parse_unary(tokens, i, variables)?;
+fn parse_list(tokens: &[Token], i: usize) -> Result<(usize, Expr)> {
+    let mut items = Vec::new();
+    let mut index = i + 1;
 
-    while i < tokens.len() {
-        match tokens[i] {
-            Token::Power => {
-                let op = tokens[i].clone();
-                let (new_index, rhs) = parse_unary(tokens, i + 1, variables)?;
-                i = new_index;
-                lhs = Expr::BinaryOp {
-                    lhs: Box::new(lhs),
-                    op,
-                    rhs: Box::new(rhs),
-                };
+    if kind_at(tokens, index) != Some(&TokenKind::RightBracket) {
+        loop {
+            let (next, item) = parse_binary(tokens, index, 0)?;
+            items.push(item);
+            match kind_at(tokens, next) {
+                Some(TokenKind::Comma) => index = next + 1,
+                Some(TokenKind::RightBracket) => {
+                    index = next;
+                    break;
+                }
+                _ => return Err(unexpected(tokens, next, "Expected ',' or ']'")),
             }
-            _ => break,
         }
     }
 
-    Ok((i, lhs))
+    let span = tokens[i].span.merge(tokens[index].span);
+    Ok((index + 1, Expr::new(ExprKind::List(items), span)))
+}
+
+/// Parses an index such as `[0]` or a slice such as `[1:3]` following `target`. Returning the
+/// index and the AST.
+///
+/// `i` must point at the left bracket.
+fn parse_index(tokens: &[Token], i: usize, target: Expr) -> Result<(usize, Expr)> {
+    let mut index = i + 1;
+
+    let start = match kind_at(tokens, index) {
+        Some(TokenKind::Colon) => None,
+        _ => {
+            let (next, start) = parse_binary(tokens, index, 0)?;
+            index = next;
+            Some(Box::new(start))
+        }
+    };
+
+    let target_span = target.span;
+    let target = Box::new(target);
+    let kind = match (start, kind_at(tokens, index)) {
+        (start, Some(TokenKind::Colon)) => {
+            index += 1;
+            let end = match kind_at(tokens, index) {
+                Some(TokenKind::RightBracket) => None,
+                _ => {
+                    let (next, end) = parse_binary(tokens, index, 0)?;
+                    index = next;
+                    Some(Box::new(end))
+                }
+            };
+            ExprKind::Slice { target, start, end }
+        }
+        (Some(start), _) => ExprKind::Index {
+            target,
+            index: start,
+        },
+        (None, _) => unreachable!("a missing start is always followed by a colon"),
+    };
+
+    match tokens.get(index) {
+        Some(close) if close.kind == TokenKind::RightBracket => {
+            Ok((index + 1, Expr::new(kind, target_span.merge(close.span))))
+        }
+        _ => Err(unexpected(tokens, index, "Expected ']'")),
+    }
 }
 
-/// Parses the unary operations such as negation. Returning the index and the AST.
+/// Parses a `let` expression such as `let x = 2, y = x + 1 in x * y`. Returning the index and
+/// the AST.
+///
+/// Each binding can use the ones before it, and the body extends as far to the right as possible.
 ///
 /// # Example
+/// ```ignore
+/// let tokens = lexer::generate_tokens("let x = 2 in x^2".to_string()).unwrap();
+/// let (index, ast) = parser::parse_let(&tokens, 0).unwrap();
+/// assert_eq!(index, 7);
+/// assert!(matches!(ast.kind, parser::ExprKind::Let { .. }));
 /// ```
-/// let tokens = vec![
-///     lexer::Token::Minus,
-///     lexer::Token::Number(1.0),
-/// ];
-/// let (index, ast) = parser::parse_unary(&tokens, 0);
-/// assert_eq!(index, 2);
-/// assert_eq!(ast, parser::Expr::UnaryOp {
-///     op: lexer::Token::Minus,
-///     rhs: Box::new(parser::Expr::Number(1.0)),
-/// });
-/// ```
-fn parse_unary(
-    tokens: &[Token],
-    i: usize,
-    variables: &mut HashMap<String, f64>,
-) -> Result<(usize, Expr)> {
-    if i >= tokens.len() {
-        return Err(format_err!("Unexpected end of input"));
-    }
-    match &tokens[i] {
-        Token::Number(n) => Ok((i + 1, Expr::Number(*n))),
-        Token::Identifier(name) => match variables.get(name) {
-            Some(value) => Ok((i + 1, Expr::Number(*value))),
-            None => Err(format_err!("Variable not found")),
-        },
-        Token::Minus => {
-            let (i, rhs) = parse_addition_subtraction(tokens, i + 1, variables)?;
-            Ok((
-                i,
-                Expr::UnaryOp {
-                    op: Token::Minus,
-                    rhs: Box::new(rhs),
-                },
-            ))
+fn parse_let(tokens: &[Token], i: usize) -> Result<(usize, Expr)> {
+    let mut bindings = Vec::new();
+    let mut index = i + 1;
+    loop {
+        let name = match kind_at(tokens, index) {
+            Some(TokenKind::Identifier(name)) => name.clone(),
+            _ => return Err(unexpected(tokens, index, "Expected variable name")),
+        };
+        if kind_at(tokens, index + 1) != Some(&TokenKind::Equals) {
+            return Err(unexpected(tokens, index + 1, "Expected '='"));
         }
-        Token::LeftParen => {
-            let (index, expr) = parse_addition_subtraction(tokens, i + 1, variables)?;
-            if tokens[index] == Token::RightParen {
-                Ok((index + 1, expr))
-            } else {
-                Err(format_err!("Expected right parenthesis"))
+
+        let end = binding_end(tokens, index + 2);
+        let (next, value) = parse_binary(&tokens[..end], index + 2, 0)?;
+        bindings.push((name, value));
+        index = next + 1;
+        match kind_at(tokens, next) {
+            Some(TokenKind::Comma) => (),
+            Some(TokenKind::In) => break,
+            _ => return Err(unexpected(tokens, next, "Expected ',' or 'in'")),
+        }
+    }
+
+    let (index, body) = parse_binary(tokens, index, 0)?;
+    let span = tokens[i].span.merge(body.span);
+    Ok((
+        index,
+        Expr::new(
+            ExprKind::Let {
+                bindings,
+                body: Box::new(body),
+            },
+            span,
+        ),
+    ))
+}
+
+/// Returns the index of the `in` that ends a `let` binding whose value starts at `i`, or the end
+/// of the tokens if there isn't one.
+///
+/// `in` also converts between units, so the value stops at the first `in` that isn't inside
+/// brackets or the end of a nested `let`, and `let d = 5 km in d in m` converts the body.
+fn binding_end(tokens: &[Token], i: usize) -> usize {
+    let mut depth = 0;
+    let mut nested = 0;
+    for (index, token) in tokens.iter().enumerate().skip(i) {
+        match token.kind {
+            TokenKind::LeftParen | TokenKind::LeftBracket => depth += 1,
+            TokenKind::RightParen | TokenKind::RightBracket if depth == 0 => return index,
+            TokenKind::RightParen | TokenKind::RightBracket => depth -= 1,
+            TokenKind::Let if depth == 0 => nested += 1,
+            TokenKind::In if depth == 0 && nested == 0 => return index,
+            TokenKind::In if depth == 0 => nested -= 1,
+            _ => (),
+        }
+    }
+    tokens.len()
+}
+
+/// Parses a function call such as `sqrt(9)` or `max(1, 2, 3)`. Returning the index and the AST.
+///
+/// `i` must point at the function's identifier, which has to be followed by a left parenthesis.
+///
+/// # Example
+/// ```ignore
+/// let tokens = lexer::generate_tokens("sqrt(9)".to_string()).unwrap();
+/// let (index, ast) = parser::parse_function_call(&tokens, 0).unwrap();
+/// assert_eq!(index, 4);
+/// assert!(matches!(ast.kind, parser::ExprKind::Function { .. }));
+/// ```
+fn parse_function_call(tokens: &[Token], i: usize) -> Result<(usize, Expr)> {
+    let name = match &tokens[i].kind {
+        TokenKind::Identifier(name) => name.clone(),
+        _ => return Err(unexpected(tokens, i, "Expected function name")),
+    };
+
+    let mut args = Vec::new();
+    let mut index = i + 2;
+    if kind_at(tokens, index) != Some(&TokenKind::RightParen) {
+        loop {
+            let (new_index, expr) = parse_binary(tokens, index, 0)?;
+            args.push(expr);
+            index = new_index;
+            match kind_at(tokens, index) {
+                Some(TokenKind::Comma) => index += 1,
+                Some(TokenKind::RightParen) => break,
+                Some(_) => {
+                    return Err(unexpected(
+                        tokens,
+                        index,
+                        &format!("Expected ',' or ')' in arguments to '{}'", name),
+                    ))
+                }
+                None => {
+                    return Err(unexpected(
+                        tokens,
+                        index,
+                        &format!("Unterminated argument list for '{}', expected ')'", name),
+                    ))
+                }
             }
         }
-        _ => Err(format_err!("Unexpected token: {:?}", tokens[i])),
     }
+
+    let span = tokens[i].span.merge(tokens[index].span);
+    Ok((
+        index + 1,
+        Expr::new(ExprKind::Function { name, args }, span),
+    ))
 }
 
 /// Parses the variable declaration. Returning the index and the AST.
 ///
 /// # Example
-/// ```
-/// let tokens = vec![
-///     lexer::Token::Identifier("x".to_string()),
-///     lexer::Token::Equals,
-///     lexer::Token::Number(1.0),
-/// ];
-/// let (index, ast) = parser::parse_variable_declaration(&tokens, 0);
+/// ```ignore
+/// let tokens = lexer::generate_tokens("x = 1".to_string()).unwrap();
+/// let (index, ast) = parser::parse_variable_declaration(&tokens, 0).unwrap();
 /// assert_eq!(index, 3);
-/// assert_eq!(ast, parser::Expr::Variable {
-///     name: "x".to_string(),
-///     value: 1.0,
-/// });
+/// assert!(matches!(ast.kind, parser::ExprKind::VariableDeclaration { .. }));
 /// ```
-fn parse_variable_declaration(
-    tokens: &[Token],
-    i: usize,
-    variables: &mut HashMap<String, f64>,
-) -> Result<(usize, Expr)> {
-    if i >= tokens.len() {
-        return Err(format_err!("Unexpected end of input"));
-    }
-    match &tokens[i] {
-        Token::Identifier(name) => {
-            if tokens.get(i + 1) == Some(&Token::Equals) {
-                let (index, expr) = parse_addition_subtraction(tokens, i + 2, variables)?;
+fn parse_variable_declaration(tokens: &[Token], i: usize) -> Result<(usize, Expr)> {
+    let token = match tokens.get(i) {
+        Some(token) => token,
+        None => return Err(end_of_input(tokens)),
+    };
+    match &token.kind {
+        TokenKind::Identifier(name) => {
+            if kind_at(tokens, i + 1) == Some(&TokenKind::Equals) {
+                let (index, expr) = parse_binary(tokens, i + 2, 0)?;
+                let span = token.span.merge(expr.span);
                 Ok((
                     index,
-                    Expr::VariableDeclaration {
-                        name: name.clone(),
-                        value: match expr {
-                            Expr::Number(n) => n,
-                            _ => return Err(format_err!("Expected number")),
+                    Expr::new(
+                        ExprKind::VariableDeclaration {
+                            name: name.clone(),
+                            value: Box::new(expr),
                         },
-                    },
+                        span,
+                    ),
                 ))
-            } else if tokens.get(i + 1) == Some(&Token::LeftParen) {
-                let mut args = Vec::new();
-                let mut index = i + 2;
-                while tokens.get(index) != Some(&Token::RightParen) {
-                    let (new_index, expr) = parse_addition_subtraction(tokens, index, variables)?;
-                    args.push(expr);
-                    index = new_index;
-                    if tokens.get(index) == Some(&Token::Comma) {
-                        index += 1;
-                    }
+            } else if kind_at(tokens, i + 1) == Some(&TokenKind::LeftParen)
+                && is_function_declaration(tokens, i)
+            {
+                parse_function_declaration(tokens, i)
+            } else {
+                parse_binary(tokens, i, 0)
+            }
+        }
+        _ => parse_binary(tokens, i, 0),
+    }
+}
+
+/// Returns true if the identifier at `i` starts a function declaration, i.e. its parenthesised
+/// argument list is directly followed by an equals sign.
+fn is_function_declaration(tokens: &[Token], i: usize) -> bool {
+    let mut depth = 0;
+    for (index, token) in tokens.iter().enumerate().skip(i + 1) {
+        match token.kind {
+            TokenKind::LeftParen => depth += 1,
+            TokenKind::RightParen => {
+                depth -= 1;
+                if depth == 0 {
+                    return kind_at(tokens, index + 1) == Some(&TokenKind::Equals);
                 }
-                Ok((
-                    index + 1,
-                    Expr::Function {
-                        name: name.clone(),
-                        args,
-                    },
+            }
+            _ => (),
+        }
+    }
+    false
+}
+
+/// Parses a user defined function declaration such as `f(x, y) = x^2 + y`. Returning the index
+/// and the AST.
+///
+/// The body is parsed straight away but stored unevaluated so it can be run with the real
+/// arguments later.
+///
+/// # Example
+/// ```ignore
+/// let tokens = lexer::generate_tokens("f(x) = x".to_string()).unwrap();
+/// let (index, ast) = parser::parse_function_declaration(&tokens, 0).unwrap();
+/// assert_eq!(index, 6);
+/// assert!(matches!(ast.kind, parser::ExprKind::FunctionDeclaration { .. }));
+/// ```
+fn parse_function_declaration(tokens: &[Token], i: usize) -> Result<(usize, Expr)> {
+    let name = match &tokens[i].kind {
+        TokenKind::Identifier(name) => name.clone(),
+        _ => return Err(unexpected(tokens, i, "Expected function name")),
+    };
+
+    let mut params: Vec<String> = Vec::new();
+    let mut index = i + 2;
+    while kind_at(tokens, index) != Some(&TokenKind::RightParen) {
+        match kind_at(tokens, index) {
+            Some(TokenKind::Identifier(param)) => {
+                if params.contains(param) {
+                    return Err(ArgonError::parse(
+                        tokens[index].span,
+                        format!(
+                            "Duplicate parameter '{}' in declaration of '{}'",
+                            param, name
+                        ),
+                    ));
+                }
+                params.push(param.clone());
+            }
+            _ => {
+                return Err(unexpected(
+                    tokens,
+                    index,
+                    &format!("Expected parameter name in declaration of '{}'", name),
+                ))
+            }
+        }
+        index += 1;
+        match kind_at(tokens, index) {
+            Some(TokenKind::Comma) => index += 1,
+            Some(TokenKind::RightParen) => (),
+            _ => {
+                return Err(unexpected(
+                    tokens,
+                    index,
+                    &format!("Expected ',' or ')' in declaration of '{}'", name),
                 ))
-            } else {
-                parse_addition_subtraction(tokens, i, variables)
             }
         }
-        _ => parse_addition_subtraction(tokens, i, variables),
+    }
+
+    // Skip the right parenthesis and the equals sign
+    let (index, body) = parse_binary(tokens, index + 2, 0)?;
+    let span = tokens[i].span.merge(body.span);
+
+    Ok((
+        index,
+        Expr::new(
+            ExprKind::FunctionDeclaration {
+                name,
+                params,
+                body: Box::new(body),
+            },
+            span,
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::generate_tokens;
+    use crate::value::Value;
+
+    fn parse(equation: &str) -> Result<Expr> {
+        let tokens = generate_tokens(equation.to_string())?;
+        let mut statements = parse_statements(&tokens)?;
+        assert_eq!(statements.len(), 1);
+        Ok(statements.remove(0))
+    }
+
+    fn expr(kind: ExprKind) -> Expr {
+        Expr::new(kind, Span::default())
+    }
+
+    fn number(n: f64) -> Expr {
+        expr(ExprKind::Number(n.into()))
+    }
+
+    /// A number followed by a unit, with the unit written as it would be on its own.
+    fn quantity(n: f64, unit: &str) -> Expr {
+        expr(ExprKind::Quantity {
+            value: Box::new(number(n)),
+            unit: Box::new(parse(unit).unwrap()),
+        })
+    }
+
+    fn call(name: &str, args: Vec<Expr>) -> Expr {
+        expr(ExprKind::Function {
+            name: name.to_string(),
+            args,
+        })
+    }
+
+    #[test]
+    fn test_function_call_inside_expression() {
+        let ast = parse("1 + sin(1)").unwrap();
+        assert_eq!(
+            ast,
+            expr(ExprKind::BinaryOp {
+                lhs: Box::new(number(1.0)),
+                op: TokenKind::Plus,
+                rhs: Box::new(call("sin", vec![number(1.0)])),
+            })
+        );
+
+        let ast = parse("2 * sqrt(9)").unwrap();
+        assert_eq!(
+            ast,
+            expr(ExprKind::BinaryOp {
+                lhs: Box::new(number(2.0)),
+                op: TokenKind::Multiply,
+                rhs: Box::new(call("sqrt", vec![number(9.0)])),
+            })
+        );
+    }
+
+    #[test]
+    fn test_nested_function_calls() {
+        let ast = parse("sqrt(sqrt(16))").unwrap();
+        assert_eq!(ast, call("sqrt", vec![call("sqrt", vec![number(16.0)])]));
+
+        let ast = parse("max(1, min(2, 3), 4)").unwrap();
+        assert_eq!(
+            ast,
+            call(
+                "max",
+                vec![
+                    number(1.0),
+                    call("min", vec![number(2.0), number(3.0)]),
+                    number(4.0),
+                ]
+            )
+        );
+
+        assert_eq!(parse("sum()").unwrap(), call("sum", vec![]));
+    }
+
+    fn eval(equation: &str) -> Value {
+        let ast = parse(equation).unwrap();
+        crate::interpreter::interpret(&ast, &mut crate::environment::Environment::new()).unwrap()
+    }
+
+    #[test]
+    fn test_operator_precedence() {
+        assert_eq!(eval("1 + 2 * 3"), 7.0);
+        assert_eq!(eval("(1 + 2) * 3"), 9.0);
+        assert_eq!(eval("10 - 4 - 3"), 3.0);
+        assert_eq!(eval("64 / 4 / 2"), 8.0);
+        assert_eq!(eval("2 * 3 ^ 2"), 18.0);
+        assert_eq!(eval("7 % 4 * 2"), 6.0);
+    }
+
+    #[test]
+    fn test_power_is_right_associative() {
+        assert_eq!(eval("2^3^2"), 512.0);
+        assert_eq!(eval("(2^3)^2"), 64.0);
+        assert_eq!(eval("2^-1"), 0.5);
+    }
+
+    #[test]
+    fn test_unary_minus_precedence() {
+        assert_eq!(eval("-2^2"), -4.0);
+        assert_eq!(eval("-2+3"), 1.0);
+        assert_eq!(eval("-2*3+1"), -5.0);
+        assert_eq!(eval("--2"), 2.0);
+        assert_eq!(eval("2*-3"), -6.0);
+    }
+
+    #[test]
+    fn test_statements() {
+        let tokens = generate_tokens("r = 3; area = 2 * r; area".to_string()).unwrap();
+        let statements = parse_statements(&tokens).unwrap();
+        assert_eq!(statements.len(), 3);
+        assert_eq!(statements[2], expr(ExprKind::Variable("area".to_string())));
+
+        let tokens = generate_tokens(";x = 1;; x;".to_string()).unwrap();
+        assert_eq!(parse_statements(&tokens).unwrap().len(), 2);
+
+        assert!(parse_statements(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_leftover_tokens() {
+        assert!(parse("(1 + 2) 3").is_err());
+        assert!(parse("x = 1 2").is_err());
+        assert!(parse("1 + 2)").is_err());
+
+        let tokens = generate_tokens("x = 1 y = 2".to_string()).unwrap();
+        assert!(parse_statements(&tokens).is_err());
+    }
+
+    #[test]
+    fn test_comparison_and_boolean_operators() {
+        assert_eq!(eval("1 + 1 == 2"), true);
+        assert_eq!(eval("2 * 3 < 5"), false);
+        assert_eq!(eval("3 <= 3 && 4 >= 5"), false);
+        assert_eq!(eval("1 > 2 || 2 != 3"), true);
+        assert_eq!(eval("0 || 1 && 0"), false);
+        assert_eq!(eval("!0 && !(1 > 2)"), true);
+        assert_eq!(eval("!2 == false"), true);
+    }
+
+    #[test]
+    fn test_conditional() {
+        assert_eq!(eval("1 < 2 ? 10 : 20"), 10.0);
+        assert_eq!(eval("1 > 2 ? 10 : 20 + 1"), 21.0);
+        assert_eq!(eval("0 ? 1 : 0 ? 2 : 3"), 3.0);
+        assert_eq!(eval("1 ? 0 ? 1 : 2 : 3"), 2.0);
+        assert_eq!(eval("2 * (1 ? 3 : 4)"), 6.0);
+        assert!(parse("1 ? 2").is_err());
+    }
+
+    #[test]
+    fn test_unit_conversion() {
+        let variable = |name: &str| Box::new(expr(ExprKind::Variable(name.to_string())));
+        let binary = |lhs, op, rhs| Box::new(expr(ExprKind::BinaryOp { lhs, op, rhs }));
+        let miles = Box::new(quantity(2.0, "mi"));
+        assert_eq!(
+            parse("2 mi + x to m").unwrap(),
+            *binary(
+                binary(miles.clone(), TokenKind::Plus, variable("x")),
+                TokenKind::To,
+                variable("m")
+            )
+        );
+
+        // The first `in` ends the binding, and any after it convert the body
+        assert_eq!(
+            parse("let d = 2 mi in d in m").unwrap(),
+            expr(ExprKind::Let {
+                bindings: vec![("d".to_string(), *miles.clone())],
+                body: binary(variable("d"), TokenKind::In, variable("m")),
+            })
+        );
+        assert_eq!(
+            parse("let d = (2 mi in m), e = let f = 1 in f in d").unwrap(),
+            parse("let d = (2 mi in m), e = (let f = 1 in f) in d").unwrap()
+        );
+
+        // An `in` that starts a unit after `to` or `in` is the inch
+        assert_eq!(
+            parse("x to in").unwrap(),
+            *binary(variable("x"), TokenKind::To, variable("in"))
+        );
+        assert_eq!(
+            parse("x in in/s").unwrap(),
+            *binary(
+                variable("x"),
+                TokenKind::In,
+                binary(variable("in"), TokenKind::Divide, variable("s"))
+            )
+        );
+        assert!(parse("x in").is_err());
+    }
+
+    #[test]
+    fn test_implicit_multiplication() {
+        assert_eq!(parse("2x").unwrap(), parse("2 * x").unwrap());
+        assert_eq!(parse("2 x").unwrap(), parse("2 * x").unwrap());
+        assert_eq!(parse("x y").unwrap(), parse("x * y").unwrap());
+        assert_eq!(parse("3(4+5)").unwrap(), parse("3 * (4+5)").unwrap());
+        assert_eq!(parse("(a)(b)").unwrap(), parse("(a) * (b)").unwrap());
+        assert_eq!(parse("2sin(x)").unwrap(), parse("2 * sin(x)").unwrap());
+        assert_eq!(
+            parse("sqrt(4)(2)").unwrap(),
+            parse("sqrt(4) * (2)").unwrap()
+        );
+        assert_eq!(eval("3(4+5)"), 27.0);
+        assert_eq!(eval("(1+1)(2+2)"), 8.0);
+        assert_eq!(eval("2PI"), eval("2 * PI"));
+        assert_eq!(eval("2e"), eval("2 * e"));
+        assert_eq!(eval("2e*3"), eval("6 * e"));
+    }
+
+    #[test]
+    fn test_implicit_multiplication_precedence() {
+        // Binds like an explicit `*`
+        assert_eq!(parse("2^3x").unwrap(), parse("(2^3) * x").unwrap());
+        assert_eq!(parse("2x^2").unwrap(), parse("2 * (x^2)").unwrap());
+        assert_eq!(parse("1/2x").unwrap(), parse("(1/2) * x").unwrap());
+        assert_eq!(parse("1+2x").unwrap(), parse("1 + (2 * x)").unwrap());
+        assert_eq!(parse("-2x").unwrap(), parse("(-2) * x").unwrap());
+
+        // A number followed by a unit binds tighter than `*` and `/`, but not `^`. Whether it is a
+        // quantity or an implicit multiplication is decided once the names are known
+        let binary = |lhs, op, rhs| {
+            expr(ExprKind::BinaryOp {
+                lhs: Box::new(lhs),
+                op,
+                rhs: Box::new(rhs),
+            })
+        };
+        assert_eq!(
+            parse("1/2h").unwrap(),
+            binary(number(1.0), TokenKind::Divide, quantity(2.0, "h"))
+        );
+        assert_eq!(
+            parse("100 km / 2 h").unwrap(),
+            binary(quantity(100.0, "km"), TokenKind::Divide, quantity(2.0, "h"))
+        );
+        assert_eq!(parse("9.81 m/s^2").unwrap(), quantity(9.81, "m/s^2"));
+        assert_eq!(parse("3 m^2").unwrap(), quantity(3.0, "m^2"));
+        assert_eq!(parse("2^3 m").unwrap(), parse("(2^3) * m").unwrap());
+        assert_eq!(parse("-2 m").unwrap(), parse("-(2 m)").unwrap());
+        assert_eq!(parse("1/2 h(3)").unwrap(), parse("(1/2) * h(3)").unwrap());
+        assert_eq!(
+            parse("2 m / s(3)").unwrap(),
+            binary(
+                quantity(2.0, "m"),
+                TokenKind::Divide,
+                call("s", vec![number(3.0)])
+            )
+        );
+
+        // An identifier followed by parentheses is always a call, never a multiplication
+        assert_eq!(parse("x(2)").unwrap(), call("x", vec![number(2.0)]));
+    }
+
+    #[test]
+    fn test_implicit_multiplication_rejects_numbers_on_the_right() {
+        assert!(parse("1 2").is_err());
+        assert!(parse("x 2").is_err());
+        assert!(parse("(1)2").is_err());
+        // Functions need parentheses, so `sin 2x` is not `sin(2x)`
+        assert!(parse("sin 2x").is_err());
+    }
+
+    #[test]
+    fn test_postfix_factorial() {
+        assert_eq!(eval("5!"), 120.0);
+        assert_eq!(eval("3!!"), 3.0);
+        assert_eq!(eval("8!!"), 384.0);
+        assert_eq!(eval("2^3!"), 64.0);
+        assert_eq!(eval("-3!"), -6.0);
+        assert_eq!(eval("3!^2"), 36.0);
+        assert_eq!(eval("(2+1)! + 1"), 7.0);
+        assert_eq!(eval("(3!)!"), 720.0);
+        assert_eq!(eval("!0!"), false);
+        assert_eq!(eval("!!2"), true);
+        // `!=` is always not equal, even straight after a number
+        assert_eq!(eval("5!=120"), true);
+    }
+
+    #[test]
+    fn test_percent() {
+        assert_eq!(eval("10%"), 0.1);
+        assert_eq!(eval("200 + 10%"), 220.0);
+        assert_eq!(eval("200 - 10%"), 180.0);
+        assert_eq!(eval("50 * 20%"), 10.0);
+        assert_eq!(eval("50 / 50%"), 100.0);
+        assert_eq!(eval("(200 + 10%) * 2"), 440.0);
+        assert_eq!(eval("10 % 4"), 2.0);
+        assert_eq!(eval("10%4"), 2.0);
+        assert_eq!(eval("10 % (3)"), 1.0);
+        assert_eq!(eval("10 % -3"), 1.0);
+        assert_eq!(eval("10 %-3"), 1.0);
+        assert_eq!(parse("10 % -x").unwrap(), parse("10 % (-x)").unwrap());
+        assert_eq!(eval("10% - 5"), 0.1 - 5.0);
+        assert_eq!(eval("200 + 10% - 5"), 215.0);
+    }
+
+    #[test]
+    fn test_unterminated_function_call() {
+        assert!(parse("sqrt(9").is_err());
+        assert!(parse("max(1, 2").is_err());
+        assert!(parse("max(1,").is_err());
+        assert!(parse("max(1 2)").is_err());
+    }
+
+    #[test]
+    fn test_lists() {
+        assert_eq!(parse("[]").unwrap(), expr(ExprKind::List(vec![])));
+        assert_eq!(
+            parse("[1, 2 + 3]").unwrap(),
+            expr(ExprKind::List(vec![number(1.0), parse("2 + 3").unwrap()]))
+        );
+        assert_eq!(parse("-xs[0]").unwrap(), parse("-(xs[0])").unwrap());
+        assert_eq!(parse("2^xs[0]").unwrap(), parse("2^(xs[0])").unwrap());
+        assert_eq!(parse("xs[0][1]").unwrap(), parse("(xs[0])[1]").unwrap());
+        assert!(matches!(
+            parse("xs[:2]").unwrap().kind,
+            ExprKind::Slice {
+                start: None,
+                end: Some(_),
+                ..
+            }
+        ));
+        assert!(matches!(
+            parse("xs[1:]").unwrap().kind,
+            ExprKind::Slice {
+                start: Some(_),
+                end: None,
+                ..
+            }
+        ));
+
+        assert!(parse("[1, 2").is_err());
+        assert!(parse("[1 2]").is_err());
+        assert!(parse("xs[]").is_err());
+        assert!(parse("xs[1").is_err());
+    }
+
+    fn error_span(equation: &str) -> Span {
+        parse(equation).unwrap_err().span().unwrap()
+    }
+
+    #[test]
+    fn test_expression_spans() {
+        let ast = parse("1 + sqrt(4) * 2").unwrap();
+        assert_eq!(ast.span, Span::new(0, 15));
+        match ast.kind {
+            ExprKind::BinaryOp { rhs, .. } => assert_eq!(rhs.span, Span::new(4, 15)),
+            kind => panic!("Expected a binary operation, got {:?}", kind),
+        }
+
+        let ast = parse("(1 + 2)!").unwrap();
+        assert_eq!(ast.span, Span::new(0, 8));
+    }
+
+    #[test]
+    fn test_error_spans() {
+        assert_eq!(error_span("1 + 2 3"), Span::new(6, 7));
+        assert_eq!(error_span("1 +"), Span::new(3, 3));
+        assert_eq!(error_span("(1 + 2"), Span::new(6, 6));
+        assert_eq!(error_span("max(1 2)"), Span::new(6, 7));
+        assert_eq!(error_span("f(x, x) = x"), Span::new(5, 6));
     }
 }
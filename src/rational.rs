@@ -0,0 +1,108 @@
+//! Exact fractions, used for numbers in exact mode.
+//!
+//! Numbers are stored as a [`BigRational`], a fraction of two integers that can be as large as
+//! they need to be, so `1/3 * 3` is exactly `1` and `0.1 + 0.2` is exactly `3/10`.
+
+use num_bigint::BigInt;
+use num_traits::{Signed, ToPrimitive, Zero};
+
+use crate::integer;
+
+pub use num_rational::BigRational;
+
+/// Converts a floating point number to the fraction it is written as, so `0.1` is `1/10` rather
+/// than the binary fraction closest to it. Returns `None` for infinity and `NaN`.
+///
+/// # Example
+/// ```
+/// # use argon::rational::{self, BigRational};
+/// let tenth = rational::from_f64(0.1).unwrap();
+/// assert_eq!(tenth, BigRational::new(1.into(), 10.into()));
+/// assert_eq!(rational::from_f64(f64::NAN), None);
+/// ```
+pub fn from_f64(n: f64) -> Option<BigRational> {
+    if !n.is_finite() {
+        return None;
+    }
+
+    // Formatting gives the shortest decimal that reads back as `n`, without an exponent
+    let decimal = n.to_string();
+    let (whole, fraction) = decimal.split_once('.').unwrap_or((&decimal, ""));
+    let numerator: BigInt = format!("{}{}", whole, fraction).parse().ok()?;
+    let denominator = BigInt::from(10).pow(fraction.len() as u32);
+    Some(BigRational::new(numerator, denominator))
+}
+
+/// Converts a fraction to the closest floating point number.
+pub fn to_f64(r: &BigRational) -> f64 {
+    r.to_f64().unwrap_or(f64::NAN)
+}
+
+/// Raises `base` to a whole `exponent` exactly. Returns `None` if the result can't be written as
+/// a fraction, because the exponent isn't whole or `base` is zero and the exponent is negative,
+/// or if the result would have more than [`integer::MAX_EXACT_BITS`] bits.
+pub fn pow(base: &BigRational, exponent: &BigRational) -> Option<BigRational> {
+    if !exponent.is_integer() {
+        return None;
+    }
+    let power = exponent.to_integer().abs();
+    let numerator = integer::pow(base.numer(), &power)?;
+    let denominator = integer::pow(base.denom(), &power)?;
+
+    let result = BigRational::new(numerator, denominator);
+    if exponent.is_negative() {
+        (!result.is_zero()).then(|| result.recip())
+    } else {
+        Some(result)
+    }
+}
+
+/// Returns the remainder of dividing `a` by `b`, which has the same sign as `a` like `%` on
+/// floating point numbers. Returns `None` if `b` is zero.
+pub fn remainder(a: &BigRational, b: &BigRational) -> Option<BigRational> {
+    (!b.is_zero()).then(|| a - (a / b).trunc() * b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fraction(numerator: i64, denominator: i64) -> BigRational {
+        BigRational::new(numerator.into(), denominator.into())
+    }
+
+    #[test]
+    fn test_from_f64() {
+        assert_eq!(from_f64(3.0), Some(fraction(3, 1)));
+        assert_eq!(from_f64(-2.5), Some(fraction(-5, 2)));
+        assert_eq!(from_f64(0.2), Some(fraction(1, 5)));
+        assert_eq!(from_f64(1e-7), Some(fraction(1, 10_000_000)));
+        assert_eq!(
+            from_f64(1e20).map(|r| r.to_integer().to_string()),
+            Some("100000000000000000000".to_string())
+        );
+        assert_eq!(from_f64(f64::INFINITY), None);
+    }
+
+    #[test]
+    fn test_pow() {
+        assert_eq!(pow(&fraction(2, 3), &fraction(3, 1)), Some(fraction(8, 27)));
+        assert_eq!(pow(&fraction(2, 3), &fraction(-2, 1)), Some(fraction(9, 4)));
+        assert_eq!(pow(&fraction(2, 1), &fraction(1, 2)), None);
+        assert_eq!(pow(&fraction(0, 1), &fraction(-1, 1)), None);
+        assert_eq!(pow(&fraction(2, 1), &fraction(1_000_000, 1)), None);
+    }
+
+    #[test]
+    fn test_remainder() {
+        assert_eq!(
+            remainder(&fraction(7, 2), &fraction(1, 1)),
+            Some(fraction(1, 2))
+        );
+        assert_eq!(
+            remainder(&fraction(-7, 2), &fraction(1, 1)),
+            Some(fraction(-1, 2))
+        );
+        assert_eq!(remainder(&fraction(1, 1), &fraction(0, 1)), None);
+    }
+}
@@ -0,0 +1,515 @@
+//! Units of measure, so equations can work with quantities such as `5 km + 300 m` or
+//! `60 mph to km/h`.
+//!
+//! A name after a number is looked up as a unit unless it is a variable, and so is every name after
+//! `to` and `in`. It evaluates to one of that unit, so `5 km` is `5 * km`. Every unit is defined by how many of the matching SI
+//! unit it is and by its [`Dimension`], which is what lets `3 m + 2 s` be rejected. SI units can
+//! be written with a prefix, such as `km`, `ms` or `MHz`.
+
+use std::fmt;
+
+use crate::value::Value;
+
+/// The powers of the SI base units a unit is made of: length, mass, time, electric current,
+/// temperature, amount of substance and luminous intensity. A speed is a length divided by a
+/// time, so its dimension is `[1, 0, -1, 0, 0, 0, 0]`.
+pub type Dimension = [i32; 7];
+
+const LENGTH: Dimension = [1, 0, 0, 0, 0, 0, 0];
+const MASS: Dimension = [0, 1, 0, 0, 0, 0, 0];
+const TIME: Dimension = [0, 0, 1, 0, 0, 0, 0];
+const CURRENT: Dimension = [0, 0, 0, 1, 0, 0, 0];
+const TEMPERATURE: Dimension = [0, 0, 0, 0, 1, 0, 0];
+const AMOUNT: Dimension = [0, 0, 0, 0, 0, 1, 0];
+const LUMINOSITY: Dimension = [0, 0, 0, 0, 0, 0, 1];
+const AREA: Dimension = [2, 0, 0, 0, 0, 0, 0];
+const VOLUME: Dimension = [3, 0, 0, 0, 0, 0, 0];
+const SPEED: Dimension = [1, 0, -1, 0, 0, 0, 0];
+const FREQUENCY: Dimension = [0, 0, -1, 0, 0, 0, 0];
+const FORCE: Dimension = [1, 1, -2, 0, 0, 0, 0];
+const ENERGY: Dimension = [2, 1, -2, 0, 0, 0, 0];
+const POWER: Dimension = [2, 1, -3, 0, 0, 0, 0];
+const PRESSURE: Dimension = [-1, 1, -2, 0, 0, 0, 0];
+const CHARGE: Dimension = [0, 0, 1, 1, 0, 0, 0];
+const VOLTAGE: Dimension = [2, 1, -3, -1, 0, 0, 0];
+const RESISTANCE: Dimension = [2, 1, -3, -2, 0, 0, 0];
+
+/// The significant digits [`Quantity::convert`] rounds to. Conversion factors such as `5/9` can't
+/// be held exactly, which only affects the last digit or two of a floating point number.
+pub const CONVERSION_DIGITS: usize = 15;
+
+/// An entry in the unit table.
+#[derive(Debug, PartialEq)]
+struct Definition {
+    /// The names the unit can be written as. Only the first, its symbol, can have a prefix.
+    names: &'static [&'static str],
+    /// How many of the matching SI unit one of this unit is.
+    factor: f64,
+    /// The SI value of zero in this unit, which is only non-zero for temperature scales such as
+    /// Celsius.
+    offset: f64,
+    dimension: Dimension,
+    prefixable: bool,
+}
+
+/// Defines a unit that can't be written with a prefix.
+const fn unit(names: &'static [&'static str], factor: f64, dimension: Dimension) -> Definition {
+    Definition {
+        names,
+        factor,
+        offset: 0.0,
+        dimension,
+        prefixable: false,
+    }
+}
+
+/// Defines a unit whose symbol can be written with a prefix, such as `km` or `mA`.
+const fn prefixed(names: &'static [&'static str], factor: f64, dimension: Dimension) -> Definition {
+    Definition {
+        prefixable: true,
+        ..unit(names, factor, dimension)
+    }
+}
+
+/// Units understood by the calculator. To add a new unit, register it here.
+///
+/// `min` is also a function and `in` converts between units, so those two names are only minutes
+/// and inches after a number or after `to` and `in`. Everywhere else, write `minute` and `inch`.
+const UNITS: &[Definition] = &[
+    prefixed(&["m", "metre", "metres", "meter", "meters"], 1.0, LENGTH),
+    unit(&["in", "inch", "inches"], 0.0254, LENGTH),
+    unit(&["ft", "foot", "feet"], 0.3048, LENGTH),
+    unit(&["yd", "yard", "yards"], 0.9144, LENGTH),
+    unit(&["mi", "mile", "miles"], 1609.344, LENGTH),
+    prefixed(&["g", "gram", "grams"], 1e-3, MASS),
+    unit(&["tonne", "tonnes"], 1e3, MASS),
+    unit(&["lb", "lbs", "pound", "pounds"], 0.45359237, MASS),
+    unit(&["oz", "ounce", "ounces"], 0.028349523125, MASS),
+    prefixed(&["s", "sec", "second", "seconds"], 1.0, TIME),
+    unit(&["min", "minute", "minutes"], 60.0, TIME),
+    unit(&["h", "hr", "hour", "hours"], 3600.0, TIME),
+    unit(&["day", "days"], 86400.0, TIME),
+    unit(&["week", "weeks"], 604800.0, TIME),
+    unit(&["yr", "year", "years"], 31557600.0, TIME),
+    prefixed(&["A", "amp", "amps", "ampere", "amperes"], 1.0, CURRENT),
+    prefixed(&["K", "kelvin"], 1.0, TEMPERATURE),
+    Definition {
+        offset: 273.15,
+        ..unit(&["degC", "°C", "celsius"], 1.0, TEMPERATURE)
+    },
+    Definition {
+        offset: 273.15 - 32.0 * 5.0 / 9.0,
+        ..unit(&["degF", "°F", "fahrenheit"], 5.0 / 9.0, TEMPERATURE)
+    },
+    prefixed(&["mol", "mole", "moles"], 1.0, AMOUNT),
+    prefixed(&["cd", "candela"], 1.0, LUMINOSITY),
+    unit(&["acre", "acres"], 4046.8564224, AREA),
+    unit(&["ha", "hectare", "hectares"], 1e4, AREA),
+    prefixed(&["L", "litre", "litres", "liter", "liters"], 1e-3, VOLUME),
+    unit(&["gal", "gallon", "gallons"], 3.785411784e-3, VOLUME),
+    unit(&["mph"], 0.44704, SPEED),
+    unit(&["kph"], 1.0 / 3.6, SPEED),
+    unit(&["kn", "knot", "knots"], 1852.0 / 3600.0, SPEED),
+    prefixed(&["Hz", "hertz"], 1.0, FREQUENCY),
+    prefixed(&["N", "newton", "newtons"], 1.0, FORCE),
+    prefixed(&["J", "joule", "joules"], 1.0, ENERGY),
+    prefixed(&["Wh"], 3600.0, ENERGY),
+    prefixed(&["cal", "calorie", "calories"], 4.184, ENERGY),
+    prefixed(&["eV"], 1.602176634e-19, ENERGY),
+    prefixed(&["W", "watt", "watts"], 1.0, POWER),
+    prefixed(&["Pa", "pascal", "pascals"], 1.0, PRESSURE),
+    prefixed(&["bar"], 1e5, PRESSURE),
+    unit(&["atm"], 101325.0, PRESSURE),
+    unit(&["psi"], 6894.757293168361, PRESSURE),
+    prefixed(&["C", "coulomb", "coulombs"], 1.0, CHARGE),
+    prefixed(&["V", "volt", "volts"], 1.0, VOLTAGE),
+    prefixed(&["ohm", "ohms", "Ω"], 1.0, RESISTANCE),
+];
+
+/// SI prefixes that can be written before the symbol of a unit, such as the `k` in `km`.
+const PREFIXES: &[(&str, f64)] = &[
+    ("Y", 1e24),
+    ("Z", 1e21),
+    ("E", 1e18),
+    ("P", 1e15),
+    ("T", 1e12),
+    ("G", 1e9),
+    ("M", 1e6),
+    ("k", 1e3),
+    ("h", 1e2),
+    ("da", 1e1),
+    ("d", 1e-1),
+    ("c", 1e-2),
+    ("m", 1e-3),
+    ("u", 1e-6),
+    ("µ", 1e-6),
+    ("μ", 1e-6),
+    ("n", 1e-9),
+    ("p", 1e-12),
+    ("f", 1e-15),
+    ("a", 1e-18),
+];
+
+/// A named unit raised to a whole power, one part of a [`Unit`].
+#[derive(Debug, Clone, PartialEq)]
+struct Term {
+    /// The unit as it was written, such as `km`.
+    symbol: String,
+    power: i32,
+    /// How many of the matching SI unit one of this unit is, including its prefix.
+    factor: f64,
+    definition: &'static Definition,
+}
+
+impl Term {
+    /// Looks up a unit by name, trying the unit table before prefixes so `mi` is a mile rather
+    /// than a milli-something.
+    fn parse(symbol: &str) -> Option<Term> {
+        let term = |factor: f64, definition| Term {
+            symbol: symbol.to_string(),
+            power: 1,
+            factor,
+            definition,
+        };
+        if let Some(definition) = UNITS.iter().find(|unit| unit.names.contains(&symbol)) {
+            return Some(term(definition.factor, definition));
+        }
+
+        PREFIXES.iter().find_map(|(prefix, scale)| {
+            let rest = symbol.strip_prefix(prefix)?;
+            let definition = UNITS
+                .iter()
+                .find(|unit| unit.prefixable && unit.names[0] == rest)?;
+            Some(term(scale * definition.factor, definition))
+        })
+    }
+
+    /// Returns true if both terms measure the same kind of thing, such as `km` and `ft`.
+    fn same_kind(&self, other: &Term) -> bool {
+        self.definition.dimension == other.definition.dimension
+    }
+}
+
+impl fmt::Display for Term {
+    /// Formats the term with its power, which is left out when it is one.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.power {
+            1 => write!(f, "{}", self.symbol),
+            power => write!(f, "{}^{}", self.symbol, power),
+        }
+    }
+}
+
+/// The unit a quantity is measured in, such as `km` or `m/s^2`. It is a product of named units
+/// raised to whole powers, and has no terms for a plain number.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Unit {
+    terms: Vec<Term>,
+}
+
+impl Unit {
+    /// Looks up a unit by name, such as `mile`, `kWh` or `°C`. Returns `None` if there is no unit
+    /// called `name`.
+    ///
+    /// # Example
+    /// ```
+    /// # use argon::units::Unit;
+    /// assert_eq!(Unit::parse("km").unwrap().factor(), 1000.0);
+    /// assert_eq!(Unit::parse("speed"), None);
+    /// ```
+    pub fn parse(name: &str) -> Option<Unit> {
+        Term::parse(name).map(|term| Unit { terms: vec![term] })
+    }
+
+    /// Returns the powers of the SI base units the unit is made of.
+    pub fn dimension(&self) -> Dimension {
+        let mut dimension = [0; 7];
+        for term in &self.terms {
+            for (total, base) in dimension.iter_mut().zip(term.definition.dimension) {
+                *total += base * term.power;
+            }
+        }
+        dimension
+    }
+
+    /// Returns how many of the matching SI unit one of this unit is, so `km/h` is `1 / 3.6`.
+    pub fn factor(&self) -> f64 {
+        self.terms
+            .iter()
+            .map(|term| term.factor.powi(term.power))
+            .product()
+    }
+
+    /// Returns true if the unit is a plain number, because every unit in it cancelled out.
+    pub fn is_dimensionless(&self) -> bool {
+        self.dimension() == [0; 7]
+    }
+
+    /// Returns the SI value of zero in this unit. Only a temperature scale on its own, such as
+    /// `degC`, has one, since the zero of `degC/s` isn't offset.
+    fn offset(&self) -> f64 {
+        match self.terms.as_slice() {
+            [term] if term.power == 1 => term.definition.offset,
+            _ => 0.0,
+        }
+    }
+}
+
+impl fmt::Display for Unit {
+    /// Formats the unit with the terms that are divided by after a `/`, such as `kg*m/s^2`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let join = |terms: Vec<Term>| {
+            terms
+                .iter()
+                .map(Term::to_string)
+                .collect::<Vec<_>>()
+                .join("*")
+        };
+        let (numerator, denominator): (Vec<Term>, Vec<Term>) =
+            self.terms.iter().cloned().partition(|term| term.power > 0);
+        if numerator.is_empty() {
+            return write!(f, "{}", join(denominator));
+        }
+
+        write!(f, "{}", join(numerator))?;
+        let denominator: Vec<Term> = denominator
+            .into_iter()
+            .map(|term| Term {
+                power: -term.power,
+                ..term
+            })
+            .collect();
+        match denominator.len() {
+            0 => Ok(()),
+            1 => write!(f, "/{}", join(denominator)),
+            _ => write!(f, "/({})", join(denominator)),
+        }
+    }
+}
+
+/// A number measured in a unit, such as `5.3 km`.
+///
+/// # Example
+/// ```
+/// # use argon::units::{Quantity, Unit};
+/// let speed = Quantity::new(60.0, Unit::parse("mph").unwrap());
+/// let hour = Quantity::new(1.0, Unit::parse("h").unwrap());
+/// let distance = speed.mul(&hour).convert(&Unit::parse("mi").unwrap()).unwrap();
+/// assert_eq!(distance.to_string(), "60 mi");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quantity {
+    pub value: f64,
+    pub unit: Unit,
+}
+
+impl Quantity {
+    pub fn new(value: f64, unit: Unit) -> Self {
+        Quantity { value, unit }
+    }
+
+    /// Multiplies two quantities, combining their units. A unit that measures the same kind of
+    /// thing as one already in the product is converted to it, so `km * m` is in `km^2` and
+    /// `km / m` cancels out to a plain number.
+    pub fn mul(&self, other: &Quantity) -> Quantity {
+        let mut value = self.value * other.value;
+        let mut terms = self.unit.terms.clone();
+        for term in &other.unit.terms {
+            let existing = terms
+                .iter()
+                .position(|t| t.symbol == term.symbol)
+                .or_else(|| terms.iter().position(|t| t.same_kind(term)));
+            match existing {
+                Some(i) => {
+                    value *= (term.factor / terms[i].factor).powi(term.power);
+                    terms[i].power += term.power;
+                }
+                None => terms.push(term.clone()),
+            }
+        }
+        terms.retain(|term| term.power != 0);
+        Quantity::new(value, Unit { terms })
+    }
+
+    /// Returns one divided by the quantity, which is in one divided by its unit.
+    pub fn recip(&self) -> Quantity {
+        let terms = self
+            .unit
+            .terms
+            .iter()
+            .map(|term| Term {
+                power: -term.power,
+                ..term.clone()
+            })
+            .collect();
+        Quantity::new(1.0 / self.value, Unit { terms })
+    }
+
+    /// Raises the quantity to a power, which raises its unit to the same power. Returns `None` if
+    /// the unit would have a power that isn't whole, such as `m^0.5`.
+    pub fn pow(&self, exponent: f64) -> Option<Quantity> {
+        let mut terms = Vec::new();
+        for term in &self.unit.terms {
+            let power = term.power as f64 * exponent;
+            if power.fract() != 0.0 || power.abs() > i32::MAX as f64 {
+                return None;
+            }
+            terms.push(Term {
+                power: power as i32,
+                ..term.clone()
+            });
+        }
+        terms.retain(|term| term.power != 0);
+        Some(Quantity::new(self.value.powf(exponent), Unit { terms }))
+    }
+
+    /// Converts the quantity to another unit that measures the same kind of thing, taking the
+    /// zero of temperature scales into account, so `0 degC` is `32 degF`. Returns `None` if the
+    /// units measure different kinds of things.
+    ///
+    /// The result is rounded to [`CONVERSION_DIGITS`] significant digits, so `20 degC` is `68 degF`
+    /// rather than `67.99999999999999 degF`.
+    pub fn convert(&self, unit: &Unit) -> Option<Quantity> {
+        if self.unit.dimension() != unit.dimension() {
+            return None;
+        }
+        let si = self.value * self.unit.factor() + self.unit.offset();
+        let value = (si - unit.offset()) / unit.factor();
+        Some(Quantity::new(
+            round_to_digits(value, CONVERSION_DIGITS as i32),
+            unit.clone(),
+        ))
+    }
+
+    /// Converts a difference between two quantities to another unit, which ignores the zero of
+    /// temperature scales, so a rise of `9 degF` is a rise of `5 degC`. Returns `None` if the
+    /// units measure different kinds of things.
+    pub fn convert_interval(&self, unit: &Unit) -> Option<Quantity> {
+        if self.unit.dimension() != unit.dimension() {
+            return None;
+        }
+        let value = self.value * self.unit.factor() / unit.factor();
+        Some(Quantity::new(value, unit.clone()))
+    }
+}
+
+/// The largest power of ten a floating point number holds exactly.
+const MAX_EXACT_POWER_OF_TEN: i32 = 22;
+
+/// Rounds `value` to `digits` significant digits. Scaling by a power of ten that isn't exact would
+/// add an error of its own, so values that would need one are returned as they are.
+fn round_to_digits(value: f64, digits: i32) -> f64 {
+    if value == 0.0 || !value.is_finite() {
+        return value;
+    }
+    let shift = digits - 1 - value.abs().log10().floor() as i32;
+    if shift.abs() > MAX_EXACT_POWER_OF_TEN {
+        return value;
+    }
+    let scale = 10f64.powi(shift.abs());
+    if shift >= 0 {
+        (value * scale).round() / scale
+    } else {
+        (value / scale).round() * scale
+    }
+}
+
+impl From<f64> for Quantity {
+    /// Converts a number to a quantity without a unit.
+    fn from(n: f64) -> Self {
+        Quantity::new(n, Unit::default())
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.unit.terms.is_empty() {
+            write!(f, "{}", self.value)
+        } else {
+            write!(f, "{} {}", self.value, self.unit)
+        }
+    }
+}
+
+impl From<Quantity> for Value {
+    /// Converts a quantity to a value, which is a `Number` if its units cancelled out, such as
+    /// `5 km / 300 m`.
+    fn from(q: Quantity) -> Self {
+        if q.unit.is_dimensionless() {
+            Value::Number(q.value * q.unit.factor())
+        } else {
+            Value::Quantity(Box::new(q))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quantity(value: f64, unit: &str) -> Quantity {
+        Quantity::new(value, Unit::parse(unit).unwrap())
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Unit::parse("feet").unwrap().factor(), 0.3048);
+        assert_eq!(Unit::parse("kg").unwrap().factor(), 1.0);
+        assert_eq!(Unit::parse("µs").unwrap().factor(), 1e-6);
+        assert_eq!(Unit::parse("kWh").unwrap().factor(), 3.6e6);
+        assert_eq!(Unit::parse("mi").unwrap().factor(), 1609.344);
+        assert_eq!(Unit::parse("N").unwrap().dimension(), FORCE);
+        assert_eq!(Unit::parse("kmph"), None);
+        assert_eq!(Unit::parse("kfeet"), None);
+        assert_eq!(Unit::parse("x"), None);
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let speed = quantity(9.81, "m").mul(&quantity(1.0, "s").pow(-2.0).unwrap());
+        assert_eq!(speed.to_string(), "9.81 m/s^2");
+        let area = quantity(5.0, "km").mul(&quantity(300.0, "m"));
+        assert_eq!(area.to_string(), "1.5 km^2");
+        assert_eq!(quantity(2.0, "s").recip().to_string(), "0.5 s^-1");
+        assert_eq!(quantity(4.0, "m").pow(0.5), None);
+        assert_eq!(area.pow(0.5).unwrap().unit, Unit::parse("km").unwrap());
+
+        let ratio = quantity(5.0, "km").mul(&quantity(300.0, "m").recip());
+        assert!(ratio.unit.terms.is_empty());
+        assert_eq!(Value::from(ratio), Value::Number(5.0 / 0.3));
+        assert_eq!(
+            Value::from(quantity(1.0, "Hz").mul(&quantity(2.0, "s"))),
+            2.0
+        );
+    }
+
+    #[test]
+    fn test_convert() {
+        let to = |q: Quantity, unit: &str| q.convert(&Unit::parse(unit).unwrap()).unwrap().value;
+        assert_eq!(to(quantity(60.0, "mph"), "kph"), 96.56064);
+        assert_eq!(to(quantity(20.0, "degC"), "degF"), 68.0);
+        assert_eq!(to(quantity(-40.0, "°F"), "celsius"), -40.0);
+        assert_eq!(to(quantity(300.0, "K"), "degC"), 26.85);
+        assert_eq!(to(quantity(1.0, "ft"), "in"), 12.0);
+        assert_eq!(to(quantity(2.5e-7, "km"), "mm"), 0.25);
+        assert_eq!(to(quantity(1e300, "km"), "m"), 1e303);
+        assert_eq!(to(quantity(5e-324, "m"), "m"), 5e-324);
+        assert!(quantity(1.0, "m")
+            .convert(&Unit::parse("s").unwrap())
+            .is_none());
+
+        let rise = quantity(9.0, "degF").convert_interval(&Unit::parse("degC").unwrap());
+        assert!((rise.unwrap().value - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_display() {
+        let newton = quantity(1.0, "kg")
+            .mul(&quantity(1.0, "m"))
+            .mul(&quantity(1.0, "s").pow(-2.0).unwrap());
+        assert_eq!(newton.to_string(), "1 kg*m/s^2");
+        let pressure = newton.mul(&quantity(1.0, "m").pow(-2.0).unwrap());
+        assert_eq!(pressure.to_string(), "1 kg/(m*s^2)");
+        assert_eq!(Quantity::from(2.5).to_string(), "2.5");
+    }
+}
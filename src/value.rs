@@ -0,0 +1,451 @@
+use std::fmt;
+
+use num_complex::Complex64;
+use num_traits::Zero;
+
+use crate::decimal::Decimal;
+use crate::integer::{self, BigInt};
+use crate::matrix::Matrix;
+use crate::rational::{self, BigRational};
+use crate::units::Quantity;
+
+/// A value that an expression can evaluate to.
+///
+/// `Function` holds the name of a function, so `f = sqrt` stores `sqrt` in `f` and `f(9)` calls it.
+///
+/// `Complex` only holds numbers with a non-zero imaginary part. Arithmetic that cancels out the
+/// imaginary part, such as `i * i`, gives a `Number`.
+///
+/// `Integer` only holds whole numbers too large for a `Number` to hold exactly, such as `2^512`.
+/// Smaller whole numbers are always a `Number`.
+///
+/// `Rational` is an exact fraction, which numbers are in exact mode. `Decimal` is a decimal with
+/// a chosen number of significant digits, which numbers are when a precision is set. Both are
+/// boxed so the other values stay small.
+///
+/// `Quantity` is a number measured in a unit, such as `5 km`. A quantity whose units cancel out
+/// is a `Number` instead.
+///
+/// A list of lists of numbers that all have the same length is a `Matrix` instead of a `List`.
+///
+/// `None` is the result of statements that don't produce anything, such as a function
+/// declaration.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Integer(Box<BigInt>),
+    Rational(Box<BigRational>),
+    Decimal(Box<Decimal>),
+    Complex(Complex64),
+    Quantity(Box<Quantity>),
+    Bool(bool),
+    String(String),
+    List(Vec<Value>),
+    Matrix(Matrix),
+    Function(String),
+    None,
+}
+
+impl Value {
+    /// Returns the name of the value's type, as used in error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Number(_) | Value::Integer(_) | Value::Rational(_) | Value::Decimal(_) => {
+                "number"
+            }
+            Value::Complex(_) => "complex",
+            Value::Quantity(_) => "quantity",
+            Value::Bool(_) => "bool",
+            Value::String(_) => "string",
+            Value::List(_) => "list",
+            Value::Matrix(_) => "matrix",
+            Value::Function(_) => "function",
+            Value::None => "none",
+        }
+    }
+
+    /// Returns the number this value holds, or `None` if it isn't a number. Large integers, exact
+    /// fractions and decimals are converted to the closest floating point number.
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            Value::Integer(n) => Some(integer::to_f64(n)),
+            Value::Rational(r) => Some(rational::to_f64(r)),
+            Value::Decimal(d) => Some(d.to_f64()),
+            _ => None,
+        }
+    }
+
+    /// Returns the complex number this value holds, treating real numbers as complex numbers with
+    /// no imaginary part. Returns `None` if the value isn't a number.
+    pub fn as_complex(&self) -> Option<Complex64> {
+        match self {
+            Value::Number(_) | Value::Integer(_) | Value::Rational(_) | Value::Decimal(_) => {
+                self.as_number().map(Complex64::from)
+            }
+            Value::Complex(c) => Some(*c),
+            _ => None,
+        }
+    }
+
+    /// Converts a number to an exact fraction, leaving infinity, `NaN` and anything that isn't a
+    /// real number as it is.
+    pub fn into_exact(self) -> Value {
+        match self {
+            Value::Number(n) => rational::from_f64(n).map_or(self, Value::from),
+            Value::Integer(n) => BigRational::from_integer(*n).into(),
+            value => value,
+        }
+    }
+
+    /// Converts a number to a decimal with `precision` significant digits. Returns `None` for
+    /// floating point numbers that aren't whole, which are only as precise as a floating point
+    /// number, and for anything that isn't a real number.
+    pub fn to_decimal(&self, precision: usize) -> Option<Decimal> {
+        match self {
+            Value::Number(n) => integer::from_f64(*n).map(|n| Decimal::from_integer(n, precision)),
+            Value::Integer(n) => Some(Decimal::from_integer((**n).clone(), precision)),
+            Value::Rational(r) => Some(Decimal::from_rational(r, precision)),
+            Value::Decimal(d) => Some(d.with_precision(precision)),
+            _ => None,
+        }
+    }
+
+    /// Returns an object that formats the value with complex numbers and fractions written in
+    /// `format`, for use with `println!` and `to_string`.
+    ///
+    /// # Example
+    /// ```
+    /// # use argon::value::{ComplexFormat, Format};
+    /// # use argon::{Complex64, Value};
+    /// let value = Value::from(Complex64::new(0.0, 2.0));
+    /// let polar = Format {
+    ///     complex: ComplexFormat::Polar,
+    ///     ..Format::default()
+    /// };
+    /// assert_eq!(value.display(Format::default()).to_string(), "2i");
+    /// assert_eq!(value.display(polar).to_string(), "2 ∠ 1.5707963267948966");
+    /// ```
+    pub fn display(&self, format: Format) -> Display<'_> {
+        Display {
+            value: self,
+            format,
+        }
+    }
+
+    /// Returns true if the value counts as true in a condition. Zero, `false`, `none` and empty
+    /// strings and lists are false, and everything else is true.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Number(n) => *n != 0.0,
+            Value::Integer(_) => true,
+            Value::Rational(r) => !r.is_zero(),
+            Value::Decimal(d) => !d.is_zero(),
+            Value::Complex(c) => *c != Complex64::from(0.0),
+            Value::Quantity(q) => q.value != 0.0,
+            Value::Bool(b) => *b,
+            Value::String(s) => !s.is_empty(),
+            Value::List(items) => !items.is_empty(),
+            Value::Matrix(_) => true,
+            Value::Function(_) => true,
+            Value::None => false,
+        }
+    }
+}
+
+/// How complex numbers are written when a value is printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ComplexFormat {
+    /// The real and imaginary parts, such as `3 + 4i`.
+    #[default]
+    Rectangular,
+    /// The magnitude and the angle in radians, such as `5 ∠ 0.9272952180016122`.
+    Polar,
+}
+
+impl ComplexFormat {
+    /// Returns the format called `name`, if there is one.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "rectangular" => Some(ComplexFormat::Rectangular),
+            "polar" => Some(ComplexFormat::Polar),
+            _ => None,
+        }
+    }
+}
+
+/// How exact fractions are written when a value is printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RationalFormat {
+    /// A numerator and denominator, such as `1/3`.
+    #[default]
+    Fraction,
+    /// The closest floating point number, such as `0.3333333333333333`.
+    Decimal,
+}
+
+impl RationalFormat {
+    /// Returns the format called `name`, if there is one.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "fraction" => Some(RationalFormat::Fraction),
+            "decimal" => Some(RationalFormat::Decimal),
+            _ => None,
+        }
+    }
+}
+
+/// How values are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Format {
+    pub complex: ComplexFormat,
+    pub rational: RationalFormat,
+    /// The most digits a whole number is printed with, or `None` to print every digit. Longer
+    /// numbers are rounded and printed in scientific notation.
+    pub max_digits: Option<usize>,
+}
+
+impl Format {
+    /// Returns this format with the setting called `name` changed, such as `polar` or `decimal`.
+    /// Returns `None` if there is no setting called `name`.
+    pub fn with_setting(self, name: &str) -> Option<Self> {
+        if let Some(complex) = ComplexFormat::from_name(name) {
+            Some(Format { complex, ..self })
+        } else {
+            RationalFormat::from_name(name).map(|rational| Format { rational, ..self })
+        }
+    }
+}
+
+/// Formats a value with a chosen [`Format`]. Created by [`Value::display`].
+pub struct Display<'a> {
+    value: &'a Value,
+    format: Format,
+}
+
+impl fmt::Display for Display<'_> {
+    /// Formats the value the way the REPL prints it. Strings are printed as they are, except
+    /// inside a list where they are quoted.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value {
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Integer(n) => write!(f, "{}", integer::format(n, self.format.max_digits)),
+            Value::Rational(r) => match self.format.rational {
+                RationalFormat::Fraction if r.is_integer() => {
+                    write!(f, "{}", integer::format(r.numer(), self.format.max_digits))
+                }
+                RationalFormat::Fraction => write!(f, "{}", r),
+                RationalFormat::Decimal => write!(f, "{}", rational::to_f64(r)),
+            },
+            Value::Decimal(d) => write!(f, "{}", d),
+            Value::Complex(c) => match self.format.complex {
+                ComplexFormat::Rectangular => write_rectangular(f, *c),
+                ComplexFormat::Polar => write!(f, "{} ∠ {}", c.norm(), c.arg()),
+            },
+            Value::Quantity(q) => write!(f, "{}", q),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::String(s) => write!(f, "{}", s),
+            Value::List(items) => {
+                write!(f, "[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    match item {
+                        Value::String(s) => write!(f, "{:?}", s)?,
+                        item => write!(f, "{}", item.display(self.format))?,
+                    }
+                }
+                write!(f, "]")
+            }
+            Value::Matrix(matrix) => write!(f, "{}", matrix),
+            Value::Function(name) => write!(f, "<function {}>", name),
+            Value::None => write!(f, "none"),
+        }
+    }
+}
+
+/// Writes a complex number as its real and imaginary parts, leaving out a real part of zero and
+/// an imaginary coefficient of one, so `i` is written `i` rather than `0 + 1i`.
+fn write_rectangular(f: &mut fmt::Formatter<'_>, c: Complex64) -> fmt::Result {
+    let coefficient = if c.im.abs() == 1.0 {
+        String::new()
+    } else {
+        c.im.abs().to_string()
+    };
+    let sign = if c.im.is_sign_negative() { "-" } else { "+" };
+    if c.re == 0.0 {
+        let sign = if sign == "-" { "-" } else { "" };
+        write!(f, "{}{}i", sign, coefficient)
+    } else {
+        write!(f, "{} {} {}i", c.re, sign, coefficient)
+    }
+}
+
+impl fmt::Display for Value {
+    /// Formats the value with complex numbers in rectangular form and exact numbers as fractions.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.display(Format::default()))
+    }
+}
+
+impl From<f64> for Value {
+    fn from(n: f64) -> Self {
+        Value::Number(n)
+    }
+}
+
+impl From<Complex64> for Value {
+    /// Converts a complex number to a value, which is a `Number` if it has no imaginary part.
+    fn from(c: Complex64) -> Self {
+        if c.im == 0.0 {
+            Value::Number(c.re)
+        } else {
+            Value::Complex(c)
+        }
+    }
+}
+
+impl From<BigInt> for Value {
+    /// Converts an integer to a value, which is a `Number` if it is small enough for a `Number` to
+    /// hold exactly.
+    fn from(n: BigInt) -> Self {
+        if integer::is_big(&n) {
+            Value::Integer(Box::new(n))
+        } else {
+            Value::Number(integer::to_f64(&n))
+        }
+    }
+}
+
+impl From<BigRational> for Value {
+    fn from(r: BigRational) -> Self {
+        Value::Rational(Box::new(r))
+    }
+}
+
+impl From<Decimal> for Value {
+    fn from(d: Decimal) -> Self {
+        Value::Decimal(Box::new(d))
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Bool(b)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(items: Vec<Value>) -> Self {
+        Value::List(items)
+    }
+}
+
+impl From<Matrix> for Value {
+    fn from(matrix: Matrix) -> Self {
+        Value::Matrix(matrix)
+    }
+}
+
+impl PartialEq<f64> for Value {
+    fn eq(&self, other: &f64) -> bool {
+        self.as_number() == Some(*other)
+    }
+}
+
+impl PartialEq<bool> for Value {
+    fn eq(&self, other: &bool) -> bool {
+        *self == Value::Bool(*other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Value::Number(2.5).to_string(), "2.5");
+        assert_eq!(Value::Bool(true).to_string(), "true");
+        assert_eq!(Value::from("hi").to_string(), "hi");
+        assert_eq!(
+            Value::List(vec![1.0.into(), "a".into(), Value::List(vec![])]).to_string(),
+            "[1, \"a\", []]"
+        );
+        assert_eq!(
+            Value::Function("sqrt".to_string()).to_string(),
+            "<function sqrt>"
+        );
+        assert_eq!(Value::None.to_string(), "none");
+    }
+
+    #[test]
+    fn test_complex() {
+        assert_eq!(Value::from(Complex64::new(2.0, 0.0)), Value::Number(2.0));
+
+        let display = |re: f64, im: f64| Value::from(Complex64::new(re, im)).to_string();
+        assert_eq!(display(3.0, 4.0), "3 + 4i");
+        assert_eq!(display(3.0, -0.5), "3 - 0.5i");
+        assert_eq!(display(0.0, 1.0), "i");
+        assert_eq!(display(0.0, -2.0), "-2i");
+        assert_eq!(display(-1.0, -1.0), "-1 - i");
+
+        let polar = Value::List(vec![Complex64::new(3.0, 4.0).into(), 1.0.into()]);
+        let format = Format::default().with_setting("polar").unwrap();
+        assert_eq!(
+            polar.display(format).to_string(),
+            "[5 ∠ 0.9272952180016122, 1]"
+        );
+    }
+
+    #[test]
+    fn test_rational() {
+        let third = Value::from(BigRational::new(1.into(), 3.into()));
+        assert_eq!(third.type_name(), "number");
+        assert_eq!(third.to_string(), "1/3");
+        assert_eq!(
+            Value::from(BigRational::from_integer(4.into())).to_string(),
+            "4"
+        );
+
+        let decimal = Format::default().with_setting("decimal").unwrap();
+        assert_eq!(third.display(decimal).to_string(), "0.3333333333333333");
+        assert_eq!(Format::default().with_setting("exact"), None);
+    }
+
+    #[test]
+    fn test_integer() {
+        assert_eq!(Value::from(BigInt::from(12)), Value::Number(12.0));
+
+        let big = Value::from(BigInt::from(10).pow(20));
+        assert_eq!(big.type_name(), "number");
+        assert_eq!(big.to_string(), "100000000000000000000");
+        let format = Format {
+            max_digits: Some(5),
+            ..Format::default()
+        };
+        assert_eq!(big.display(format).to_string(), "1e20");
+    }
+
+    #[test]
+    fn test_truthiness() {
+        assert!(Value::Number(-1.0).is_truthy());
+        assert!(!Value::Number(0.0).is_truthy());
+        assert!(!Value::from("").is_truthy());
+        assert!(Value::List(vec![Value::None]).is_truthy());
+        assert!(!Value::None.is_truthy());
+    }
+}